@@ -0,0 +1,90 @@
+//! Crash-safe file writes. Every file the helper modifies goes through here.
+//!
+//! Data is written to a temp file next to the target, flushed to disk and then renamed over the
+//! original, so a crash or power cut leaves either the old file or the new one, never a truncated mix.
+//! Append-only files such as logs are instead written through an append handle, so concurrent
+//! writers each add their record to the end without rereading or replacing the file.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Returned (wrapped in an `io::Error`) when a write would go through a symlink or junction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRefused {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for LinkRefused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "refusing to write through symbolic link or junction: {}", self.path.display())
+    }
+}
+
+impl std::error::Error for LinkRefused {}
+
+/// Whether `path` is a symbolic link or junction. On Windows this covers any name-surrogate
+/// reparse point, but not e.g. OneDrive placeholder files, which are safe to replace.
+pub fn is_link(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+fn check_not_link(path: &Path) -> io::Result<()> {
+    if is_link(path) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            LinkRefused { path: path.to_path_buf() },
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces the contents of `path` atomically.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    check_not_link(path)?;
+    check_not_link(parent)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+    let tmp_path = parent.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    // A temp file left behind by an earlier crash is safe to discard.
+    let _ = std::fs::remove_file(&tmp_path);
+
+    let result = (|| {
+        let mut tmp = OpenOptions::new().write(true).create_new(true).open(&tmp_path)?;
+        tmp.write_all(contents)?;
+        tmp.sync_all()?;
+        drop(tmp);
+        std::fs::rename(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Appends `contents` to `path` with a single write through an append handle, creating the file
+/// if needed. Writers in other processes each land their record whole at the end of the file.
+pub fn append(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    check_not_link(path)?;
+    check_not_link(parent)?;
+
+    let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+    file.write_all(contents)
+}
@@ -0,0 +1,161 @@
+//! Timestamped backups of Setup.ini files, taken before the helper changes them.
+//!
+//! Backups live under `%LOCALAPPDATA%\CondorVR\Backups\<data dir>-<hash>\<pilot>\Setup.<millis>.ini`,
+//! with the global Setup.ini of each data directory stored under `_Global`. The hash is of the
+//! Setup.ini's full path, so data directories with the same folder name on different drives keep
+//! separate backups.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use directories::BaseDirs;
+
+use crate::atomic_write::write_atomic;
+use crate::profiles::SetupFile;
+use crate::settings::SETTINGS_DIR;
+
+pub const BACKUPS_DIR: &str = "Backups";
+/// The number of backups kept for each Setup.ini.
+pub const DEFAULT_RETENTION: usize = 10;
+
+const GLOBAL_DIR: &str = "_Global";
+
+/// A single saved copy of a Setup.ini.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// Milliseconds since the Unix epoch when the backup was taken.
+    pub taken_at: u64,
+}
+
+impl Backup {
+    /// The backup time as "YYYY-MM-DD HH:MM:SS UTC".
+    pub fn timestamp(&self) -> String {
+        format_utc(UNIX_EPOCH + Duration::from_millis(self.taken_at))
+    }
+}
+
+/// A directory of Setup.ini backups with a per-file retention limit.
+#[derive(Debug, Clone)]
+pub struct BackupStore {
+    root: PathBuf,
+    retention: usize,
+}
+
+impl BackupStore {
+    pub fn new(root: PathBuf, retention: usize) -> Self {
+        Self { root, retention: retention.max(1) }
+    }
+
+    /// The default backup location, `%LOCALAPPDATA%\CondorVR\Backups`.
+    pub fn default_root() -> Option<PathBuf> {
+        Some(BaseDirs::new()?.data_local_dir().join(SETTINGS_DIR).join(BACKUPS_DIR))
+    }
+
+    /// Opens the store at the default location, falling back to the temp directory.
+    pub fn open_default() -> Self {
+        let root = Self::default_root()
+            .unwrap_or_else(|| std::env::temp_dir().join(SETTINGS_DIR).join(BACKUPS_DIR));
+        Self::new(root, DEFAULT_RETENTION)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn dir_for(&self, file: &SetupFile) -> PathBuf {
+        let path = std::fs::canonicalize(&file.path).unwrap_or_else(|_| file.path.clone());
+        self.root
+            .join(format!("{}-{:016x}", file.data_dir, path_hash(&path)))
+            .join(file.pilot.as_deref().unwrap_or(GLOBAL_DIR))
+    }
+
+    /// Copies the current contents of a Setup.ini into the store, then prunes old backups.
+    pub fn backup(&self, file: &SetupFile) -> io::Result<Backup> {
+        let contents = std::fs::read(&file.path)?;
+        let dir = self.dir_for(file);
+        std::fs::create_dir_all(&dir)?;
+
+        // Never overwrite an existing backup, even if two are taken within the same millisecond.
+        let mut taken_at = now_millis();
+        if let Some(latest) = self.list(file).first() {
+            taken_at = taken_at.max(latest.taken_at + 1);
+        }
+
+        let path = dir.join(format!("Setup.{}.ini", taken_at));
+        write_atomic(&path, &contents)?;
+
+        for old in self.list(file).into_iter().skip(self.retention) {
+            let _ = std::fs::remove_file(old.path);
+        }
+
+        Ok(Backup { path, taken_at })
+    }
+
+    /// Lists the backups of a Setup.ini, newest first.
+    pub fn list(&self, file: &SetupFile) -> Vec<Backup> {
+        let mut backups: Vec<Backup> = std::fs::read_dir(self.dir_for(file))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let taken_at = name.strip_prefix("Setup.")?.strip_suffix(".ini")?.parse().ok()?;
+                Some(Backup { path: entry.path(), taken_at })
+            })
+            .collect();
+        backups.sort_by_key(|b| std::cmp::Reverse(b.taken_at));
+        backups
+    }
+
+    /// Puts a backup back in place, first backing up the current file so the restore can be undone.
+    pub fn restore(&self, backup: &Backup, file: &SetupFile) -> io::Result<()> {
+        let contents = std::fs::read(&backup.path)?;
+        if file.path.exists() {
+            self.backup(file)?;
+        }
+        write_atomic(&file.path, &contents)
+    }
+}
+
+/// 64-bit FNV-1a of the path, which unlike `DefaultHasher` stays the same between builds.
+fn path_hash(path: &Path) -> u64 {
+    path.to_string_lossy().bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Formats a time as "YYYY-MM-DD HH:MM:SS UTC" without pulling in a date library.
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
@@ -0,0 +1,741 @@
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::*;
+use windows::Win32::Security::*;
+use windows::Win32::Security::Authorization::*;
+use windows::Win32::System::Services::*;
+
+use condor3_revive_helper::command_line::split_args;
+use condor3_revive_helper::config::ServiceMode;
+use condor3_revive_helper::hooked_exe::{
+    discover_install_paths, find, validate_exe_name, with_install_paths, HookedExe,
+};
+use condor3_revive_helper::ifeo::{LocalMachine, Registry};
+use condor3_revive_helper::plan::{hook_changes, plan_setup_ini, removal_changes, RegistryChange, ServiceChange, SERVICE_SDDL};
+use condor3_revive_helper::{
+    apply_setup_ini_changes, find_revive_injector, get_companion_exe_path, handle_version_args,
+    is_ifeo_hook_present, ActivationPlan, Compatibility, Component, Config, ConfigError, InvokingUser,
+    Logger, SetupIniOutcome, LAUNCHER_EXE_NAME, SERVICE_NAME, TARGET_EXE,
+};
+
+pub fn main() -> io::Result<()> {
+    if handle_version_args("Condor-VR-Configurer") {
+        return Ok(());
+    }
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (user, args) = InvokingUser::from_args(&args)?;
+    let Some((command, extra_args)) = args.split_first() else {
+        println!("Usage: Condor-VR-Configurer.exe [activate|deactivate] [--dry-run]");
+        println!("       Condor-VR-Configurer.exe restore [backup]");
+        println!("       Condor-VR-Configurer.exe [add-data-dir|remove-data-dir] [--root] <folder>");
+        println!("       Condor-VR-Configurer.exe add-exe <name.exe> [--injector <path>] [--injector-args <args>] [--path <full path>]... [--dry-run]");
+        println!("       Condor-VR-Configurer.exe [remove-exe|enable-exe|disable-exe] <name.exe> [--dry-run]");
+        println!("       Condor-VR-Configurer.exe service-mode [demand|persistent] [--dry-run]");
+        println!("Any command may be followed by --user <name> --documents <folder> --settings <file>");
+        println!("--backups <folder> to act for a user other than the one running it.");
+        return Ok(());
+    };
+
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let logger = if dry_run { Logger::console_only(Component::Configurer) } else { Logger::new(Component::Configurer) };
+
+    // When elevated with other credentials, act on the pilot's files rather than the administrator's.
+    let user = match user {
+        Some(user) => {
+            logger.info(&format!("Acting for user {}.", user.name));
+            user
+        }
+        None => InvokingUser::current(),
+    };
+    let res = run_command(command, extra_args, dry_run, &user, &logger);
+
+    if let Err(e) = res {
+        logger.error(&format!("Fatal error: {}", e));
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+fn run_command(
+    command: &str,
+    extra_args: &[String],
+    dry_run: bool,
+    user: &InvokingUser,
+    logger: &Logger,
+) -> io::Result<()> {
+    match command {
+        "activate" | "deactivate" => {
+            let mut config = match load_config(dry_run, logger) {
+                Ok(config) => config,
+                // Unhooking shouldn't depend on a readable config.
+                Err(e) if command == "deactivate" => {
+                    logger.warn(&format!("{}. Only Condor.exe will be unhooked.", e));
+                    Config::default()
+                }
+                Err(e) => return Err(e),
+            };
+            logger.set_level(config.log_level);
+            if command == "activate" {
+                remember_install_paths(&mut config, dry_run, logger)?;
+            }
+            let plan = build_plan(command == "activate", &config, user, logger)?;
+            if dry_run {
+                logger.info(&format!("Dry run: '{}' would make the following changes.", command));
+                for line in plan.to_string().lines() {
+                    logger.info(line);
+                }
+            } else if command == "activate" {
+                activate(&plan, user, logger)?;
+            } else {
+                deactivate(&plan, user, logger)?;
+            }
+        }
+        "restore" => restore_backup(extra_args.first().map(String::as_str), user, logger)?,
+        "add-data-dir" | "remove-data-dir" => edit_data_dirs(command == "add-data-dir", extra_args, user, logger)?,
+        "add-exe" | "remove-exe" | "enable-exe" | "disable-exe" => edit_hooked_exes(command, extra_args, dry_run, logger)?,
+        "service-mode" => set_service_mode(extra_args, dry_run, logger)?,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown command: {}", command))),
+    }
+
+    Ok(())
+}
+
+/// Reads `config.toml`. If it doesn't exist yet, it's written from the injector path older versions
+/// kept in `.env`; if standard users could have changed it, it's rewritten from that.
+fn load_config(dry_run: bool, logger: &Logger) -> io::Result<Config> {
+    let path = Config::path();
+    let config = match Config::load_machine() {
+        Ok(config) if path.exists() => return Ok(config),
+        Ok(config) => {
+            logger.info(&format!("Moving settings from .env to {}.", path.display()));
+            config
+        }
+        Err(e @ ConfigError::Untrusted(_)) => {
+            logger.warn(&format!("{}.", e));
+            Config::migrate_legacy()
+        }
+        Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+    };
+    if !dry_run {
+        config.save(&path)?;
+    }
+    Ok(config)
+}
+
+/// Saves the Condor installs found in `config.toml` if no paths are listed for `Condor.exe`, so the
+/// hook is scoped to them and they can be seen and edited there.
+fn remember_install_paths(config: &mut Config, dry_run: bool, logger: &Logger) -> io::Result<()> {
+    let exes = config.hooked_exes();
+    let scoped = with_install_paths(exes.clone(), &discover_install_paths());
+    if scoped == exes {
+        if find(&exes, TARGET_EXE).is_some_and(|exe| exe.paths.is_empty()) {
+            logger.warn(&format!("No Condor install was found, so every {} will be hooked.", TARGET_EXE));
+        }
+        return Ok(());
+    }
+    config.executables = scoped;
+    let path = Config::path();
+    if dry_run {
+        logger.info(&format!("Dry run: would save the Condor installs found to {}.", path.display()));
+        return Ok(());
+    }
+    config.save(&path)?;
+    logger.info(&format!(
+        "Saved the Condor installs found to {}. Add any other copy of {} to its paths.",
+        path.display(),
+        TARGET_EXE
+    ));
+    Ok(())
+}
+
+/// Works out everything `activate` or `deactivate` will change, without changing anything.
+fn build_plan(activate: bool, config: &Config, user: &InvokingUser, logger: &Logger) -> io::Result<ActivationPlan> {
+    let settings = user.load_settings();
+    let setup_ini = plan_setup_ini(&user.profiles(&settings), &settings, activate);
+    let exes = config.hooked_exes();
+    if !activate {
+        return Ok(ActivationPlan::deactivate(&LocalMachine, &exes, setup_ini));
+    }
+    for exe in exes.iter().filter(|e| e.enabled && !e.paths.is_empty()) {
+        let paths: Vec<String> = exe.paths.iter().map(|p| p.display().to_string()).collect();
+        logger.info(&format!("{} will only be hooked at {}.", exe.name, paths.join(", ")));
+    }
+
+    let launcher_path = get_companion_exe_path(LAUNCHER_EXE_NAME)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Launcher not found"))?;
+    let service_path = get_companion_exe_path(&format!("{SERVICE_NAME}.exe"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Service not found"))?;
+
+    let revive_path = find_revive_injector();
+    match config.injector.path.as_deref().or(revive_path.as_deref().map(Path::new)) {
+        None => logger.warn("ReviveInjector.exe not found. You may need to install Revive."),
+        Some(path) => match Compatibility::of_injector(path) {
+            compatibility if compatibility.is_supported() => logger.info(&format!("Found {}.", compatibility)),
+            compatibility => logger.warn(&format!("{}.", compatibility)),
+        },
+    }
+
+    Ok(ActivationPlan::activate(
+        &LocalMachine,
+        &launcher_path,
+        ServiceChange::install_or_update(&service_path, service_exists(), config.service.mode),
+        &exes,
+        setup_ini,
+    ))
+}
+
+/// Adds, removes, enables or disables a hooked executable in `config.toml`. If VR is currently
+/// enabled, its IFEO hook is added or removed to match straight away.
+fn edit_hooked_exes(command: &str, extra_args: &[String], dry_run: bool, logger: &Logger) -> io::Result<()> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let mut args = extra_args.iter().filter(|a| *a != "--dry-run");
+    let name = args.next().ok_or_else(|| invalid("No executable name given".to_string()))?;
+    validate_exe_name(name).map_err(invalid)?;
+
+    let mut config = load_config(dry_run, logger)?;
+    let old = config.hooked_exes();
+    let mut new = old.clone();
+    let existing = new.iter().position(|e| e.is_named(name));
+    match (command, existing) {
+        ("add-exe", _) => {
+            let mut exe = HookedExe::new(name.as_str());
+            while let Some(arg) = args.next() {
+                let value = args.next().ok_or_else(|| invalid(format!("{} needs a value", arg)))?;
+                match arg.as_str() {
+                    "--injector" => exe.injector_path = Some(PathBuf::from(value)),
+                    "--injector-args" => exe.injector_args = split_args(value),
+                    "--path" => exe.paths.push(PathBuf::from(value)),
+                    _ => return Err(invalid(format!("Unknown option: {}", arg))),
+                }
+            }
+            match existing {
+                Some(i) => new[i] = exe,
+                None => new.push(exe),
+            }
+        }
+        (_, None) => return Err(invalid(format!("{} is not a hooked executable", name))),
+        ("remove-exe", Some(_)) if new.len() == 1 => {
+            return Err(invalid(format!("{} is the only hooked executable; disable it instead", name)));
+        }
+        ("remove-exe", Some(i)) => {
+            new.remove(i);
+        }
+        (_, Some(i)) => new[i].enabled = command == "enable-exe",
+    }
+
+    let mut changes = removal_changes(&LocalMachine, &old, &new);
+    let vr_enabled = old.iter().any(|e| is_ifeo_hook_present(&e.name));
+    if vr_enabled && let Some(exe) = find(&new, name) {
+        let launcher_path = get_companion_exe_path(LAUNCHER_EXE_NAME)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Launcher not found"))?;
+        changes.extend(hook_changes(&LocalMachine, &launcher_path, std::slice::from_ref(exe)));
+    }
+
+    config.executables = new;
+    if let Err(e) = config.validate() {
+        return Err(invalid(e));
+    }
+
+    let path = Config::path();
+    if dry_run {
+        logger.info(&format!("Dry run: '{} {}' would make the following changes.", command, name));
+        logger.info(&format!("  Write {}", path.display()));
+        for change in &changes {
+            logger.info(&format!("  {}", change));
+        }
+        return Ok(());
+    }
+    config.save(&path)?;
+    logger.info(&format!("Saved {}.", path.display()));
+    for change in &changes {
+        apply_registry_change(change, logger)?;
+    }
+    Ok(())
+}
+
+/// Chooses whether the helper service is started for each launch or runs all the time, saving the
+/// choice in `config.toml` and applying it to the installed service, if there is one.
+fn set_service_mode(extra_args: &[String], dry_run: bool, logger: &Logger) -> io::Result<()> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let mode = match extra_args.iter().find(|a| *a != "--dry-run").map(String::as_str) {
+        Some("demand") => ServiceMode::Demand,
+        Some("persistent") => ServiceMode::Persistent,
+        Some(other) => return Err(invalid(format!("Unknown service mode: {}", other))),
+        None => return Err(invalid("No service mode given".to_string())),
+    };
+
+    let mut config = load_config(dry_run, logger)?;
+    config.service.mode = mode;
+    let change = match service_exists() {
+        true => Some(ServiceChange::Update {
+            path: get_companion_exe_path(&format!("{SERVICE_NAME}.exe"))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Service not found"))?
+                .to_string_lossy()
+                .into_owned(),
+            mode,
+        }),
+        false => None,
+    };
+
+    let path = Config::path();
+    if dry_run {
+        logger.info("Dry run: 'service-mode' would make the following changes.");
+        logger.info(&format!("  Write {}", path.display()));
+        if let Some(change) = &change {
+            logger.info(&format!("  {}", change));
+        }
+        return Ok(());
+    }
+    config.save(&path)?;
+    logger.info(&format!("Saved {}.", path.display()));
+    if let Some(change) = &change
+        && !apply_service_change(change, logger)
+    {
+        logger.error("The helper service could not be reconfigured.");
+    }
+    Ok(())
+}
+
+fn activate(plan: &ActivationPlan, user: &InvokingUser, logger: &Logger) -> io::Result<()> {
+    if !apply_service_change(&plan.service, logger) {
+        logger.error("VR support could not be activated because the helper service could not be installed.");
+        logger.error("This often happens if you recently uninstalled and haven't restarted yet.");
+        logger.error("Please restart your computer and try again.");
+        return Ok(());
+    }
+
+    if let Some(sddl) = &plan.service_sddl {
+        if let Err(e) = allow_everyone_to_start_service(sddl) {
+            logger.error(&format!("Failed to set service permissions: {}", e));
+        } else {
+            logger.info("Service permissions set.");
+        }
+    }
+
+    for change in &plan.registry {
+        apply_registry_change(change, logger)?;
+    }
+
+    apply_planned_setup_ini(plan, user, logger);
+    Ok(())
+}
+
+fn deactivate(plan: &ActivationPlan, user: &InvokingUser, logger: &Logger) -> io::Result<()> {
+    // Remove the hook before the service, so Condor never points at a missing helper.
+    for change in &plan.registry {
+        apply_registry_change(change, logger)?;
+    }
+
+    apply_service_change(&plan.service, logger);
+
+    apply_planned_setup_ini(plan, user, logger);
+    Ok(())
+}
+
+/// Applies the plan's Setup.ini changes and saves the graphics snapshots taken along the way.
+fn apply_planned_setup_ini(plan: &ActivationPlan, user: &InvokingUser, logger: &Logger) {
+    let mut settings = user.load_settings();
+    let results = apply_setup_ini_changes(&plan.setup_ini, &mut settings, &user.backups());
+    log_setup_results(logger, results);
+    if let Err(e) = user.save_settings(&settings) {
+        logger.error(&format!("Failed to save settings: {}", e));
+    }
+}
+
+/// Returns whether the service is now in the planned state.
+fn apply_service_change(change: &ServiceChange, logger: &Logger) -> bool {
+    let update = |path: &str, mode: ServiceMode, logger: &Logger| {
+        let previous = installed_mode();
+        match update_service_config(path, mode) {
+            Ok(()) => {
+                logger.info("Service configuration updated.");
+                apply_service_mode(mode, previous, logger);
+                true
+            }
+            Err(e) => {
+                logger.error(&format!("Failed to update service config: {}", e));
+                false
+            }
+        }
+    };
+
+    match change {
+        ServiceChange::Install { path, mode } => match install_service(path, *mode) {
+            Ok(_) => {
+                logger.info("Service installed.");
+                apply_service_mode(*mode, None, logger);
+                true
+            }
+            Err(e) if e.code() == ERROR_SERVICE_EXISTS.to_hresult() => {
+                logger.info("Service already exists, updating configuration...");
+                update(path, *mode, logger)
+            }
+            Err(e) => {
+                logger.error(&format!("Failed to install service: {}", e));
+                false
+            }
+        },
+        ServiceChange::Update { path, mode } => update(path, *mode, logger),
+        ServiceChange::Uninstall => match uninstall_service(SERVICE_NAME) {
+            Ok(()) => {
+                logger.info(&format!("Service {} uninstalled.", SERVICE_NAME));
+                true
+            }
+            Err(e) => {
+                logger.error(&format!("Failed to uninstall {}: {}", SERVICE_NAME, e));
+                false
+            }
+        },
+    }
+}
+
+fn apply_registry_change(change: &RegistryChange, logger: &Logger) -> io::Result<()> {
+    LocalMachine.apply(change)?;
+    logger.info(&format!("{}.", change));
+    Ok(())
+}
+
+fn service_exists() -> bool {
+    unsafe {
+        let Ok(scm) = OpenSCManagerW(None, None, SC_MANAGER_CONNECT) else {
+            return false;
+        };
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let exists = match OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_QUERY_STATUS) {
+            Ok(service) => {
+                let _ = CloseServiceHandle(service);
+                true
+            }
+            Err(_) => false,
+        };
+        let _ = CloseServiceHandle(scm);
+        exists
+    }
+}
+
+fn log_setup_results(logger: &Logger, results: Vec<SetupIniOutcome>) {
+    for outcome in results {
+        if outcome.is_ok() {
+            logger.info(&outcome.to_string());
+        } else {
+            logger.error(&outcome.to_string());
+        }
+    }
+}
+
+/// Lists the Setup.ini backups of every pilot, or restores the given backup file.
+fn restore_backup(backup_path: Option<&str>, user: &InvokingUser, logger: &Logger) -> io::Result<()> {
+    let profiles = user.profiles(&user.load_settings());
+    let store = user.backups();
+
+    let Some(backup_path) = backup_path else {
+        for file in profiles.setup_files() {
+            let backups = store.list(&file);
+            if backups.is_empty() {
+                continue;
+            }
+            logger.info(&format!("{}:", file.label()));
+            for backup in backups {
+                logger.info(&format!("  {}  {}", backup.timestamp(), backup.path.display()));
+            }
+        }
+        logger.info("To restore, run: Condor-VR-Configurer.exe restore <backup path>");
+        return Ok(());
+    };
+
+    for file in profiles.setup_files() {
+        if let Some(backup) = store.list(&file).into_iter().find(|b| b.path == Path::new(backup_path)) {
+            store.restore(&backup, &file)?;
+            logger.info(&format!("Restored {} from backup taken {}.", file.label(), backup.timestamp()));
+            return Ok(());
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No pilot Setup.ini has a backup at {}", backup_path),
+    ))
+}
+
+/// Adds or removes a Condor data directory, or with `--root` a folder searched for `Condor*` directories.
+fn edit_data_dirs(add: bool, extra_args: &[String], user: &InvokingUser, logger: &Logger) -> io::Result<()> {
+    let root = extra_args.iter().any(|a| a == "--root");
+    let Some(folder) = extra_args.iter().find(|a| !a.starts_with("--")) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "No folder given"));
+    };
+    let folder = Path::new(folder);
+    if add && !folder.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Folder not found: {}", folder.display()),
+        ));
+    }
+
+    let mut settings = user.load_settings();
+    let kind = if root { "Search folder" } else { "Data directory" };
+    let changed = match (add, root) {
+        (true, true) => settings.add_search_root(folder),
+        (true, false) => settings.add_data_dir(folder),
+        (false, true) => settings.remove_search_root(folder),
+        (false, false) => settings.remove_data_dir(folder),
+    };
+    if !changed {
+        let state = if add { "already listed" } else { "not listed" };
+        logger.info(&format!("{} {} is {}.", kind, folder.display(), state));
+        return Ok(());
+    }
+
+    user.save_settings(&settings)?;
+    let verb = if add { "added" } else { "removed" };
+    logger.info(&format!("{} {} {}.", kind, folder.display(), verb));
+    Ok(())
+}
+
+fn start_type(mode: ServiceMode) -> SERVICE_START_TYPE {
+    match mode {
+        ServiceMode::Demand => SERVICE_DEMAND_START,
+        ServiceMode::Persistent => SERVICE_AUTO_START,
+    }
+}
+
+/// Starts a persistent service, or stops one that was persistent until now. A running demand-mode
+/// service is left alone: it is in the middle of a launch and stops by itself once that is done.
+fn apply_service_mode(mode: ServiceMode, previous: Option<ServiceMode>, logger: &Logger) {
+    let result = match mode {
+        ServiceMode::Persistent => start_service(),
+        ServiceMode::Demand if previous == Some(ServiceMode::Persistent) => stop_service(),
+        ServiceMode::Demand => return,
+    };
+    match result {
+        Ok(()) if mode == ServiceMode::Persistent => logger.info("Service started."),
+        Ok(()) => {}
+        Err(e) => logger.warn(&format!("Couldn't {} the service: {}", if mode == ServiceMode::Persistent { "start" } else { "stop" }, e)),
+    }
+}
+
+/// How the installed service is set to start, if it is installed.
+fn installed_mode() -> Option<ServiceMode> {
+    unsafe {
+        let scm = OpenSCManagerW(None, None, SC_MANAGER_CONNECT).ok()?;
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let start_type = OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_QUERY_CONFIG).ok().and_then(|service| {
+            // The strings follow the struct; 8 KB is the documented maximum.
+            let mut buffer = vec![0u64; 1024];
+            let mut needed = 0;
+            let config = buffer.as_mut_ptr().cast::<QUERY_SERVICE_CONFIGW>();
+            let result = QueryServiceConfigW(service, Some(config), (buffer.len() * 8) as u32, &mut needed);
+            let _ = CloseServiceHandle(service);
+            result.ok().map(|()| (*config).dwStartType)
+        });
+        let _ = CloseServiceHandle(scm);
+        match start_type? {
+            SERVICE_AUTO_START => Some(ServiceMode::Persistent),
+            _ => Some(ServiceMode::Demand),
+        }
+    }
+}
+
+fn update_service_config(path: &str, mode: ServiceMode) -> Result<(), windows::core::Error> {
+    unsafe {
+        let scm = OpenSCManagerW(None, None, SC_MANAGER_ALL_ACCESS)?;
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let mut service_res =
+            OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_CHANGE_CONFIG);
+
+        if let Err(ref e) = service_res
+            && e.code() == ERROR_ACCESS_DENIED.to_hresult() {
+            // Try to fix permissions and then try again
+            let _ = allow_everyone_to_start_service(SERVICE_SDDL);
+            service_res =
+                OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_CHANGE_CONFIG);
+        }
+
+        let service = service_res?;
+
+        let service_path_w: Vec<u16> = format!("\"{}\"", path)
+            .encode_utf16()
+            .chain(Some(0))
+            .collect();
+
+        let res = ChangeServiceConfigW(
+            service,
+            ENUM_SERVICE_TYPE(SERVICE_NO_CHANGE),
+            start_type(mode),
+            SERVICE_ERROR(SERVICE_NO_CHANGE),
+            PCWSTR(service_path_w.as_ptr()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let _ = CloseServiceHandle(service);
+        let _ = CloseServiceHandle(scm);
+        res
+    }
+}
+
+fn install_service(path: &str, mode: ServiceMode) -> Result<(), windows::core::Error> {
+    unsafe {
+        let scm = OpenSCManagerW(None, None, SC_MANAGER_ALL_ACCESS)?;
+        if scm.is_invalid() {
+            return Err(windows::core::Error::from_thread());
+        }
+
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let service_path_w: Vec<u16> = format!("\"{}\"", path)
+            .encode_utf16()
+            .chain(Some(0))
+            .collect();
+
+        let service = CreateServiceW(
+            scm,
+            PCWSTR(service_name_w.as_ptr()),
+            PCWSTR(service_name_w.as_ptr()),
+            SERVICE_ALL_ACCESS,
+            SERVICE_WIN32_OWN_PROCESS,
+            start_type(mode),
+            SERVICE_ERROR_NORMAL,
+            PCWSTR(service_path_w.as_ptr()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        match service {
+            Ok(s) => {
+                let _ = CloseServiceHandle(s);
+                let _ = CloseServiceHandle(scm);
+                Ok(())
+            }
+            Err(e) => {
+                let _ = CloseServiceHandle(scm);
+                Err(e)
+            }
+        }
+    }
+}
+
+fn start_service() -> Result<(), windows::core::Error> {
+    unsafe {
+        let scm = OpenSCManagerW(None, None, SC_MANAGER_CONNECT)?;
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let service = match OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_START) {
+            Ok(service) => service,
+            Err(e) => {
+                let _ = CloseServiceHandle(scm);
+                return Err(e);
+            }
+        };
+        let res = match StartServiceW(service, None) {
+            Err(e) if e.code() == ERROR_SERVICE_ALREADY_RUNNING.to_hresult() => Ok(()),
+            res => res,
+        };
+        let _ = CloseServiceHandle(service);
+        let _ = CloseServiceHandle(scm);
+        res
+    }
+}
+
+fn stop_service() -> Result<(), windows::core::Error> {
+    unsafe {
+        let scm = OpenSCManagerW(None, None, SC_MANAGER_CONNECT)?;
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let service = match OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_STOP) {
+            Ok(service) => service,
+            Err(e) => {
+                let _ = CloseServiceHandle(scm);
+                return Err(e);
+            }
+        };
+        let mut status = SERVICE_STATUS::default();
+        let res = match ControlService(service, SERVICE_CONTROL_STOP, &mut status) {
+            Err(e) if e.code() == ERROR_SERVICE_NOT_ACTIVE.to_hresult() => Ok(()),
+            res => res,
+        };
+        let _ = CloseServiceHandle(service);
+        let _ = CloseServiceHandle(scm);
+        res
+    }
+}
+
+fn uninstall_service(name: &str) -> Result<(), windows::core::Error> {
+    unsafe {
+        let scm = OpenSCManagerW(None, None, SC_MANAGER_ALL_ACCESS)?;
+        if scm.is_invalid() {
+            return Err(windows::core::Error::from_thread());
+        }
+
+        let service_name_w: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+        let mut service_res =
+            OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_ALL_ACCESS);
+
+        if let Err(ref e) = service_res
+            && e.code() == ERROR_ACCESS_DENIED.to_hresult() {
+            // Try to fix permissions and then try again
+            let _ = allow_everyone_to_start_service(SERVICE_SDDL);
+            service_res = OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), SERVICE_ALL_ACCESS);
+        }
+
+        if let Ok(service) = service_res {
+            let mut status = SERVICE_STATUS::default();
+            let _ = ControlService(service, SERVICE_CONTROL_STOP, &mut status);
+
+            // Wait for service to stop
+            for _ in 0..50 {
+                let mut status = SERVICE_STATUS::default();
+                if QueryServiceStatus(service, &mut status).is_ok()
+                    && status.dwCurrentState == SERVICE_STOPPED {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+
+            let _ = DeleteService(service);
+            let _ = CloseServiceHandle(service);
+        }
+
+        let _ = CloseServiceHandle(scm);
+        Ok(())
+    }
+}
+
+fn allow_everyone_to_start_service(sddl: &str) -> Result<(), windows::core::Error> {
+    unsafe {
+        let scm = OpenSCManagerW(None, None, SC_MANAGER_CONNECT)?;
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        // WRITE_DAC (0x00040000) is required to set security
+        let service = OpenServiceW(scm, PCWSTR(service_name_w.as_ptr()), 0x00040000)?;
+
+        let sddl_w: Vec<u16> = sddl.encode_utf16().chain(Some(0)).collect();
+
+        let mut p_sd: PSECURITY_DESCRIPTOR = PSECURITY_DESCRIPTOR::default();
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            PCWSTR(sddl_w.as_ptr()),
+            SDDL_REVISION_1,
+            &mut p_sd,
+            None,
+        )?;
+
+        let res = SetServiceObjectSecurity(
+            service,
+            DACL_SECURITY_INFORMATION,
+            p_sd,
+        );
+
+        let _ = LocalFree(Some(HLOCAL(p_sd.0)));
+        let _ = CloseServiceHandle(service);
+        let _ = CloseServiceHandle(scm);
+
+        res
+    }
+}
@@ -0,0 +1,686 @@
+//! The launcher which is triggered by the registry key when Condor.exe is executed;
+//! it then launches Condor with ReviveInjector, setting flags to avoid the registry
+//! hook triggering again.
+
+use std::path::Path;
+use std::sync::{Arc, LazyLock, mpsc};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::{thread, time::Duration, time::Instant, time::SystemTime};
+
+use windows::Win32::Foundation::*;
+use windows::Win32::System::Services::*;
+use windows::core::PCWSTR;
+
+use eframe::egui;
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_SHIFT};
+
+use condor3_revive_helper::{
+    find_revive_injector, get_companion_exe_path, handle_version_args, Compatibility,
+    Component, Config, FlatscreenSession, GraphicsMode, InterceptedLaunch, InvokingUser, LaunchChooser,
+    LaunchHistory, LaunchParseError, LaunchPlan, LaunchRecord, Logger, SetupIniOutcome,
+    UserSettings, SERVICE_NAME,
+};
+use condor3_revive_helper::bypass_protocol::{self, BypassClient, BypassRequest, BypassState};
+use condor3_revive_helper::config::Timeouts;
+use condor3_revive_helper::hooked_exe::find;
+use condor3_revive_helper::process_watch::{EARLY_EXIT, WatchedProcess, find_started_process, list_processes};
+
+/// Our command line exactly as Windows passed it, before `env::args` splits it up.
+fn raw_command_line() -> String {
+    unsafe { windows::Win32::System::Environment::GetCommandLineW().to_string() }.unwrap_or_default()
+}
+
+/// Asks the service to lift the IFEO hook of `exe_name` until that program has started, or until
+/// this launcher exits. The service is started if needed and then asked over its pipe.
+fn trigger_bypass_service(exe_name: &str, timeouts: &Timeouts) -> Result<(), Box<dyn std::error::Error>> {
+    start_service()?;
+
+    let mut connection = bypass_protocol::connect(timeouts.service_start())
+        .map_err(|e| format!("couldn't connect to {}: {}", SERVICE_NAME, e))?;
+    LOGGER.info("Connected to the service.");
+
+    // Follow the request on another thread, which passes on each step until the hook is lifted and
+    // then stays to log when it is back.
+    let mut client = BypassClient::new(BypassRequest::new(exe_name, std::process::id()));
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if let Err(e) = client.send(&mut connection.writer) {
+            let _ = tx.send(Err(e));
+            return;
+        }
+        loop {
+            let state = client.next(&mut connection.reader).cloned();
+            let lifted = matches!(state, Ok(BypassState::Lifted));
+            let failed = state.is_err();
+            let _ = tx.send(state);
+            if lifted {
+                break;
+            }
+            if failed {
+                return;
+            }
+        }
+        match client.wait_for_restore(&mut connection.reader) {
+            Ok(BypassState::Restored { reason, .. }) => LOGGER.info(&format!("The service put the hook back because {}.", reason)),
+            Ok(_) => {}
+            Err(e) => LOGGER.warn(&format!("No word from the service on putting the hook back: {}", e)),
+        }
+    });
+
+    let mut deadline = Instant::now() + timeouts.hook_removal();
+    loop {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Ok(BypassState::Lifted)) => {
+                LOGGER.info("IFEO hook confirmed deleted.");
+                return Ok(());
+            }
+            Ok(Ok(BypassState::Queued { ahead })) => {
+                // Each launch ahead may hold its hook for a whole lease.
+                LOGGER.info(&format!("Waiting behind {} other launch(es).", ahead));
+                deadline = Instant::now() + timeouts.bypass_lease() * (ahead as u32 + 1) + timeouts.hook_removal();
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                LOGGER.error(&e.to_string());
+                return Err(e.into());
+            }
+            Err(_) => {
+                LOGGER.error("IFEO hook still present after timeout.");
+                return Err("IFEO hook could not be removed. Please ensure the CondorReviveHelperService is running or restart your computer.".into());
+            }
+        }
+    }
+}
+
+/// Starts the helper service, which is fine if it is already running.
+fn start_service() -> Result<(), Box<dyn std::error::Error>> {
+    unsafe {
+        let scm = match OpenSCManagerW(None, None, SC_MANAGER_CONNECT) {
+            Ok(h) => h,
+            Err(e) => {
+                LOGGER.error(&format!("OpenSCManagerW failed: {}", e));
+                return Err(e.into());
+            }
+        };
+        
+        let service_name_w: Vec<u16> = SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let service = match OpenServiceW(
+            scm,
+            PCWSTR(service_name_w.as_ptr()),
+            SERVICE_START | SERVICE_QUERY_STATUS,
+        ) {
+            Ok(h) => h,
+            Err(e) => {
+                LOGGER.error(&format!("OpenServiceW failed for {}: {}", SERVICE_NAME, e));
+                let _ = CloseServiceHandle(scm);
+                return Err(e.into());
+            }
+        };
+
+        let result = if StartServiceW(service, None).is_ok() {
+            LOGGER.info("Service start signal sent successfully.");
+            Ok(())
+        } else {
+            let err = windows::core::Error::from_thread();
+            if err.code() == ERROR_SERVICE_ALREADY_RUNNING.to_hresult() {
+                LOGGER.info("Service is already running.");
+                Ok(())
+            } else {
+                LOGGER.error(&format!("StartServiceW failed: {}", err));
+                Err(err.into())
+            }
+        };
+
+        let _ = CloseServiceHandle(service);
+        let _ = CloseServiceHandle(scm);
+        result
+    }
+}
+
+static LOGGER: LazyLock<Logger> = LazyLock::new(|| Logger::new(Component::Launcher));
+
+struct LauncherState {
+    progress: AtomicU32,
+    finished: AtomicBool,
+    error_message: std::sync::Mutex<Option<String>>,
+    /// The "VR or flatscreen?" question and when it was asked, until it's answered or times out.
+    chooser: std::sync::Mutex<Option<(LaunchChooser, Instant)>>,
+    /// Set once the launch is going ahead without VR.
+    flatscreen: AtomicBool,
+    /// This launch's entry in the launch history, once the target is known.
+    record: std::sync::Mutex<Option<LaunchRecord>>,
+    /// When the current stage of the launch began.
+    stage_started: std::sync::Mutex<Instant>,
+}
+
+impl LauncherState {
+    fn fail(&self, msg: String) {
+        LOGGER.error(&msg);
+        *self.error_message.lock().unwrap() = Some(msg);
+    }
+
+    fn update_record(&self, update: impl FnOnce(&mut LaunchRecord)) {
+        if let Some(record) = self.record.lock().unwrap().as_mut() {
+            update(record);
+        }
+    }
+
+    /// Records how long the stage that just finished took.
+    fn stage_done(&self, name: &str) {
+        let took = {
+            let mut started = self.stage_started.lock().unwrap();
+            let took = started.elapsed();
+            *started = Instant::now();
+            took
+        };
+        self.update_record(|record| record.add_stage(name, took));
+    }
+
+    /// Writes the launch to the history, failed with the error shown if there is one.
+    fn save_record(&self) {
+        let error = self.error_message.lock().unwrap().clone();
+        let Some(mut record) = self.record.lock().unwrap().clone() else {
+            return;
+        };
+        if let Some(error) = error {
+            record.fail(&error);
+        }
+        if let Err(e) = LaunchHistory::open_default().save(&record) {
+            LOGGER.warn(&format!("Could not save the launch history: {}", e));
+        }
+    }
+}
+
+fn is_shift_down() -> bool {
+    unsafe { GetAsyncKeyState(VK_SHIFT.0 as i32) as u16 & 0x8000 != 0 }
+}
+
+/// Asks "VR or flatscreen?" in the window until the user answers or the default is picked.
+/// Holding Shift answers flatscreen.
+fn wait_for_launch_choice(state: &LauncherState, chooser: LaunchChooser) -> GraphicsMode {
+    *state.chooser.lock().unwrap() = Some((chooser, Instant::now()));
+    let mode = loop {
+        {
+            let mut choosing = state.chooser.lock().unwrap();
+            let (chooser, asked) = choosing.as_mut().unwrap();
+            if is_shift_down() {
+                chooser.choose(GraphicsMode::Flatscreen);
+            }
+            if let Some(mode) = chooser.decision(asked.elapsed()) {
+                *choosing = None;
+                break mode;
+            }
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    state.flatscreen.store(mode == GraphicsMode::Flatscreen, Ordering::Relaxed);
+    mode
+}
+
+fn log_setup_results(results: &[SetupIniOutcome]) {
+    for result in results {
+        if result.is_ok() {
+            LOGGER.info(&result.to_string());
+        } else {
+            LOGGER.error(&result.to_string());
+        }
+    }
+}
+
+/// Switches Setup.ini back to VR for flatscreen sessions whose launcher was killed before it could.
+fn recover_flatscreen_sessions(user: &InvokingUser, settings: &mut UserSettings) {
+    let running: Vec<u32> = list_processes().iter().map(|p| p.pid).collect();
+    let results =
+        FlatscreenSession::recover(&FlatscreenSession::default_dir(), |pid| running.contains(&pid), settings, &user.backups());
+    if !results.is_empty() {
+        LOGGER.info("Switching Setup.ini back to VR after an unfinished flatscreen launch.");
+        log_setup_results(&results);
+        let _ = user.save_settings(settings);
+    }
+}
+
+/// Starts the intercepted program directly through the service bypass, without ReviveInjector.
+/// If Setup.ini is switched to flatscreen for the session, it's switched back once Condor exits.
+fn launch_flatscreen(
+    state: &LauncherState,
+    exe_name: &str,
+    launch: &InterceptedLaunch,
+    user: &InvokingUser,
+    mut settings: UserSettings,
+    timeouts: &Timeouts,
+) {
+    LOGGER.info("Flatscreen launch requested.");
+    state.progress.store(0.05f32.to_bits(), Ordering::Relaxed);
+
+    let backups = user.backups();
+    let sessions = FlatscreenSession::default_dir();
+    let session = if settings.flatscreen_disables_vr() {
+        let profiles = user.profiles(&settings);
+        match FlatscreenSession::begin(&profiles, launch.condor_version(), &mut settings, &backups, &sessions) {
+            Ok((session, results)) => {
+                log_setup_results(&results);
+                let _ = user.save_settings(&settings);
+                state.stage_done("Switching Setup.ini to flatscreen");
+                Some(session)
+            }
+            Err(e) => {
+                state.fail(format!("Failed to record the flatscreen session, so Setup.ini was left in VR: {}", e));
+                return;
+            }
+        }
+    } else {
+        None
+    };
+    let end_session = |mut settings: UserSettings| {
+        if let Some(session) = session.clone() {
+            log_setup_results(&session.end(&mut settings, &backups, &sessions));
+            let _ = user.save_settings(&settings);
+        }
+    };
+
+    LOGGER.info("Triggering CondorReviveHelperService to bypass IFEO...");
+    if let Err(e) = trigger_bypass_service(exe_name, timeouts) {
+        state.fail(format!("Failed to bypass IFEO: {}. Please restart your computer to resolve this.", e));
+        end_session(settings);
+        return;
+    }
+    state.stage_done("Lifting the hook");
+    state.progress.store(0.50f32.to_bits(), Ordering::Relaxed);
+
+    let plan = LaunchPlan::direct(launch);
+    LOGGER.info(&format!("Running without Revive: {}", plan));
+    let mut child = match plan.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            state.fail(format!("Failed to start {}: {}", launch.target.display(), e));
+            end_session(settings);
+            return;
+        }
+    };
+    state.stage_done("Starting the program");
+    state.progress.store(1.0f32.to_bits(), Ordering::Relaxed);
+    state.finished.store(true, Ordering::Relaxed);
+    state.save_record();
+
+    if session.as_ref().is_some_and(|s| !s.files().is_empty()) {
+        // The window has closed; stay in the background to put VR back afterwards.
+        LOGGER.info("Waiting for Condor to exit before switching Setup.ini back to VR...");
+        let _ = child.wait();
+        end_session(user.load_settings());
+    }
+    LOGGER.info("Done. Launcher exiting.");
+}
+
+/// Follows the program ReviveInjector started. The window stays open until it has survived
+/// [`EARLY_EXIT`], so a VR failure can be shown; after that the launcher waits in the background
+/// to log how it ended.
+fn watch_started_program(state: &LauncherState, exe_name: &str, injector_pid: u32) {
+    let started = find_started_process(&list_processes(), injector_pid, exe_name);
+    let Some(program) = started.and_then(|pid| WatchedProcess::open(pid, exe_name).ok()) else {
+        state.fail(format!(
+            "{} is not running after Revive Injector finished, so it probably exited immediately. Is SteamVR running and your headset connected?",
+            exe_name
+        ));
+        return;
+    };
+    LOGGER.info(&format!("{} started with PID {}.", exe_name, program.pid()));
+    state.progress.store(0.75f32.to_bits(), Ordering::Relaxed);
+
+    let running_for = program.started().elapsed().unwrap_or_default();
+    let early_run = program.wait_timeout(EARLY_EXIT.saturating_sub(running_for));
+    state.stage_done("Waiting for the program to start");
+    if let Some(run) = early_run {
+        LOGGER.info(&format!("{}.", run));
+        state.update_record(|record| record.set_run(&run));
+        match run.problem() {
+            Some(problem) => state.fail(problem),
+            None => state.finished.store(true, Ordering::Relaxed),
+        }
+        return;
+    }
+    state.progress.store(1.0f32.to_bits(), Ordering::Relaxed);
+    state.finished.store(true, Ordering::Relaxed);
+    state.save_record();
+
+    let run = program.wait();
+    LOGGER.info(&format!("{}.", run));
+    state.update_record(|record| record.set_run(&run));
+    if let Some(problem) = run.problem() {
+        LOGGER.warn(&problem);
+    }
+    LOGGER.info("Done. Launcher exiting.");
+}
+
+/// Runs one intercepted launch, in VR through ReviveInjector or flatscreen directly.
+fn run_launch(state: &LauncherState, launch: Result<InterceptedLaunch, LaunchParseError>) {
+    let launch = match launch {
+        Ok(launch) => launch,
+        Err(e) => {
+            let msg = format!("Could not work out what to launch: {}.", e);
+            state.fail(msg);
+            return;
+        }
+    };
+
+    *state.record.lock().unwrap() = Some(LaunchRecord::new(&launch, SystemTime::now()));
+
+    let config = Config::load_machine().unwrap_or_else(|e| {
+        LOGGER.warn(&format!("{}; using the default settings.", e));
+        Config::default()
+    });
+    LOGGER.set_level(config.log_level);
+
+    // IFEO matches on the file name; a target typed without ".exe" still matched "<name>.exe".
+    let exes = config.hooked_exes();
+    let exe_name = launch.exe_name();
+    let Some(exe) = find(&exes, &exe_name).or_else(|| find(&exes, &format!("{}.exe", exe_name))) else {
+        let msg = format!("{} is not set up to launch through CondorVR. Please re-enable VR in the Condor3 Revive Helper.", exe_name);
+        state.fail(msg);
+        return;
+    };
+
+    let user = InvokingUser::current();
+    let mut settings = user.load_settings();
+    recover_flatscreen_sessions(&user, &mut settings);
+    let mode = if config.launch.allow_flatscreen {
+        wait_for_launch_choice(state, LaunchChooser::for_exe(&settings, &exe.name))
+    } else {
+        GraphicsMode::Vr
+    };
+    LOGGER.info(&format!("Launching {} in {}.", exe.name, mode));
+    state.stage_done("Choosing VR or flatscreen");
+    state.update_record(|record| record.mode = Some(mode));
+    if settings.remember_launch_mode() && settings.last_launch_mode(&exe.name) != Some(mode) {
+        settings.set_last_launch_mode(&exe.name, mode);
+        let _ = user.save_settings(&settings);
+    }
+    if mode == GraphicsMode::Flatscreen {
+        launch_flatscreen(state, &exe.name, &launch, &user, settings, &config.timeouts);
+        return;
+    }
+
+    // An injector configured for this executable takes precedence, then the configured
+    // default, then the one found in the registry or the usual install folders.
+    let detected = find_revive_injector();
+    let injector = match config.injector_for(exe, detected.as_deref().map(Path::new)) {
+        Some(injector) => injector,
+        None => {
+            let msg = "Revive Injector not found. Please ensure Revive is installed in Program Files.".to_string();
+            state.fail(msg);
+            return;
+        }
+    };
+
+    LOGGER.info(&format!("Intercepted launch of: {} with args {:?}", launch.target.display(), launch.args));
+    state.update_record(|record| record.injector_path = Some(injector.path.clone()));
+
+    let compatibility = Compatibility::of_injector(&injector.path);
+    if compatibility.blocks_launch() && config.launch.block_old_revive {
+        let hint = if config.launch.allow_flatscreen {
+            ". You can still launch flatscreen by holding Shift as Condor starts."
+        } else {
+            "."
+        };
+        state.fail(format!("{}{}", compatibility, hint));
+        return;
+    } else if !compatibility.is_supported() {
+        LOGGER.warn(&format!("{}; trying anyway.", compatibility));
+    } else {
+        LOGGER.info(&format!("Using {}.", compatibility));
+    }
+
+    // Start progress bar at 5% to show we're active
+    state.progress.store(0.05f32.to_bits(), Ordering::Relaxed);
+
+    // Trigger the CondorReviveHelperService to bypass IFEO
+    LOGGER.info("Triggering CondorReviveHelperService to bypass IFEO...");
+    if let Err(e) = trigger_bypass_service(&exe.name, &config.timeouts) {
+        let msg = format!("Failed to bypass IFEO: {}. This can happen if you recently reinstalled and haven't restarted, or if the helper service is disabled. Please restart your computer to resolve this.", e);
+        state.fail(msg);
+        return; // Stop on error
+    } else {
+        LOGGER.info("Bypass service triggered.");
+        state.stage_done("Lifting the hook");
+    }
+
+    let injector_pid = if injector.path.exists() {
+        let plan = LaunchPlan::for_injector(&injector, &launch);
+        LOGGER.info(&format!("Running Revive Injector: {}", plan));
+
+        LOGGER.info("Waiting for Revive Injector to initialize...");
+        state.progress.store(0.15f32.to_bits(), Ordering::Relaxed);
+
+        let child = plan.spawn();
+
+        match child {
+            Ok(mut child) => {
+                LOGGER.info("Revive Injector started. Waiting for it to exit...");
+                match child.wait() {
+                    Ok(s) => {
+                        if !s.success() {
+                            let msg = format!("Revive Injector failed with exit code: {}", s);
+                            state.fail(msg);
+                            return; // Stop on error
+                        } else {
+                            LOGGER.info("Revive Injector reported success.");
+                            state.stage_done("Running Revive Injector");
+                            state.progress.store(0.50f32.to_bits(), Ordering::Relaxed);
+                        }
+                    }
+                    Err(e) => {
+                        let msg = format!("Failed to wait for Revive Injector: {}", e);
+                        state.fail(msg);
+                        return; // Stop on error
+                    }
+                }
+                child.id()
+            }
+            Err(e) => {
+                let msg = format!("Failed to run Revive Injector: {}", e);
+                state.fail(msg);
+                return; // Stop on error
+            }
+        }
+    } else {
+        // The injector may have been moved or uninstalled since it was configured.
+        let msg = format!("Revive Injector not found at {}", injector.path.display());
+        state.fail(msg);
+        return; // Stop on error
+    };
+
+    watch_started_program(state, &exe.name, injector_pid);
+}
+
+struct LauncherApp {
+    state: Arc<LauncherState>,
+    is_manual: bool,
+}
+
+impl LauncherApp {
+    fn new(state: Arc<LauncherState>, is_manual: bool) -> Self {
+        Self { state, is_manual }
+    }
+
+    fn open_settings(&self) {
+        if let Some(p) = get_companion_exe_path("gui.exe")
+            && p.exists() {
+            let mut cmd = std::process::Command::new(p);
+            #[cfg(windows)]
+            {
+                use std::os::windows::process::CommandExt;
+                cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+            }
+            let _ = cmd.spawn();
+        }
+    }
+}
+
+impl eframe::App for LauncherApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let has_error = self.state.error_message.lock().unwrap().is_some();
+        if !self.is_manual && self.state.finished.load(Ordering::Relaxed) && !has_error {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(msg) = self.state.error_message.lock().unwrap().clone() {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(10.0);
+                    ui.heading("Launch Error");
+                    ui.add_space(10.0);
+                    ui.label(&msg);
+                    ui.add_space(20.0);
+                    if ui.button("Close").clicked() {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+                return;
+            }
+
+            let choosing = self
+                .state
+                .chooser
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|(chooser, asked)| (chooser.default, chooser.seconds_left(asked.elapsed())));
+            ui.vertical_centered(|ui| {
+                ui.add_space(10.0);
+                if self.is_manual {
+                    ui.heading("Condor VR Helper");
+                    ui.add_space(10.0);
+                    ui.label("Whenever Condor is launched, it will open via this program");
+                    ui.label("as long as VR is enabled in the Condor3 Revive Helper.");
+                    ui.add_space(20.0);
+                } else if self.state.flatscreen.load(Ordering::Relaxed) {
+                    ui.heading("Starting Condor without VR...");
+                    ui.add_space(15.0);
+
+                    let progress = f32::from_bits(self.state.progress.load(Ordering::Relaxed));
+                    ui.add(egui::ProgressBar::new(progress).show_percentage());
+
+                    ui.add_space(15.0);
+                    ui.label("Revive is skipped for this launch only.");
+                    ui.label("This window will close automatically.");
+                    ui.add_space(10.0);
+                } else if let Some((default, seconds_left)) = choosing {
+                    ui.heading("VR or flatscreen?");
+                    ui.add_space(15.0);
+
+                    let mut choice = None;
+                    ui.horizontal(|ui| {
+                        ui.add_space((ui.available_width() - 290.0) / 2.0);
+                        for (mode, label) in [(GraphicsMode::Vr, "VR"), (GraphicsMode::Flatscreen, "Flatscreen")] {
+                            let text = if mode == default { format!("{} ({})", label, seconds_left) } else { label.to_string() };
+                            if ui.add_sized([140.0, 32.0], egui::Button::new(text)).clicked() {
+                                choice = Some(mode);
+                            }
+                        }
+                    });
+                    if let Some(mode) = choice
+                        && let Some((chooser, _)) = self.state.chooser.lock().unwrap().as_mut()
+                    {
+                        chooser.choose(mode);
+                    }
+
+                    ui.add_space(10.0);
+                    ui.label("Hold Shift as Condor starts to go straight to flatscreen.");
+                    ui.add_space(10.0);
+                } else {
+                    ui.heading("Starting Condor with VR...");
+                    ui.add_space(15.0);
+
+                    let progress_bits = self.state.progress.load(Ordering::Relaxed);
+                    let progress = f32::from_bits(progress_bits);
+                    ui.add(egui::ProgressBar::new(progress).show_percentage());
+
+                    ui.add_space(15.0);
+                    ui.label(if progress < 0.75 { "Initializing VR support..." } else { "Waiting for Condor to start..." });
+                    ui.label("This window will close automatically.");
+                    ui.add_space(10.0);
+                }
+
+                ui.add_space(5.0);
+                if ui
+                    .add_sized([140.0, 32.0], egui::Button::new("Open VR Settings"))
+                    .clicked()
+                {
+                    self.open_settings();
+                }
+
+                if self.is_manual {
+                    ui.add_space(15.0);
+                    if ui
+                        .add_sized([140.0, 32.0], egui::Button::new("Exit"))
+                        .clicked()
+                    {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    ui.add_space(20.0);
+                } else {
+                    ui.add_space(10.0);
+                }
+            });
+        });
+
+        ctx.request_repaint_after(Duration::from_millis(50));
+    }
+}
+
+pub fn main() -> eframe::Result {
+    if handle_version_args("CondorVR") {
+        return Ok(());
+    }
+
+    let command_line = raw_command_line();
+    LOGGER.info(&format!("Launcher started with command line: {}", command_line));
+
+    // IFEO appends the original command line (the target and its arguments) to ours.
+    let launch = InterceptedLaunch::parse(&command_line);
+    let is_manual = launch == Err(LaunchParseError::NoTarget);
+
+    let state = Arc::new(LauncherState {
+        progress: AtomicU32::new(0.0f32.to_bits()),
+        finished: AtomicBool::new(false),
+        error_message: std::sync::Mutex::new(None),
+        chooser: std::sync::Mutex::new(None),
+        flatscreen: AtomicBool::new(false),
+        record: std::sync::Mutex::new(None),
+        stage_started: std::sync::Mutex::new(Instant::now()),
+    });
+
+    let state_clone = Arc::clone(&state);
+    let handle = if !is_manual {
+        Some(thread::spawn(move || {
+            run_launch(&state_clone, launch);
+            state_clone.save_record();
+        }))
+    } else {
+        None
+    };
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([400.0, if is_manual { 220.0 } else { 180.0 }])
+            .with_always_on_top()
+            .with_decorations(true)
+            .with_close_button(!is_manual)
+            .with_minimize_button(false)
+            .with_maximize_button(false)
+            .with_resizable(false),
+        ..Default::default()
+    };
+
+    let result = eframe::run_native(
+        "Condor VR Launcher",
+        options,
+        Box::new(|_cc| Ok(Box::new(LauncherApp::new(state, is_manual)))),
+    );
+
+    if let Some(h) = handle {
+        let _ = h.join();
+    }
+    result
+}
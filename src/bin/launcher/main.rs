@@ -0,0 +1,17 @@
+//! See `app` for what this program does. It only runs on Windows; elsewhere this stub lets the
+//! library and its tests build.
+
+#![windows_subsystem = "windows"]
+
+#[cfg(windows)]
+mod app;
+
+#[cfg(windows)]
+fn main() -> eframe::Result {
+    app::main()
+}
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("CondorVR only runs on Windows.");
+}
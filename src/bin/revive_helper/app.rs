@@ -0,0 +1,904 @@
+//! The GUI program which allows the user to enable/disable VR for Condor.
+
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use eframe::egui;
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_OK, MB_ICONERROR, SW_HIDE};
+use windows::Win32::UI::Shell::{ShellExecuteExW, SHELLEXECUTEINFOW, SEE_MASK_NOCLOSEPROCESS};
+use windows::Win32::System::Threading::{WaitForSingleObject, GetExitCodeProcess, GetProcessId, INFINITE};
+use windows::Win32::Foundation::CloseHandle;
+
+use condor3_revive_helper::backup::Backup;
+use condor3_revive_helper::command_line::join_args;
+use condor3_revive_helper::graphics_profile::profile_candidates;
+use condor3_revive_helper::hooked_exe::{discover_install_paths, unlisted_installs};
+use condor3_revive_helper::launch_history::LaunchOutcome;
+use condor3_revive_helper::logging::read_records;
+use condor3_revive_helper::process_watch::{exit_code_text, list_processes};
+use condor3_revive_helper::profiles::default_documents_dir;
+use condor3_revive_helper::{
+    find_revive_injector, get_companion_exe_path, handle_version_args, is_ifeo_hook_present,
+    read_vr_enabled, select_vr_for, BackupStore, Compatibility, Component, Config, CondorProfileSet, CondorVersion, FlatscreenSession, GraphicsMode, InvokingUser, LaunchHistory, LaunchRecord, LogLevel, Logger, SetupFile, SetupIniOutcome, UserSettings,
+    CONFIGURER_EXE_NAME,
+};
+
+static LOGGER: LazyLock<Logger> = LazyLock::new(|| Logger::new(Component::Gui));
+
+fn show_error(msg: &str) {
+    unsafe {
+        let _ = MessageBoxW(
+            None,
+            &HSTRING::from(msg),
+            &HSTRING::from("Error"),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
+
+pub fn main() -> eframe::Result {
+    if handle_version_args("Condor3 Revive Helper") {
+        return Ok(());
+    }
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([500.0, 450.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Condor3 Revive Helper",
+        options,
+        Box::new(|_cc| Ok(Box::new(ReviveHelperApp::default()))),
+    )
+}
+
+struct PilotStatus {
+    file: SetupFile,
+    vr_enabled: bool,
+    /// Whether this Setup.ini follows the VR toggle, as remembered in the user settings.
+    selected: bool,
+}
+
+struct DataDirStatus {
+    name: String,
+    pilots: Vec<PilotStatus>,
+}
+
+/// The data directories of one Condor version, and where that version is installed.
+struct VersionGroup {
+    version: CondorVersion,
+    condor_exe: Option<PathBuf>,
+    data_dirs: Vec<DataDirStatus>,
+}
+
+/// One of the executables routed through the launcher.
+struct HookStatus {
+    name: String,
+    enabled: bool,
+    hooked: bool,
+    /// The installs the hook is scoped to, or none if it catches every copy.
+    paths: Vec<PathBuf>,
+    /// Installs found that the scoped hook misses.
+    unlisted: Vec<PathBuf>,
+}
+
+enum FolderEdit {
+    AddDataDir(PathBuf),
+    AddSearchRoot(PathBuf),
+    RemoveDataDir(PathBuf),
+    RemoveSearchRoot(PathBuf),
+}
+
+struct ReviveHelperApp {
+    is_active: bool,
+    hooks: Vec<HookStatus>,
+    /// The installed ReviveInjector's version check, or `None` if it wasn't found.
+    revive: Option<Compatibility>,
+    /// Why `config.toml` couldn't be used, if it couldn't.
+    config_error: Option<String>,
+    /// The user running the GUI, whose files the (possibly elevated) configurer should change.
+    user: InvokingUser,
+    profiles: CondorProfileSet,
+    settings: UserSettings,
+    backups: BackupStore,
+    versions: Vec<VersionGroup>,
+    /// Every `[Graphics]` key found in any Setup.ini, offered in the graphics profile editor.
+    graphics_keys: Vec<String>,
+    status_msg: String,
+    logs: String,
+    show_logs: bool,
+    show_backups: bool,
+    show_profile_editor: bool,
+    show_folders: bool,
+    show_launcher_options: bool,
+    show_launches: bool,
+    /// The launch history, newest first.
+    launches: Vec<LaunchRecord>,
+    /// Text the launch list is filtered by.
+    launch_filter: String,
+    failed_launches_only: bool,
+    /// The folder typed into the folders window, not yet added.
+    new_folder: String,
+}
+
+impl Default for ReviveHelperApp {
+    fn default() -> Self {
+        let user = InvokingUser::current();
+        let mut slf = Self {
+            is_active: false,
+            hooks: Vec::new(),
+            revive: None,
+            config_error: None,
+            profiles: CondorProfileSet::default(),
+            settings: user.load_settings(),
+            backups: user.backups(),
+            user,
+            versions: Vec::new(),
+            graphics_keys: Vec::new(),
+            status_msg: "Initializing...".to_string(),
+            logs: String::new(),
+            show_logs: false,
+            show_backups: false,
+            show_profile_editor: false,
+            show_folders: false,
+            show_launcher_options: false,
+            show_launches: false,
+            launches: Vec::new(),
+            launch_filter: String::new(),
+            failed_launches_only: false,
+            new_folder: String::new(),
+        };
+        slf.recover_flatscreen_sessions();
+        slf.refresh_status();
+        slf
+    }
+}
+
+impl ReviveHelperApp {
+    fn get_setup_path(&self) -> Option<String> {
+        get_companion_exe_path(CONFIGURER_EXE_NAME)?
+            .to_str()?
+            .to_string()
+            .into()
+    }
+
+    fn refresh_status(&mut self) {
+        let config = Config::load_machine();
+        self.config_error = config.as_ref().err().map(ToString::to_string);
+        let config = config.unwrap_or_default();
+        LOGGER.set_level(config.log_level);
+        let discovered = discover_install_paths();
+        self.hooks = config
+            .hooked_exes()
+            .into_iter()
+            .map(|exe| HookStatus {
+                hooked: is_ifeo_hook_present(&exe.name),
+                enabled: exe.enabled,
+                unlisted: unlisted_installs(&exe, &discovered),
+                paths: exe.paths,
+                name: exe.name,
+            })
+            .collect();
+        self.is_active = self.hooks.iter().any(|h| h.enabled && h.hooked);
+        self.revive = config
+            .injector
+            .path
+            .or_else(|| find_revive_injector().map(PathBuf::from))
+            .map(|path| Compatibility::of_injector(&path));
+        self.status_msg = if self.is_active {
+            "Condor will launch with Revive.".to_string()
+        } else {
+            "Condor will launch without Revive.".to_string()
+        };
+
+        let history = LaunchHistory::open_default();
+        if let Err(e) = history.trim() {
+            LOGGER.warn(&format!("Failed to trim the launch history: {}", e));
+        }
+        self.launches = history.load();
+
+        // Pilot status
+        self.profiles = self.user.profiles(&self.settings);
+        let mut versions: Vec<VersionGroup> = Vec::new();
+        for dir in &self.profiles.data_dirs {
+            let status = DataDirStatus {
+                name: dir.name.clone(),
+                pilots: dir
+                    .setup_files()
+                    .into_iter()
+                    .map(|file| PilotStatus {
+                        vr_enabled: read_vr_enabled(&file.path),
+                        selected: self.settings.vr_selected(&file.path),
+                        file,
+                    })
+                    .collect(),
+            };
+            match versions.iter_mut().find(|g| g.version == dir.version) {
+                Some(group) => group.data_dirs.push(status),
+                None => versions.push(VersionGroup {
+                    version: dir.version,
+                    condor_exe: dir.version.find_condor_exe(),
+                    data_dirs: vec![status],
+                }),
+            }
+        }
+        versions.sort_by_key(|g| g.version);
+        self.versions = versions;
+
+        let mut keys: Vec<String> = Vec::new();
+        for file in self.profiles.setup_files() {
+            let contents = std::fs::read(&file.path).unwrap_or_default();
+            for key in profile_candidates(&contents) {
+                if !keys.iter().any(|k| k.eq_ignore_ascii_case(&key)) {
+                    keys.push(key);
+                }
+            }
+        }
+        keys.sort_by_key(|k| k.to_ascii_lowercase());
+        self.graphics_keys = keys;
+    }
+
+    /// Adds or removes a data directory or search folder, then rescans.
+    fn edit_folders(&mut self, edit: FolderEdit) {
+        let changed = match &edit {
+            FolderEdit::AddDataDir(path) | FolderEdit::AddSearchRoot(path) if !path.is_dir() => {
+                self.logs.push_str(&format!("Folder not found: {}\n", path.display()));
+                self.show_logs = true;
+                false
+            }
+            FolderEdit::AddDataDir(path) => self.settings.add_data_dir(path),
+            FolderEdit::AddSearchRoot(path) => self.settings.add_search_root(path),
+            FolderEdit::RemoveDataDir(path) => self.settings.remove_data_dir(path),
+            FolderEdit::RemoveSearchRoot(path) => self.settings.remove_search_root(path),
+        };
+        if changed {
+            self.new_folder.clear();
+            self.save_settings();
+            self.refresh_status();
+        }
+    }
+
+    fn save_settings(&mut self) {
+        if let Err(e) = self.user.save_settings(&self.settings) {
+            self.logs.push_str(&format!("Failed to save settings: {}\n", e));
+            self.show_logs = true;
+        }
+    }
+
+    /// Remembers the VR choice for some Setup.ini files and applies it if VR is currently enabled.
+    fn select_vr(&mut self, files: Vec<SetupFile>, selected: bool) {
+        let results = select_vr_for(&mut self.settings, &self.backups, &files, selected, self.is_active);
+        self.save_settings();
+
+        self.log_setup_results(results);
+        self.refresh_status();
+    }
+
+    /// Switches Setup.ini back to VR for flatscreen launches whose launcher was killed before it could.
+    fn recover_flatscreen_sessions(&mut self) {
+        let running: Vec<u32> = list_processes().iter().map(|p| p.pid).collect();
+        let dir = FlatscreenSession::default_dir();
+        let results = FlatscreenSession::recover(&dir, |pid| running.contains(&pid), &mut self.settings, &self.backups);
+        if !results.is_empty() {
+            self.logs.push_str("Switching Setup.ini back to VR after an unfinished flatscreen launch.\n");
+            self.save_settings();
+            self.log_setup_results(results);
+        }
+    }
+
+    fn log_setup_results(&mut self, results: Vec<SetupIniOutcome>) {
+        for outcome in results {
+            if !outcome.is_ok() {
+                show_error(&outcome.to_string());
+                self.show_logs = true;
+            }
+            self.logs.push_str(&format!("{}\n", outcome));
+        }
+    }
+
+    fn restore_backup(&mut self, backup: Backup, file: SetupFile) {
+        match self.backups.restore(&backup, &file) {
+            Ok(()) => self.logs.push_str(&format!(
+                "Restored {} from backup taken {}.\n",
+                file.label(),
+                backup.timestamp()
+            )),
+            Err(e) => {
+                let err_msg = format!("Failed to restore {}: {}", file.label(), e);
+                show_error(&err_msg);
+                self.logs.push_str(&format!("{}\n", err_msg));
+                self.show_logs = true;
+            }
+        }
+        self.refresh_status();
+    }
+
+    /// Shows what toggling would change by running the configurer with `--dry-run`.
+    /// This needs no elevation because nothing is modified.
+    fn preview_changes(&mut self) {
+        self.refresh_status();
+        self.logs.clear();
+        self.show_logs = true;
+
+        let Some(setup_path) = self.get_setup_path() else {
+            self.logs.push_str("Could not find the configurer next to this program.\n");
+            return;
+        };
+        let action = if self.is_active { "deactivate" } else { "activate" };
+
+        let mut cmd = std::process::Command::new(setup_path);
+        cmd.args([action, "--dry-run"]).args(self.user.to_args());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+
+        match cmd.output() {
+            Ok(output) => {
+                self.logs.push_str(&String::from_utf8_lossy(&output.stdout));
+                self.logs.push_str(&String::from_utf8_lossy(&output.stderr));
+            }
+            Err(e) => self.logs.push_str(&format!("Failed to preview changes: {}\n", e)),
+        }
+    }
+
+    fn toggle_hook(&mut self) {
+        // Refresh status first to ensure we have the latest pilot list and hook state
+        self.refresh_status();
+
+        self.logs.clear();
+        self.show_logs = false;
+
+        let mut configurer_success = false;
+        
+        if let Some(setup_path) = self.get_setup_path() {
+            let action = if self.is_active {
+                "deactivate"
+            } else {
+                "activate"
+            };
+
+            // Use native Windows API ShellExecuteExW to trigger UAC elevation
+            let mut sei = SHELLEXECUTEINFOW {
+                cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+                fMask: SEE_MASK_NOCLOSEPROCESS,
+                lpVerb: windows::core::w!("runas"),
+                nShow: SW_HIDE.0,
+                ..Default::default()
+            };
+            
+            let path_w = HSTRING::from(&setup_path);
+            sei.lpFile = PCWSTR(path_w.as_ptr());
+            
+            // Pass our identity and folders, as the administrator approving the prompt may be someone else.
+            let mut params = vec![action.to_string()];
+            params.extend(self.user.to_args());
+            let action_w = HSTRING::from(join_args(&params));
+            sei.lpParameters = PCWSTR(action_w.as_ptr());
+
+            let success = unsafe { ShellExecuteExW(&mut sei) }.is_ok();
+            let mut configurer_pid = None;
+
+            if success {
+                unsafe {
+                    configurer_pid = Some(GetProcessId(sei.hProcess));
+                    let _ = WaitForSingleObject(sei.hProcess, INFINITE);
+                    let mut exit_code = 0u32;
+                    let _ = GetExitCodeProcess(sei.hProcess, &mut exit_code);
+                    let _ = CloseHandle(sei.hProcess);
+
+                    if exit_code == 0 {
+                        LOGGER.info(&format!("Successfully executed setup with action: {}", action));
+                        self.logs.push_str(&format!("Successfully executed setup with action: {}\n", action));
+                        configurer_success = true;
+                    } else {
+                        LOGGER.error(&format!("Setup exited with error status: {}", exit_code));
+                        self.logs.push_str(&format!("Setup exited with error status: {}\n", exit_code));
+                        self.show_logs = true;
+                    }
+                }
+            } else {
+                let err = std::io::Error::last_os_error();
+                LOGGER.error(&format!("Failed to execute setup: {}", err));
+                self.logs.push_str(&format!("Failed to execute setup: {}\n", err));
+                self.show_logs = true;
+            }
+
+            // Show what this run of the configurer logged
+            let records: Vec<_> = read_records(&Logger::default_path(Component::Configurer))
+                .into_iter()
+                .filter(|r| Some(r.pid) == configurer_pid)
+                .collect();
+            if !records.is_empty() {
+                self.logs.push_str("\n--- Setup Logs ---\n");
+                for record in &records {
+                    self.logs.push_str(&format!("{:<5} {}\n", record.level, record.message));
+                }
+                if records.iter().any(|r| r.level == LogLevel::Error) {
+                    self.show_logs = true;
+                    configurer_success = false; // Override success if the configurer logged errors
+                }
+            }
+        }
+
+        // The configurer switched our Setup.ini files and saved the graphics snapshots it took.
+        self.settings = self.user.load_settings();
+        if !configurer_success {
+            self.logs.push_str("\nSome Setup.ini files may not have been updated because the configuration failed.\n");
+        }
+
+        self.refresh_status();
+    }
+}
+
+impl eframe::App for ReviveHelperApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Condor3 Revive Helper");
+            ui.add_space(10.0);
+
+            let action_verb = if self.is_active { "disable" } else { "enable" };
+            ui.add(egui::Label::new(format!(
+                "Welcome to the Revive Helper for Condor3 (and Condor2).\n\nYou can choose to {} VR for Condor below. It will take effect whenever you launch Condor, including from the Server List.",
+                action_verb
+            )).wrap());
+            ui.add_space(20.0);
+            
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                ui.label("Current status: Condor will launch ");
+                if self.is_active {
+                    ui.label(egui::RichText::new("with Revive").color(egui::Color32::GREEN));
+                } else {
+                    ui.label(egui::RichText::new("without Revive").color(egui::Color32::RED));
+                }
+                ui.label(".");
+            });
+            match &self.revive {
+                Some(revive) if revive.is_supported() => {
+                    ui.label(egui::RichText::new(format!("Using {}.", revive)).weak());
+                }
+                Some(revive @ Compatibility::TooOld(_)) => {
+                    ui.label(egui::RichText::new(format!("{}.", revive)).color(egui::Color32::RED));
+                }
+                Some(revive) => {
+                    ui.label(egui::RichText::new(format!("Warning: {}.", revive)).color(egui::Color32::ORANGE));
+                }
+                None => {
+                    ui.label(egui::RichText::new("Revive was not found. Please install Revive to use VR.").color(egui::Color32::RED));
+                }
+            }
+            if let Some(error) = &self.config_error {
+                ui.label(egui::RichText::new(format!("Settings problem: {}.", error)).color(egui::Color32::RED));
+            }
+            if self.hooks.len() > 1 {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Hooked programs:");
+                    for hook in &self.hooks {
+                        let text = match (hook.enabled, hook.hooked) {
+                            (false, _) => egui::RichText::new(format!("{} (disabled)", hook.name)).weak(),
+                            (true, true) => egui::RichText::new(&hook.name).color(egui::Color32::GREEN),
+                            (true, false) => egui::RichText::new(&hook.name).color(egui::Color32::RED),
+                        };
+                        let label = ui.label(text);
+                        if !hook.paths.is_empty() {
+                            let paths: Vec<String> = hook.paths.iter().map(|p| p.display().to_string()).collect();
+                            label.on_hover_text(format!("Only hooked at:\n{}", paths.join("\n")));
+                        }
+                    }
+                });
+            }
+            for hook in self.hooks.iter().filter(|h| h.enabled && h.hooked) {
+                for path in &hook.unlisted {
+                    let text = format!(
+                        "{} isn't routed through the helper. Deactivate and activate VR to include it.",
+                        path.display()
+                    );
+                    ui.label(egui::RichText::new(text).color(egui::Color32::ORANGE));
+                }
+            }
+            ui.add_space(10.0);
+
+            let mut selection_change: Option<(Vec<SetupFile>, bool)> = None;
+            ui.group(|ui| {
+                ui.set_min_height(100.0);
+                ui.label(egui::RichText::new("Condor Settings & Pilots:").strong());
+                if self.versions.iter().flat_map(|g| &g.data_dirs).all(|d| d.pilots.is_empty()) {
+                    ui.label(egui::RichText::new("No Setup.ini files found in any Documents/Condor* directories. Use Folders to add yours.").weak());
+                } else {
+                    egui::ScrollArea::vertical().id_salt("pilot_scroll").show(ui, |ui| {
+                        for group in &self.versions {
+                            if group.data_dirs.iter().all(|d| d.pilots.is_empty()) {
+                                continue;
+                            }
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new(group.version.to_string()).heading());
+                                match &group.condor_exe {
+                                    Some(exe) => ui.label(egui::RichText::new(exe.display().to_string()).weak()),
+                                    None => ui.label(egui::RichText::new("(installation not found)").weak()),
+                                };
+                            });
+                            for dir in &group.data_dirs {
+                                if dir.pilots.is_empty() {
+                                    continue;
+                                }
+
+                                let selected_count = dir.pilots.iter().filter(|p| p.selected).count();
+                                let mut all_selected = selected_count == dir.pilots.len();
+                                let mixed = selected_count > 0 && !all_selected;
+                                if ui
+                                    .add(egui::Checkbox::new(&mut all_selected, egui::RichText::new(&dir.name).strong()).indeterminate(mixed))
+                                    .changed()
+                                {
+                                    let files = dir.pilots.iter().map(|p| p.file.clone()).collect();
+                                    selection_change = Some((files, all_selected));
+                                }
+
+                                ui.indent(&dir.name, |ui| {
+                                    for pilot in &dir.pilots {
+                                        ui.horizontal(|ui| {
+                                            let mut selected = pilot.selected;
+                                            if ui.checkbox(&mut selected, format!("{}:", pilot.file.label())).changed() {
+                                                selection_change = Some((vec![pilot.file.clone()], selected));
+                                            }
+                                            if pilot.vr_enabled {
+                                                ui.label(egui::RichText::new("VR Enabled").color(egui::Color32::GREEN));
+                                            } else {
+                                                ui.label(egui::RichText::new("VR Disabled").color(egui::Color32::RED));
+                                            }
+                                        });
+                                    }
+                                });
+                            }
+                        }
+                    });
+                }
+            });
+            if let Some((files, selected)) = selection_change {
+                self.select_vr(files, selected);
+            }
+
+            ui.add_space(10.0);
+
+            let button_text = if self.is_active { "Disable VR" } else { "Enable VR" };
+            let (mut toggle_clicked, mut preview_clicked) = (false, false);
+            ui.horizontal(|ui| {
+                toggle_clicked = ui.add_sized([120.0, 40.0], egui::Button::new(button_text)).clicked();
+                preview_clicked = ui.add_sized([120.0, 40.0], egui::Button::new("Preview changes")).clicked();
+            });
+            if toggle_clicked {
+                self.toggle_hook();
+            } else if preview_clicked {
+                self.preview_changes();
+            }
+            ui.add_space(10.0);
+            ui.label(egui::RichText::new("Tip: Toggling the VR setting will open a permission dialog and update the ticked pilots' Setup.ini.").weak());
+
+            if !self.logs.is_empty() {
+                ui.add_space(10.0);
+                if ui.button("View Execution Logs").clicked() {
+                    self.show_logs = true;
+                }
+            }
+
+            if self.show_logs {
+                let mut is_open = self.show_logs;
+                let mut clear_clicked = false;
+                egui::Window::new("Execution Logs")
+                    .open(&mut is_open)
+                    .default_size([600.0, 400.0])
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        egui::ScrollArea::vertical()
+                            .id_salt("log_scroll")
+                            .stick_to_bottom(true)
+                            .show(ui, |ui| {
+                                ui.add(
+                                    egui::Label::new(egui::RichText::new(&self.logs).monospace())
+                                        .wrap()
+                                );
+                            });
+                        ui.add_space(10.0);
+                        if ui.button("Clear & Close").clicked() {
+                            clear_clicked = true;
+                        }
+                    });
+                
+                if clear_clicked {
+                    self.logs.clear();
+                    self.show_logs = false;
+                } else {
+                    self.show_logs = is_open;
+                }
+            }
+
+            if self.show_backups {
+                let mut is_open = self.show_backups;
+                let mut restore: Option<(Backup, SetupFile)> = None;
+                egui::Window::new("Restore Setup.ini Backups")
+                    .open(&mut is_open)
+                    .default_size([500.0, 350.0])
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        ui.label("A backup is saved each time the helper changes a Setup.ini.");
+                        ui.add_space(5.0);
+                        egui::ScrollArea::vertical().id_salt("backup_scroll").show(ui, |ui| {
+                            for pilot in self.versions.iter().flat_map(|g| &g.data_dirs).flat_map(|d| &d.pilots) {
+                                let backups = self.backups.list(&pilot.file);
+                                ui.collapsing(format!("{} ({})", pilot.file.label(), backups.len()), |ui| {
+                                    if backups.is_empty() {
+                                        ui.label(egui::RichText::new("No backups yet.").weak());
+                                    }
+                                    for backup in backups {
+                                        ui.horizontal(|ui| {
+                                            ui.label(backup.timestamp());
+                                            if ui.button("Restore").clicked() {
+                                                restore = Some((backup.clone(), pilot.file.clone()));
+                                            }
+                                        });
+                                    }
+                                });
+                            }
+                        });
+                    });
+                self.show_backups = is_open;
+                if let Some((backup, file)) = restore {
+                    self.restore_backup(backup, file);
+                }
+            }
+
+            if self.show_profile_editor {
+                let mut is_open = self.show_profile_editor;
+                let mut new_keys: Option<Vec<String>> = None;
+                egui::Window::new("Graphics Profile")
+                    .open(&mut is_open)
+                    .default_size([400.0, 400.0])
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        ui.add(egui::Label::new(
+                            "Ticked [Graphics] settings are saved separately for VR and flatscreen. \
+                             Each time VR is toggled, the values for the mode being left are saved and \
+                             the values last used in the other mode are put back.",
+                        ).wrap());
+                        ui.add_space(5.0);
+                        egui::ScrollArea::vertical().id_salt("profile_scroll").show(ui, |ui| {
+                            if self.graphics_keys.is_empty() {
+                                ui.label(egui::RichText::new("No [Graphics] settings found.").weak());
+                            }
+                            let current = self.settings.profile_keys();
+                            for key in &self.graphics_keys {
+                                let mut ticked = current.iter().any(|k| k.eq_ignore_ascii_case(key));
+                                if ui.checkbox(&mut ticked, key).changed() {
+                                    let mut keys: Vec<String> = current
+                                        .iter()
+                                        .filter(|k| !k.eq_ignore_ascii_case(key))
+                                        .cloned()
+                                        .collect();
+                                    if ticked {
+                                        keys.push(key.clone());
+                                    }
+                                    new_keys = Some(keys);
+                                }
+                            }
+                        });
+                    });
+                self.show_profile_editor = is_open;
+                if let Some(keys) = new_keys {
+                    self.settings.set_profile_keys(keys);
+                    self.save_settings();
+                }
+            }
+
+            if self.show_folders {
+                let mut is_open = self.show_folders;
+                let mut folder_edit: Option<FolderEdit> = None;
+                egui::Window::new("Condor Folders")
+                    .open(&mut is_open)
+                    .default_size([500.0, 350.0])
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        ui.add(egui::Label::new(
+                            "Condor* folders in Documents are found automatically. Add a data directory \
+                             if yours is elsewhere (e.g. another drive), or a search folder to scan it for Condor* folders.",
+                        ).wrap());
+                        ui.add_space(5.0);
+                        if let Some(docs) = default_documents_dir() {
+                            ui.label(format!("Documents: {}", docs.display()));
+                        }
+
+                        ui.label(egui::RichText::new("Search folders:").strong());
+                        if self.settings.search_roots().is_empty() {
+                            ui.label(egui::RichText::new("None").weak());
+                        }
+                        for root in self.settings.search_roots() {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("Remove").clicked() {
+                                    folder_edit = Some(FolderEdit::RemoveSearchRoot(root.clone()));
+                                }
+                                ui.label(root.display().to_string());
+                            });
+                        }
+
+                        ui.label(egui::RichText::new("Data directories:").strong());
+                        if self.settings.data_dirs().is_empty() {
+                            ui.label(egui::RichText::new("None").weak());
+                        }
+                        for dir in self.settings.data_dirs() {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("Remove").clicked() {
+                                    folder_edit = Some(FolderEdit::RemoveDataDir(dir.clone()));
+                                }
+                                ui.label(dir.display().to_string());
+                            });
+                        }
+
+                        ui.add_space(5.0);
+                        ui.text_edit_singleline(&mut self.new_folder);
+                        ui.horizontal(|ui| {
+                            let folder = PathBuf::from(self.new_folder.trim());
+                            let enabled = !self.new_folder.trim().is_empty();
+                            if ui.add_enabled(enabled, egui::Button::new("Add data directory")).clicked() {
+                                folder_edit = Some(FolderEdit::AddDataDir(folder.clone()));
+                            }
+                            if ui.add_enabled(enabled, egui::Button::new("Add search folder")).clicked() {
+                                folder_edit = Some(FolderEdit::AddSearchRoot(folder));
+                            }
+                        });
+                    });
+                self.show_folders = is_open;
+                if let Some(edit) = folder_edit {
+                    self.edit_folders(edit);
+                }
+            }
+
+            if self.show_launcher_options {
+                let mut is_open = self.show_launcher_options;
+                let mut timeout = self.settings.launch_choice_timeout();
+                let mut default_mode = self.settings.default_launch_mode();
+                let mut remember = self.settings.remember_launch_mode();
+                let mut disables_vr = self.settings.flatscreen_disables_vr();
+                let mut changed = false;
+                egui::Window::new("Launcher Options")
+                    .open(&mut is_open)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        ui.add(egui::Label::new(
+                            "While VR is enabled, the launcher asks whether to start Condor in VR or on the \
+                             monitor, picking the default if nobody answers. Holding Shift as Condor starts \
+                             always launches flatscreen.",
+                        ).wrap());
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Ask for");
+                            changed |= ui.add(egui::DragValue::new(&mut timeout).range(0..=60).suffix(" s")).changed();
+                            ui.label("then launch in");
+                            changed |= ui.radio_value(&mut default_mode, GraphicsMode::Vr, "VR").changed();
+                            changed |= ui.radio_value(&mut default_mode, GraphicsMode::Flatscreen, "flatscreen").changed();
+                        });
+                        changed |= ui
+                            .checkbox(&mut remember, "Default to the last choice for each program instead")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut disables_vr, "Turn VR off in Setup.ini until Condor exits")
+                            .changed();
+                    });
+                self.show_launcher_options = is_open;
+                if changed {
+                    self.settings.set_launch_choice_timeout(timeout);
+                    self.settings.set_default_launch_mode(default_mode);
+                    self.settings.set_remember_launch_mode(remember);
+                    self.settings.set_flatscreen_disables_vr(disables_vr);
+                    self.save_settings();
+                }
+            }
+
+            if self.show_launches {
+                let mut is_open = self.show_launches;
+                let mut reload = false;
+                egui::Window::new("Recent Launches")
+                    .open(&mut is_open)
+                    .default_size([560.0, 380.0])
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Filter:");
+                            ui.text_edit_singleline(&mut self.launch_filter);
+                            ui.checkbox(&mut self.failed_launches_only, "Failures only");
+                            if ui.button("Reload").clicked() {
+                                reload = true;
+                            }
+                        });
+                        ui.add_space(5.0);
+                        let shown: Vec<&LaunchRecord> = self
+                            .launches
+                            .iter()
+                            .filter(|r| (!self.failed_launches_only || r.is_failure()) && r.matches(&self.launch_filter))
+                            .collect();
+                        if shown.is_empty() {
+                            ui.label(egui::RichText::new("No launches to show.").weak());
+                        }
+                        egui::ScrollArea::vertical().id_salt("launch_scroll").show(ui, |ui| {
+                            for record in shown {
+                                let (outcome, color) = match record.outcome {
+                                    LaunchOutcome::Launched => ("launched", egui::Color32::GREEN),
+                                    LaunchOutcome::Failed => ("failed", egui::Color32::RED),
+                                };
+                                let mode = record.mode.map(|m| format!(" in {}", m)).unwrap_or_default();
+                                let title = format!("{}  {}{}: {}", record.timestamp(), record.exe_name(), mode, outcome);
+                                egui::CollapsingHeader::new(egui::RichText::new(title).color(color))
+                                    .id_salt((record.started_at, record.id))
+                                    .show(ui, |ui| {
+                                        if let Some(error) = &record.error {
+                                            ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+                                        }
+                                        ui.label(format!("Target: {}", record.target.display()));
+                                        if !record.args.is_empty() {
+                                            ui.label(format!("Arguments: {}", join_args(&record.args)));
+                                        }
+                                        if let Some(injector) = &record.injector_path {
+                                            ui.label(format!("Injector: {}", injector.display()));
+                                        }
+                                        for stage in &record.stages {
+                                            ui.label(format!("{}: {:.1} s", stage.name, stage.millis as f64 / 1000.0));
+                                        }
+                                        match (record.ran_for_secs, record.exit_code) {
+                                            (Some(secs), Some(code)) => {
+                                                ui.label(format!(
+                                                    "Ran for {}:{:02}:{:02}, exit code {}",
+                                                    secs / 3600,
+                                                    secs / 60 % 60,
+                                                    secs % 60,
+                                                    exit_code_text(code)
+                                                ));
+                                            }
+                                            _ if !record.is_failure() => {
+                                                ui.label(egui::RichText::new("Still running, or the launcher was closed first.").weak());
+                                            }
+                                            _ => {}
+                                        }
+                                    });
+                            }
+                        });
+                    });
+                self.show_launches = is_open;
+                if reload {
+                    self.launches = LaunchHistory::open_default().load();
+                }
+            }
+
+            ui.with_layout(egui::Layout::bottom_up(egui::Align::RIGHT), |ui| {
+                ui.add_space(10.0);
+                if ui.add_sized([80.0, 30.0], egui::Button::new("Exit")).clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+                ui.add_space(5.0);
+                if ui.add_sized([80.0, 30.0], egui::Button::new("Refresh")).clicked() {
+                    self.refresh_status();
+                }
+                ui.add_space(5.0);
+                if ui.add_sized([80.0, 30.0], egui::Button::new("Backups")).clicked() {
+                    self.show_backups = true;
+                }
+                ui.add_space(5.0);
+                if ui.add_sized([80.0, 30.0], egui::Button::new("Graphics")).clicked() {
+                    self.show_profile_editor = true;
+                }
+                ui.add_space(5.0);
+                if ui.add_sized([80.0, 30.0], egui::Button::new("Folders")).clicked() {
+                    self.show_folders = true;
+                }
+                ui.add_space(5.0);
+                if ui.add_sized([80.0, 30.0], egui::Button::new("Launcher")).clicked() {
+                    self.show_launcher_options = true;
+                }
+                ui.add_space(5.0);
+                if ui.add_sized([80.0, 30.0], egui::Button::new("Launches")).clicked() {
+                    self.launches = LaunchHistory::open_default().load();
+                    self.show_launches = true;
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,293 @@
+//! This service allows the IFEO registry key, which makes Condor.exe defer to our launcher, to be 
+//! deleted while the launcher runs and then re-enabled after Condor is launched via ReviveInjector. 
+//! This prevents an infinite loop of the launcher being executed. 
+//!
+//! Once started, the service waits for the launcher on its named pipe (see `bypass_protocol`) and
+//! lifts the hook of the executable it asks for; only names in the configured list are accepted.
+//! The hook is put back once the program starts, the launcher exits or
+//! `timeouts.bypass_lease_secs` pass, and the launcher is told.
+//!
+//! With `[service] mode = "demand"` the service handles a single launcher and stops. With
+//! `"persistent"` it starts with Windows and keeps running, queuing requests and handling them one
+//! at a time (see `bypass_queue`), so each hook is only lifted for its own request's lease.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use windows_service::{
+    define_windows_service,
+    service::{
+        ServiceControl, ServiceStatus, ServiceType,
+    },
+    service_control_handler::{self, ServiceControlHandlerResult},
+    service_dispatcher,
+};
+
+use condor3_revive_helper::bypass_lease::{BypassLease, LeaseEnd};
+use condor3_revive_helper::bypass_protocol::{self, BypassRequest, ErrorCode, ProtocolError};
+use condor3_revive_helper::bypass_queue::BypassQueue;
+use condor3_revive_helper::config::ServiceMode;
+use condor3_revive_helper::hooked_exe::{find, load_hooked_exes};
+use condor3_revive_helper::ifeo::{self, LocalMachine};
+use condor3_revive_helper::process_watch::{ProcessEntry, list_processes};
+use condor3_revive_helper::{
+    get_companion_exe_path, handle_version_args, Component, Config, Logger, LAUNCHER_EXE_NAME, SERVICE_NAME,
+};
+
+define_windows_service!(ffi_service_main, service_main);
+
+static LOGGER: LazyLock<Logger> = LazyLock::new(|| Logger::new(Component::Service));
+
+pub fn main() -> Result<(), windows_service::Error> {
+    if handle_version_args("CondorReviveHelperService") {
+        return Ok(());
+    }
+
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+}
+
+fn service_main(_arguments: Vec<std::ffi::OsString>) {
+    // Still answer launchers with a bad config, so they can say why instead of timing out.
+    let config = Config::load_machine().unwrap_or_else(|e| {
+        LOGGER.error(&format!("{}. Every request will be refused until it is fixed.", e));
+        Config::default()
+    });
+    LOGGER.set_level(config.log_level);
+    LOGGER.info("Service started.");
+    if let Err(e) = run_service(&config) {
+        LOGGER.error(&e.to_string());
+    }
+}
+
+/// Anyone may start the service, so only ever touch hooks we installed ourselves. The config is
+/// read for each request, so fixing it takes effect without restarting the service.
+fn hooked_exe_name(requested_exe: &str) -> Result<String, ErrorCode> {
+    let exes = load_hooked_exes().map_err(|e| {
+        LOGGER.error(&format!("Refusing to lift any hook: {}.", e));
+        ErrorCode::ConfigInvalid
+    })?;
+    match find(&exes, requested_exe) {
+        Some(exe) if exe.enabled => Ok(exe.name.clone()),
+        _ => {
+            LOGGER.warn(&format!("{} is not a hooked executable.", requested_exe));
+            Err(ErrorCode::NotHooked)
+        }
+    }
+}
+
+fn run_service(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    let event_handler = move |control_event| -> ServiceControlHandlerResult {
+        match control_event {
+            ServiceControl::Stop => {
+                r.store(false, Ordering::SeqCst);
+                // Let a wait for the launcher see the stop, without holding up the control handler.
+                thread::spawn(bypass_protocol::wake);
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        }
+    };
+
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)?;
+    let set_state = |state, exit_code| {
+        status_handle.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == windows_service::service::ServiceState::Running {
+                windows_service::service::ServiceControlAccept::STOP
+            } else {
+                windows_service::service::ServiceControlAccept::empty()
+            },
+            exit_code: windows_service::service::ServiceExitCode::Win32(exit_code),
+            checkpoint: 0,
+            wait_hint: Duration::default(),
+            process_id: None,
+        })
+    };
+
+    set_state(windows_service::service::ServiceState::Running, 0)?;
+    let result = match config.service.mode {
+        ServiceMode::Demand => serve_one_launcher(config, &running).map(|_| ()),
+        ServiceMode::Persistent => serve_launchers(config, &running),
+    };
+    let exit_code = match &result {
+        Ok(_) => 0,
+        Err(_) => 1,
+    };
+    set_state(windows_service::service::ServiceState::Stopped, exit_code)?;
+    result
+}
+
+/// Waits for a launcher to connect, giving up after as long as it would wait for us, and handles its
+/// request.
+fn serve_one_launcher(config: &Config, running: &Arc<AtomicBool>) -> Result<Option<LeaseEnd>, Box<dyn std::error::Error>> {
+    let connected = Arc::new(AtomicBool::new(false));
+    let patience = config.timeouts.service_start() + config.timeouts.hook_removal();
+    {
+        let (connected, running) = (connected.clone(), running.clone());
+        thread::spawn(move || {
+            thread::sleep(patience);
+            if !connected.load(Ordering::SeqCst) {
+                running.store(false, Ordering::SeqCst);
+                bypass_protocol::wake();
+            }
+        });
+    }
+
+    let mut connection = bypass_protocol::accept(true)?;
+    connected.store(true, Ordering::SeqCst);
+    if !running.load(Ordering::SeqCst) {
+        LOGGER.warn("No launcher connected before the service stopped.");
+        return Ok(None);
+    }
+
+    let deadline = connection.read_deadline(config.timeouts.hook_removal());
+    let received = bypass_protocol::receive_request(&mut connection.reader, &mut connection.writer, connection.peer_pid);
+    drop(deadline);
+
+    let lease_length = config.timeouts.bypass_lease();
+    let before_lift = Mutex::new(Vec::new());
+    let lift = |request: &BypassRequest| lift_hook(request).map(|processes| *before_lift.lock().unwrap() = processes);
+    let hold = |request: &BypassRequest| {
+        let before = std::mem::take(&mut *before_lift.lock().unwrap());
+        hold_hook(request, &before, lease_length, running)
+    };
+    match received.and_then(|request| bypass_protocol::handle_request(&mut connection.writer, &request, lift, hold)) {
+        Ok(end) => Ok(Some(end)),
+        Err(ProtocolError::Refused(code)) => Err(format!("request refused: {}", code).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Keeps accepting launchers until the service is stopped, queuing their requests so that only one
+/// hook is lifted at a time.
+fn serve_launchers(config: &Config, running: &Arc<AtomicBool>) -> Result<(), Box<dyn std::error::Error>> {
+    let lease_length = config.timeouts.bypass_lease();
+    let read_timeout = config.timeouts.hook_removal();
+    let before_lift = Arc::new(Mutex::new(Vec::new()));
+    let lift = {
+        let running = running.clone();
+        let before_lift = before_lift.clone();
+        move |request: &BypassRequest| {
+            // Requests still queued when the service is told to stop are turned away.
+            if !running.load(Ordering::SeqCst) {
+                return Err(ErrorCode::Stopping);
+            }
+            lift_hook(request).map(|processes| *before_lift.lock().unwrap() = processes)
+        }
+    };
+    let hold = {
+        let running = running.clone();
+        move |request: &BypassRequest| {
+            let before = std::mem::take(&mut *before_lift.lock().unwrap());
+            hold_hook(request, &before, lease_length, &running)
+        }
+    };
+    let done = |request: &BypassRequest, result: Result<LeaseEnd, ProtocolError>| {
+        if let Err(e) = result {
+            LOGGER.warn(&format!("The request of launcher (PID {}) failed: {}.", request.pid, e));
+        }
+    };
+    let (queue, worker) = BypassQueue::start(lift, hold, done);
+    let queue = Arc::new(queue);
+
+    let mut first = true;
+    loop {
+        let connection = bypass_protocol::accept(first)?;
+        first = false;
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        // Read the request off the accept loop, so a slow launcher can't hold up the others, and give
+        // up on one that says nothing.
+        let queue = queue.clone();
+        thread::spawn(move || {
+            let mut connection = connection;
+            let deadline = connection.read_deadline(read_timeout);
+            let received =
+                bypass_protocol::receive_request(&mut connection.reader, &mut connection.writer, connection.peer_pid);
+            drop(deadline);
+            match received {
+                Ok(request) => {
+                    let ahead = queue.push(request, connection.writer);
+                    if ahead > 0 {
+                        LOGGER.info(&format!("Queued a request behind {} other(s).", ahead));
+                    }
+                }
+                Err(ProtocolError::Closed) => {}
+                Err(e) => LOGGER.warn(&format!("Ignored a request: {}.", e)),
+            }
+        });
+    }
+
+    drop(queue);
+    let _ = worker.join();
+    Ok(())
+}
+
+/// Lifts the hook, returning the processes that were running just before, so copies of the
+/// program that were already open aren't mistaken for the one the launcher starts.
+fn lift_hook(request: &BypassRequest) -> Result<Vec<ProcessEntry>, ErrorCode> {
+    LOGGER.info(&format!("Launcher (PID {}) asked to start {}.", request.pid, request.target));
+    let name = hooked_exe_name(&request.target)?;
+    let processes = list_processes();
+    delete_ifeo_hook(&name).map_err(|e| {
+        LOGGER.error(&format!("Failed to lift the hook of {}: {}", name, e));
+        ErrorCode::HookNotLifted
+    })?;
+    LOGGER.info(&format!("Lifted the hook of {}.", name));
+    Ok(processes)
+}
+
+/// Waits for the lease on a lifted hook to end, then puts the hook back.
+fn hold_hook(
+    request: &BypassRequest,
+    before_lift: &[ProcessEntry],
+    lease_length: Duration,
+    running: &AtomicBool,
+) -> Result<LeaseEnd, ErrorCode> {
+    // `lift_hook` has already checked the name. Prefer the configured spelling, but a config
+    // broken since then mustn't keep the hook from going back.
+    let exe_name = hooked_exe_name(&request.target).unwrap_or_else(|_| request.target.clone());
+    let lease = BypassLease::new(&exe_name, Some(request.pid), before_lift, Instant::now(), lease_length);
+    let end = lease.wait(Instant::now, list_processes, || !running.load(Ordering::SeqCst), thread::sleep);
+
+    if end.is_abandoned() {
+        LOGGER.warn(&format!("Putting the hook of {} back because {}.", exe_name, end));
+    } else {
+        LOGGER.info(&format!("{} has started.", exe_name));
+    }
+    match restore_ifeo_hook(&exe_name) {
+        Ok(()) => {
+            LOGGER.info(&format!("Restored the hook of {}.", exe_name));
+            Ok(end)
+        }
+        Err(e) => {
+            LOGGER.error(&format!("Failed to restore the hook of {}: {}", exe_name, e));
+            Err(ErrorCode::HookNotRestored)
+        }
+    }
+}
+
+/// Deletes the `Debugger` value wherever the hook keeps it, which for a hook scoped to certain
+/// installs is each of their filter subkeys.
+fn delete_ifeo_hook(exe_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let changes = ifeo::lift_changes(&LocalMachine, exe_name);
+    ifeo::apply_all(&mut LocalMachine, &changes)?;
+    Ok(())
+}
+
+fn restore_ifeo_hook(exe_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let launcher_path = get_companion_exe_path(LAUNCHER_EXE_NAME).ok_or("Launcher not found")?;
+    let changes = ifeo::restore_changes(&LocalMachine, exe_name, &launcher_path);
+    if changes.is_empty() {
+        return Err(format!("{} has no IFEO key to put the hook back in", exe_name).into());
+    }
+    ifeo::apply_all(&mut LocalMachine, &changes)?;
+    Ok(())
+}
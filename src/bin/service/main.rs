@@ -0,0 +1,15 @@
+//! See `app` for what this program does. It only runs on Windows; elsewhere this stub lets the
+//! library and its tests build.
+
+#[cfg(windows)]
+mod app;
+
+#[cfg(windows)]
+fn main() -> Result<(), windows_service::Error> {
+    app::main()
+}
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("CondorReviveHelperService only runs on Windows.");
+}
@@ -0,0 +1,115 @@
+//! How long the service may keep an IFEO hook lifted.
+//!
+//! While the hook is gone, starting the program skips the launcher and so runs without VR. The
+//! service therefore only lifts it for a lease, which ends as soon as the program starts, the
+//! launcher that asked for it exits, or the lease runs out, whichever happens first. A copy of the
+//! program that was already running when the hook was lifted doesn't count as it starting.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::LAUNCHER_EXE_NAME;
+use crate::process_watch::ProcessEntry;
+
+/// How often the service checks whether a lease has ended.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BypassLease {
+    pub exe_name: String,
+    /// The launcher that asked for the hook to be lifted, if it said who it was.
+    pub launcher_pid: Option<u32>,
+    /// The PIDs of copies of the program that were running before the hook was lifted.
+    pub already_running: Vec<u32>,
+    pub started: Instant,
+    pub length: Duration,
+}
+
+/// Why a lease ended, and so why the hook was put back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeaseEnd {
+    /// The program started, which is what the hook was lifted for.
+    ProgramStarted,
+    /// The launcher exited without the program starting, e.g. because the injector failed.
+    LauncherExited(u32),
+    /// The lease ran out first.
+    Expired(Duration),
+    /// The service was told to stop.
+    Stopped,
+}
+
+impl LeaseEnd {
+    /// Whether the hook was put back without the program having started.
+    pub fn is_abandoned(&self) -> bool {
+        *self != Self::ProgramStarted
+    }
+}
+
+impl fmt::Display for LeaseEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProgramStarted => write!(f, "the program started"),
+            Self::LauncherExited(pid) => write!(f, "the launcher (PID {}) exited", pid),
+            Self::Expired(length) => write!(f, "it wasn't started within {} seconds", length.as_secs()),
+            Self::Stopped => write!(f, "the service was stopped"),
+        }
+    }
+}
+
+impl BypassLease {
+    /// Starts a lease, given the processes that were running when the hook was lifted.
+    pub fn new(
+        exe_name: &str,
+        launcher_pid: Option<u32>,
+        processes: &[ProcessEntry],
+        started: Instant,
+        length: Duration,
+    ) -> Self {
+        let already_running = processes
+            .iter()
+            .filter(|p| p.exe_name.eq_ignore_ascii_case(exe_name))
+            .map(|p| p.pid)
+            .collect();
+        Self { exe_name: exe_name.to_string(), launcher_pid, already_running, started, length }
+    }
+
+    /// Whether the lease has ended at `now`, given the running processes. `None` means the hook
+    /// should stay lifted for now.
+    pub fn check(&self, now: Instant, processes: &[ProcessEntry]) -> Option<LeaseEnd> {
+        if processes
+            .iter()
+            .any(|p| p.exe_name.eq_ignore_ascii_case(&self.exe_name) && !self.already_running.contains(&p.pid))
+        {
+            return Some(LeaseEnd::ProgramStarted);
+        }
+        if let Some(pid) = self.launcher_pid
+            && !processes.iter().any(|p| p.pid == pid && p.exe_name.eq_ignore_ascii_case(LAUNCHER_EXE_NAME))
+        {
+            return Some(LeaseEnd::LauncherExited(pid));
+        }
+        if now.saturating_duration_since(self.started) >= self.length {
+            return Some(LeaseEnd::Expired(self.length));
+        }
+        None
+    }
+
+    /// Checks the lease every [`POLL_INTERVAL`] until it ends. The clock, process list and sleep are
+    /// passed in so the timing can be tried out without waiting.
+    pub fn wait(
+        &self,
+        mut now: impl FnMut() -> Instant,
+        mut processes: impl FnMut() -> Vec<ProcessEntry>,
+        mut stop_requested: impl FnMut() -> bool,
+        mut sleep: impl FnMut(Duration),
+    ) -> LeaseEnd {
+        loop {
+            if stop_requested() {
+                return LeaseEnd::Stopped;
+            }
+            if let Some(end) = self.check(now(), &processes()) {
+                return end;
+            }
+            sleep(POLL_INTERVAL);
+        }
+    }
+}
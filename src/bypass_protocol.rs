@@ -0,0 +1,532 @@
+//! The conversation between the launcher and the service over a named pipe.
+//!
+//! Each message is one JSON object on its own line, carrying the protocol version. The launcher
+//! sends a [`BypassRequest`]; a service that is busy with other launches first answers
+//! [`ServiceReply::Queued`]. It then answers [`ServiceReply::Ack`] once the hook is lifted, or
+//! [`ServiceReply::Refused`] with an [`ErrorCode`], and then [`ServiceReply::Restored`] once the
+//! hook is back. Every reply repeats the request's nonce, so a launcher can't mistake another
+//! launch's reply for its own.
+
+use std::fmt;
+use std::hash::{BuildHasher, Hasher, RandomState};
+use std::io::{self, BufRead, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::bypass_lease::LeaseEnd;
+
+pub const PIPE_NAME: &str = r"\\.\pipe\CondorReviveHelper";
+/// Bumped whenever a message changes in a way the other side can't read.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Longer messages are refused rather than read into memory.
+pub const MAX_MESSAGE_BYTES: usize = 4096;
+/// The pipe DACL: Local System and Administrators get full control, Authenticated Users may read
+/// and write data (so the launcher can ask for a bypass). `0x12008b` is `FILE_GENERIC_READ` plus
+/// `FILE_WRITE_DATA`, leaving out `FILE_CREATE_PIPE_INSTANCE`, so nobody else can add a server end.
+pub const PIPE_SDDL: &str = "D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;0x12008b;;;AU)";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BypassRequest {
+    /// The hooked executable to start, e.g. `Condor.exe`.
+    pub target: String,
+    /// The launcher's PID; the hook is put back if it exits.
+    pub pid: u32,
+    pub nonce: u64,
+}
+
+impl BypassRequest {
+    pub fn new(target: &str, pid: u32) -> Self {
+        Self { target: target.to_string(), pid, nonce: new_nonce() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServiceReply {
+    /// The request waits for `ahead` earlier launches to finish with the hook.
+    Queued { nonce: u64, ahead: usize },
+    /// The hook is lifted; start the program now.
+    Ack { nonce: u64 },
+    Refused { nonce: u64, code: ErrorCode },
+    /// The hook is back, and why.
+    Restored { nonce: u64, program_started: bool, reason: String },
+}
+
+impl ServiceReply {
+    pub fn restored(nonce: u64, end: &LeaseEnd) -> Self {
+        Self::Restored { nonce, program_started: !end.is_abandoned(), reason: end.to_string() }
+    }
+
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Self::Queued { nonce, .. }
+            | Self::Ack { nonce }
+            | Self::Refused { nonce, .. }
+            | Self::Restored { nonce, .. } => *nonce,
+        }
+    }
+}
+
+/// Why the service refused a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    UnsupportedVersion,
+    Malformed,
+    /// The target isn't an enabled executable in the config.
+    NotHooked,
+    /// The config can't be read or trusted, so no hook is lifted until it is fixed.
+    ConfigInvalid,
+    /// The PID in the request isn't the process on the other end of the pipe.
+    WrongPid,
+    HookNotLifted,
+    HookNotRestored,
+    /// The service stopped before the request's turn came.
+    Stopping,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::UnsupportedVersion => "the service speaks a different protocol version",
+            Self::Malformed => "the request couldn't be read",
+            Self::NotHooked => "the program isn't a hooked executable",
+            Self::ConfigInvalid => "the helper's config.toml is invalid or untrusted; run the configurer to fix it",
+            Self::WrongPid => "the request came from a different process than it claims",
+            Self::HookNotLifted => "the IFEO hook couldn't be removed",
+            Self::HookNotRestored => "the IFEO hook couldn't be put back",
+            Self::Stopping => "the service is stopping",
+        };
+        f.write_str(text)
+    }
+}
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(io::Error),
+    /// The other side hung up.
+    Closed,
+    TooLong,
+    Malformed(String),
+    UnsupportedVersion(u32),
+    /// A reply that doesn't fit the conversation so far.
+    Unexpected(String),
+    Refused(ErrorCode),
+}
+
+impl ProtocolError {
+    /// The code the service answers with when a request can't be read.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::UnsupportedVersion(_) => ErrorCode::UnsupportedVersion,
+            Self::Refused(code) => *code,
+            _ => ErrorCode::Malformed,
+        }
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Closed => write!(f, "the connection was closed"),
+            Self::TooLong => write!(f, "a message was longer than {} bytes", MAX_MESSAGE_BYTES),
+            Self::Malformed(e) => write!(f, "a message couldn't be read: {}", e),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "protocol version {} isn't supported (expected {})", v, PROTOCOL_VERSION)
+            }
+            Self::Unexpected(e) => write!(f, "unexpected message: {}", e),
+            Self::Refused(code) => write!(f, "the service refused: {}", code),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl From<io::Error> for ProtocolError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u32,
+    #[serde(flatten)]
+    body: T,
+}
+
+/// A nonce that differs between launches, even ones started in the same instant.
+pub fn new_nonce() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.write_u128(std::time::SystemTime::UNIX_EPOCH.elapsed().map(|d| d.as_nanos()).unwrap_or(0));
+    hasher.finish()
+}
+
+pub fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_vec(&Envelope { version: PROTOCOL_VERSION, body: message }).map_err(io::Error::other)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// Reads one message, checking its version before anything else.
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl BufRead) -> Result<T, ProtocolError> {
+    let mut line = Vec::new();
+    reader.take(MAX_MESSAGE_BYTES as u64 + 1).read_until(b'\n', &mut line)?;
+    if line.len() > MAX_MESSAGE_BYTES {
+        return Err(ProtocolError::TooLong);
+    }
+    if line.pop() != Some(b'\n') {
+        return Err(ProtocolError::Closed);
+    }
+    let value: serde_json::Value = serde_json::from_slice(&line).map_err(|e| ProtocolError::Malformed(e.to_string()))?;
+    match value.get("version").and_then(serde_json::Value::as_u64) {
+        Some(version) if version == PROTOCOL_VERSION as u64 => {}
+        Some(version) => return Err(ProtocolError::UnsupportedVersion(version as u32)),
+        None => return Err(ProtocolError::Malformed("no version".to_string())),
+    }
+    let envelope: Envelope<T> = serde_json::from_value(value).map_err(|e| ProtocolError::Malformed(e.to_string()))?;
+    Ok(envelope.body)
+}
+
+/// Where a launcher's request has got to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BypassState {
+    Requested,
+    Queued { ahead: usize },
+    Lifted,
+    Restored { program_started: bool, reason: String },
+}
+
+/// The launcher's side of the conversation.
+#[derive(Debug, Clone)]
+pub struct BypassClient {
+    request: BypassRequest,
+    state: BypassState,
+}
+
+impl BypassClient {
+    pub fn new(request: BypassRequest) -> Self {
+        Self { request, state: BypassState::Requested }
+    }
+
+    pub fn request(&self) -> &BypassRequest {
+        &self.request
+    }
+
+    pub fn state(&self) -> &BypassState {
+        &self.state
+    }
+
+    /// Moves on to the state a reply from the service leads to.
+    pub fn receive(&mut self, reply: ServiceReply) -> Result<&BypassState, ProtocolError> {
+        if reply.nonce() != self.request.nonce {
+            return Err(ProtocolError::Unexpected(format!("a reply to request {:x}", reply.nonce())));
+        }
+        self.state = match (&self.state, reply) {
+            (_, ServiceReply::Refused { code, .. }) => return Err(ProtocolError::Refused(code)),
+            (BypassState::Requested | BypassState::Queued { .. }, ServiceReply::Queued { ahead, .. }) => {
+                BypassState::Queued { ahead }
+            }
+            (BypassState::Requested | BypassState::Queued { .. }, ServiceReply::Ack { .. }) => BypassState::Lifted,
+            (BypassState::Lifted, ServiceReply::Restored { program_started, reason, .. }) => {
+                BypassState::Restored { program_started, reason }
+            }
+            (state, reply) => return Err(ProtocolError::Unexpected(format!("{:?} while {:?}", reply, state))),
+        };
+        Ok(&self.state)
+    }
+
+    pub fn send(&self, writer: &mut impl Write) -> Result<(), ProtocolError> {
+        Ok(write_message(writer, &self.request)?)
+    }
+
+    /// Reads the service's next reply and moves on to the state it leads to.
+    pub fn next(&mut self, reader: &mut impl BufRead) -> Result<&BypassState, ProtocolError> {
+        let reply = read_message(reader)?;
+        self.receive(reply)
+    }
+
+    /// Sends the request and waits until the service has lifted the hook, however long it is queued.
+    pub fn request_bypass(&mut self, reader: &mut impl BufRead, writer: &mut impl Write) -> Result<(), ProtocolError> {
+        self.send(writer)?;
+        while *self.next(reader)? != BypassState::Lifted {}
+        Ok(())
+    }
+
+    /// Waits for the service to say the hook is back.
+    pub fn wait_for_restore(&mut self, reader: &mut impl BufRead) -> Result<&BypassState, ProtocolError> {
+        self.next(reader)
+    }
+}
+
+/// The service's side of the conversation: reads a request, lifts the hook with `lift`, and once
+/// `hold` has waited for the lease to end and put the hook back, says so.
+///
+/// `client_pid` is the process on the other end of the pipe, if it is known. Replies are sent on a
+/// best-effort basis after the acknowledgement, as the launcher may already have exited.
+pub fn serve(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    client_pid: Option<u32>,
+    lift: impl FnOnce(&BypassRequest) -> Result<(), ErrorCode>,
+    hold: impl FnOnce(&BypassRequest) -> Result<LeaseEnd, ErrorCode>,
+) -> Result<(BypassRequest, LeaseEnd), ProtocolError> {
+    let request = receive_request(reader, writer, client_pid)?;
+    let end = handle_request(writer, &request, lift, hold)?;
+    Ok((request, end))
+}
+
+/// Reads a request and checks it came from the process it names, refusing it if not.
+pub fn receive_request(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    client_pid: Option<u32>,
+) -> Result<BypassRequest, ProtocolError> {
+    let request: BypassRequest = match read_message(reader) {
+        Ok(request) => request,
+        Err(ProtocolError::Closed) => return Err(ProtocolError::Closed),
+        Err(e) => {
+            let _ = write_message(writer, &ServiceReply::Refused { nonce: 0, code: e.code() });
+            return Err(e);
+        }
+    };
+    if client_pid.is_some_and(|pid| pid != request.pid) {
+        return refuse(writer, request.nonce, ErrorCode::WrongPid);
+    }
+    Ok(request)
+}
+
+/// Lifts the hook for a request that has been received, holds it for the lease and reports back.
+pub fn handle_request(
+    writer: &mut impl Write,
+    request: &BypassRequest,
+    lift: impl FnOnce(&BypassRequest) -> Result<(), ErrorCode>,
+    hold: impl FnOnce(&BypassRequest) -> Result<LeaseEnd, ErrorCode>,
+) -> Result<LeaseEnd, ProtocolError> {
+    if let Err(code) = lift(request) {
+        return refuse(writer, request.nonce, code);
+    }
+    let _ = write_message(writer, &ServiceReply::Ack { nonce: request.nonce });
+
+    match hold(request) {
+        Ok(end) => {
+            let _ = write_message(writer, &ServiceReply::restored(request.nonce, &end));
+            Ok(end)
+        }
+        Err(code) => refuse(writer, request.nonce, code),
+    }
+}
+
+fn refuse<T>(writer: &mut impl Write, nonce: u64, code: ErrorCode) -> Result<T, ProtocolError> {
+    let _ = write_message(writer, &ServiceReply::Refused { nonce, code });
+    Err(ProtocolError::Refused(code))
+}
+
+#[cfg(windows)]
+pub use os::{PipeConnection, ReadDeadline, accept, connect, wake};
+
+#[cfg(windows)]
+mod os {
+    use std::fs::File;
+    use std::io::{self, BufReader};
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{CloseHandle, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, HANDLE, HLOCAL, LocalFree};
+    use windows::Win32::Security::Authorization::{ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1};
+    use windows::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+    use windows::Win32::System::IO::CancelIoEx;
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_GENERIC_READ, FILE_SHARE_NONE, FILE_WRITE_DATA, OPEN_EXISTING,
+        PIPE_ACCESS_DUPLEX, SECURITY_IDENTIFICATION, SECURITY_SQOS_PRESENT,
+    };
+    use windows::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, GetNamedPipeClientProcessId, GetNamedPipeServerProcessId, PIPE_READMODE_BYTE,
+        PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT, WaitNamedPipeW,
+    };
+    use windows::Win32::System::Services::{
+        CloseServiceHandle, OpenSCManagerW, OpenServiceW, QueryServiceStatusEx, SC_MANAGER_CONNECT,
+        SC_STATUS_PROCESS_INFO, SERVICE_QUERY_STATUS, SERVICE_STATUS_PROCESS,
+    };
+
+    use super::{MAX_MESSAGE_BYTES, PIPE_NAME, PIPE_SDDL};
+    use crate::SERVICE_NAME;
+
+    /// One end of a connected pipe, read through a buffer and written directly.
+    pub struct PipeConnection {
+        pub reader: BufReader<File>,
+        pub writer: File,
+        /// The process on the other end, as Windows reports it.
+        pub peer_pid: Option<u32>,
+    }
+
+    impl PipeConnection {
+        fn new(file: File, peer_pid: Option<u32>) -> io::Result<Self> {
+            Ok(Self { writer: file.try_clone()?, reader: BufReader::new(file), peer_pid })
+        }
+
+        /// Cancels a blocking read on the connection if it is still waiting after `timeout`, so a
+        /// client that connects and says nothing can't hold a thread and pipe instance forever.
+        /// Drop the deadline, while the connection is still open, once the read is done.
+        pub fn read_deadline(&self, timeout: Duration) -> ReadDeadline {
+            // Raw handles aren't `Send`; the connection outlives the timer, see `ReadDeadline::drop`.
+            let handle = self.writer.as_raw_handle() as usize;
+            let (done, finished) = mpsc::channel::<()>();
+            let timer = thread::spawn(move || {
+                if finished.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout) {
+                    let _ = unsafe { CancelIoEx(HANDLE(handle as *mut _), None) };
+                }
+            });
+            ReadDeadline { done: Some(done), timer: Some(timer) }
+        }
+    }
+
+    /// See [`PipeConnection::read_deadline`].
+    pub struct ReadDeadline {
+        done: Option<mpsc::Sender<()>>,
+        timer: Option<JoinHandle<()>>,
+    }
+
+    impl Drop for ReadDeadline {
+        /// Stops the timer and waits for it, so it can never cancel I/O issued after this.
+        fn drop(&mut self) {
+            drop(self.done.take());
+            if let Some(timer) = self.timer.take() {
+                let _ = timer.join();
+            }
+        }
+    }
+
+    /// Creates an instance of the pipe and waits for a launcher to connect. The `first` instance
+    /// fails if another process already created a pipe of that name; later ones are added while
+    /// earlier connections are still open.
+    pub fn accept(first: bool) -> io::Result<PipeConnection> {
+        unsafe {
+            let sddl = HSTRING::from(PIPE_SDDL);
+            let mut descriptor = PSECURITY_DESCRIPTOR::default();
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(&sddl, SDDL_REVISION_1, &mut descriptor, None)?;
+            let attributes = SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: descriptor.0,
+                bInheritHandle: false.into(),
+            };
+            let buffer = MAX_MESSAGE_BYTES as u32;
+            let pipe = CreateNamedPipeW(
+                &HSTRING::from(PIPE_NAME),
+                if first { PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE } else { PIPE_ACCESS_DUPLEX },
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                buffer,
+                buffer,
+                0,
+                Some(&attributes),
+            );
+            let _ = LocalFree(Some(HLOCAL(descriptor.0)));
+            if pipe.is_invalid() {
+                return Err(io::Error::last_os_error());
+            }
+            let file = File::from_raw_handle(pipe.0);
+
+            if let Err(e) = ConnectNamedPipe(pipe, None)
+                && e.code() != ERROR_PIPE_CONNECTED.to_hresult()
+            {
+                return Err(e.into());
+            }
+            let mut pid = 0;
+            let peer_pid = GetNamedPipeClientProcessId(pipe, &mut pid).is_ok().then_some(pid);
+            PipeConnection::new(file, peer_pid)
+        }
+    }
+
+    /// Connects to the service's pipe, waiting up to `timeout` for it to be ready.
+    ///
+    /// The server only gets an identification token, so it can't act as the (possibly elevated)
+    /// launcher, and the connection is refused unless the server end belongs to the helper service.
+    pub fn connect(timeout: Duration) -> io::Result<PipeConnection> {
+        let name = HSTRING::from(PIPE_NAME);
+        let started = Instant::now();
+        loop {
+            let handle = unsafe {
+                CreateFileW(
+                    PCWSTR(name.as_ptr()),
+                    (FILE_GENERIC_READ | FILE_WRITE_DATA).0,
+                    FILE_SHARE_NONE,
+                    None,
+                    OPEN_EXISTING,
+                    SECURITY_SQOS_PRESENT | SECURITY_IDENTIFICATION,
+                    None,
+                )
+            };
+            match handle {
+                Ok(handle) => match served_by_service(handle) {
+                    Ok(pid) => return PipeConnection::new(unsafe { File::from_raw_handle(handle.0) }, Some(pid)),
+                    Err(e) => {
+                        let _ = unsafe { CloseHandle(handle) };
+                        if started.elapsed() >= timeout {
+                            return Err(e);
+                        }
+                        // A service that is starting has no PID yet.
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                },
+                Err(e) if started.elapsed() >= timeout => return Err(e.into()),
+                Err(e) if e.code() == ERROR_PIPE_BUSY.to_hresult() => {
+                    let left = timeout.saturating_sub(started.elapsed()).as_millis() as u32;
+                    let _ = unsafe { WaitNamedPipeW(&name, left.max(1)) };
+                }
+                // The service hasn't created the pipe yet.
+                Err(_) => std::thread::sleep(Duration::from_millis(100)),
+            }
+        }
+    }
+
+    /// The server PID of a connected pipe, if it is the running helper service.
+    fn served_by_service(pipe: HANDLE) -> io::Result<u32> {
+        let mut server_pid = 0;
+        unsafe { GetNamedPipeServerProcessId(pipe, &mut server_pid)? };
+        match service_pid()? {
+            pid if pid != 0 && pid == server_pid => Ok(pid),
+            _ => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("the pipe is served by process {}, not {}", server_pid, SERVICE_NAME),
+            )),
+        }
+    }
+
+    /// The PID of the helper service, or 0 if it isn't running.
+    fn service_pid() -> io::Result<u32> {
+        unsafe {
+            let scm = OpenSCManagerW(None, None, SC_MANAGER_CONNECT)?;
+            let pid = OpenServiceW(scm, &HSTRING::from(SERVICE_NAME), SERVICE_QUERY_STATUS).and_then(|service| {
+                let mut status = SERVICE_STATUS_PROCESS::default();
+                let buffer = std::slice::from_raw_parts_mut(
+                    (&mut status as *mut SERVICE_STATUS_PROCESS).cast::<u8>(),
+                    std::mem::size_of::<SERVICE_STATUS_PROCESS>(),
+                );
+                let mut needed = 0;
+                let result = QueryServiceStatusEx(service, SC_STATUS_PROCESS_INFO, Some(buffer), &mut needed);
+                let _ = CloseServiceHandle(service);
+                result.map(|()| status.dwProcessId)
+            });
+            let _ = CloseServiceHandle(scm);
+            Ok(pid?)
+        }
+    }
+
+    /// How long [`wake`] keeps trying to get through to [`accept`].
+    const WAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Connects and hangs up straight away, so a service waiting in [`accept`] can check whether it
+    /// should stop. Between two launchers there may briefly be no instance listening, or every
+    /// instance may be busy, so this keeps trying for [`WAKE_TIMEOUT`] and can block that long.
+    pub fn wake() {
+        let _ = connect(WAKE_TIMEOUT);
+    }
+}
@@ -0,0 +1,52 @@
+//! The queue of bypass requests kept by the service when it runs persistently.
+//!
+//! Launchers may ask at any time, but requests are handled one at a time in the order they came:
+//! each one's hook is lifted, held for its lease and put back before the next is lifted.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::bypass_lease::LeaseEnd;
+use crate::bypass_protocol::{BypassRequest, ErrorCode, ProtocolError, ServiceReply, handle_request, write_message};
+
+pub struct BypassQueue<W> {
+    sender: Sender<(BypassRequest, W)>,
+    /// Requests queued or being handled.
+    waiting: Arc<AtomicUsize>,
+}
+
+impl<W: Write + Send + 'static> BypassQueue<W> {
+    /// Starts the worker that handles queued requests in order, passing each result to `done`. The
+    /// worker finishes once the queue has been dropped and emptied.
+    pub fn start(
+        mut lift: impl FnMut(&BypassRequest) -> Result<(), ErrorCode> + Send + 'static,
+        mut hold: impl FnMut(&BypassRequest) -> Result<LeaseEnd, ErrorCode> + Send + 'static,
+        mut done: impl FnMut(&BypassRequest, Result<LeaseEnd, ProtocolError>) + Send + 'static,
+    ) -> (Self, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel::<(BypassRequest, W)>();
+        let waiting = Arc::new(AtomicUsize::new(0));
+        let worker_waiting = waiting.clone();
+        let worker = thread::spawn(move || {
+            for (request, mut writer) in receiver {
+                let result = handle_request(&mut writer, &request, &mut lift, &mut hold);
+                worker_waiting.fetch_sub(1, Ordering::SeqCst);
+                done(&request, result);
+            }
+        });
+        (Self { sender, waiting }, worker)
+    }
+
+    /// Queues a request, telling its launcher how many requests are ahead of it, and returns that
+    /// number.
+    pub fn push(&self, request: BypassRequest, mut writer: W) -> usize {
+        let ahead = self.waiting.fetch_add(1, Ordering::SeqCst);
+        let _ = write_message(&mut writer, &ServiceReply::Queued { nonce: request.nonce, ahead });
+        if self.sender.send((request, writer)).is_err() {
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+        }
+        ahead
+    }
+}
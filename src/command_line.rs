@@ -0,0 +1,125 @@
+//! Windows command lines, as understood by `CommandLineToArgvW` and the Microsoft C runtime.
+
+/// Quotes one argument so that it survives being split back out of a command line unchanged.
+pub fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '"']) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escapes, so double them and escape the quote.
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Trailing backslashes would otherwise escape the closing quote.
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Joins arguments into a command line, quoting each as needed.
+pub fn join_args<S: AsRef<str>>(args: &[S]) -> String {
+    args.iter().map(|a| quote_arg(a.as_ref())).collect::<Vec<_>>().join(" ")
+}
+
+/// Splits arguments that were stored with [`join_args`], which have no program name in front.
+pub fn split_args(args: &str) -> Vec<String> {
+    // Give it a dummy program name so the first argument follows the normal rules.
+    split_command_line(&format!("program {}", args)).into_iter().skip(1).map(|a| a.value).collect()
+}
+
+/// One argument split out of a command line, with the byte range it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawArg {
+    pub value: String,
+    pub span: std::ops::Range<usize>,
+    /// Whether the raw text contained a quote.
+    pub quoted: bool,
+}
+
+/// Splits a full command line, including the program name, as `CommandLineToArgvW` does.
+///
+/// The program name ends at the first whitespace, or at the closing quote if it starts with one,
+/// and backslashes in it are never escapes. The remaining arguments follow the C runtime rules:
+/// `2n` backslashes before a quote become `n` and the quote opens or closes a quoted section,
+/// `2n + 1` become `n` followed by a literal quote, and `""` inside a quoted section is a literal quote.
+pub fn split_command_line(command_line: &str) -> Vec<RawArg> {
+    let bytes = command_line.as_bytes();
+    let mut args = Vec::new();
+
+    let mut pos = if bytes.first() == Some(&b'"') {
+        let end = command_line[1..].find('"').map_or(bytes.len(), |i| i + 1);
+        let after = (end + 1).min(bytes.len());
+        args.push(RawArg { value: command_line[1..end].to_string(), span: 0..after, quoted: true });
+        after
+    } else {
+        let end = command_line.find([' ', '\t']).unwrap_or(bytes.len());
+        if end > 0 {
+            args.push(RawArg { value: command_line[..end].to_string(), span: 0..end, quoted: false });
+        }
+        end
+    };
+
+    loop {
+        while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t') {
+            pos += 1;
+        }
+        if pos >= bytes.len() {
+            break;
+        }
+
+        let start = pos;
+        let mut value = String::new();
+        let mut in_quotes = false;
+        let mut quoted = false;
+        while pos < bytes.len() {
+            match bytes[pos] {
+                b' ' | b'\t' if !in_quotes => break,
+                b'\\' => {
+                    let run = bytes[pos..].iter().take_while(|&&b| b == b'\\').count();
+                    pos += run;
+                    if bytes.get(pos) == Some(&b'"') {
+                        value.extend(std::iter::repeat_n('\\', run / 2));
+                        if run % 2 == 1 {
+                            value.push('"');
+                            pos += 1;
+                        }
+                    } else {
+                        value.extend(std::iter::repeat_n('\\', run));
+                    }
+                }
+                b'"' => {
+                    quoted = true;
+                    if in_quotes && bytes.get(pos + 1) == Some(&b'"') {
+                        value.push('"');
+                        pos += 2;
+                    } else {
+                        in_quotes = !in_quotes;
+                        pos += 1;
+                    }
+                }
+                _ => {
+                    // Copy a whole UTF-8 character; its bytes never match the ASCII cases above.
+                    let ch = command_line[pos..].chars().next().unwrap_or_default();
+                    value.push(ch);
+                    pos += ch.len_utf8();
+                }
+            }
+        }
+        args.push(RawArg { value, span: start..pos, quoted });
+    }
+    args
+}
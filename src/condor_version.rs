@@ -0,0 +1,141 @@
+//! Telling Condor 2 and Condor 3 data directories apart, and finding the matching installation.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{GRAPHICS_SECTION, TARGET_EXE, VR_KEY, ini_edit};
+
+/// Setup.ini keys that only one version writes, so a data directory in a folder of any name can
+/// still be told apart by its contents.
+const VERSION_MARKERS: &[(CondorVersion, &str, &str)] = &[
+    (CondorVersion::Condor3, GRAPHICS_SECTION, "Upscaling"),
+    (CondorVersion::Condor2, GRAPHICS_SECTION, "ShaderModel"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CondorVersion {
+    Condor3,
+    Condor2,
+    Unknown,
+}
+
+impl CondorVersion {
+    /// Works out the version of a data directory from its folder name, falling back to the keys
+    /// in its Setup.ini files. Condor 3 uses `Documents\Condor3`; Condor 2 uses `Documents\Condor2`
+    /// or plain `Documents\Condor`.
+    pub fn detect(folder_name: &str, setup_inis: &[PathBuf]) -> Self {
+        if let Some(version) = Self::from_folder_name(folder_name) {
+            return version;
+        }
+        if let Some(version) = setup_inis
+            .iter()
+            .find_map(|path| Self::from_setup_ini(&std::fs::read(path).ok()?))
+        {
+            return version;
+        }
+        if folder_name.eq_ignore_ascii_case("Condor") { Self::Condor2 } else { Self::Unknown }
+    }
+
+    /// `Condor3`, `Condor 3` and `Condor_3` (and similarly for 2), but not e.g. `CondorTools` or `Condor30`.
+    pub fn from_folder_name(name: &str) -> Option<Self> {
+        let prefix = name.get(..6)?;
+        if !prefix.eq_ignore_ascii_case("Condor") {
+            return None;
+        }
+        let rest = name[6..].trim_start_matches([' ', '_', '-']);
+        Self::from_major(rest)
+    }
+
+    /// The version whose marker keys a Setup.ini contains, if exactly one version's do.
+    pub fn from_setup_ini(contents: &[u8]) -> Option<Self> {
+        let mut found = VERSION_MARKERS
+            .iter()
+            .filter(|(_, section, key)| ini_edit::get_value(contents, section, key).is_some())
+            .map(|(version, _, _)| *version);
+        let version = found.next()?;
+        found.all(|other| other == version).then_some(version)
+    }
+
+    /// The major version the text starts with, e.g. `3` in `3 Beta` but not in `30`.
+    fn from_major(text: &str) -> Option<Self> {
+        let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        match text[..digits].parse::<u32>().ok()? {
+            3 => Some(Self::Condor3),
+            2 => Some(Self::Condor2),
+            _ => None,
+        }
+    }
+
+    /// The Setup.ini section and key that switch Condor into VR mode.
+    pub fn vr_key(self) -> (&'static str, &'static str) {
+        // Condor 2 and 3 currently share the same key; this is where a future version would differ.
+        (GRAPHICS_SECTION, VR_KEY)
+    }
+
+    /// Where this version is usually installed.
+    fn default_install_dirs(self) -> &'static [&'static str] {
+        match self {
+            Self::Condor3 => &[r"C:\Condor3", r"C:\Program Files\Condor3", r"C:\Program Files (x86)\Condor3"],
+            Self::Condor2 => &[r"C:\Condor2", r"C:\Program Files\Condor2", r"C:\Program Files (x86)\Condor2"],
+            Self::Unknown => &[],
+        }
+    }
+
+    /// Finds the `Condor.exe` of this version, from the uninstall registry entries or common locations.
+    pub fn find_condor_exe(self) -> Option<PathBuf> {
+        #[cfg(windows)]
+        if let Some(exe) = self.find_installed_exe() {
+            return Some(exe);
+        }
+
+        self.default_install_dirs()
+            .iter()
+            .map(|dir| Path::new(dir).join(TARGET_EXE))
+            .find(|exe| exe.is_file())
+    }
+
+    /// Looks through the installed programs for one whose name matches this version.
+    #[cfg(windows)]
+    fn find_installed_exe(self) -> Option<PathBuf> {
+        use winreg::RegKey;
+        use winreg::enums::*;
+
+        const UNINSTALL_PATH: &str = r"Software\Microsoft\Windows\CurrentVersion\Uninstall";
+        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+        for flags in [KEY_READ | KEY_WOW64_64KEY, KEY_READ | KEY_WOW64_32KEY] {
+            let Ok(uninstall) = hklm.open_subkey_with_flags(UNINSTALL_PATH, flags) else {
+                continue;
+            };
+            for name in uninstall.enum_keys().flatten() {
+                let Ok(app) = uninstall.open_subkey_with_flags(&name, flags) else {
+                    continue;
+                };
+                let Ok(display_name) = app.get_value::<String, _>("DisplayName") else {
+                    continue;
+                };
+                if Self::from_folder_name(&display_name) != Some(self) {
+                    continue;
+                }
+                if let Ok(location) = app.get_value::<String, _>("InstallLocation") {
+                    let exe = Path::new(&location).join(TARGET_EXE);
+                    if exe.is_file() {
+                        return Some(exe);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for CondorVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Condor3 => write!(f, "Condor 3"),
+            Self::Condor2 => write!(f, "Condor 2"),
+            Self::Unknown => write!(f, "Unknown Condor version"),
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Machine-wide settings in `%ProgramData%\CondorVR\config.toml`, written by the elevated
+//! configurer and read by the launcher, service and GUI.
+//!
+//! Until that file exists, the injector path is taken from where older versions kept it: the
+//! `.env` file next to the launcher, or the `ReviveInjectorPath` registry value.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write::write_atomic;
+use crate::file_security::{has_strict_permissions, is_admin_owned};
+use crate::hooked_exe::{self, HookedExe, validate_exe_name, validate_install_path};
+use crate::launch_plan::InjectorSettings;
+use crate::logging::LogLevel;
+use crate::{get_companion_exe_path, is_safe_path};
+
+pub const CONFIG_DIR: &str = "CondorVR";
+pub const CONFIG_FILE: &str = "config.toml";
+pub const LEGACY_ENV_FILE: &str = ".env";
+/// The `.env` key older versions read the injector path from.
+pub const LEGACY_ENV_KEY: &str = "C3_REVIVE_INJECTOR_PATH";
+/// The value under `HKLM\Software\CondorVR` older versions saved the injector path in.
+pub const LEGACY_INJECTOR_VALUE: &str = "ReviveInjectorPath";
+
+const MAX_TIMEOUT_SECS: u64 = 300;
+
+/// The injector used for executables that don't name their own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InjectorConfig {
+    /// Used instead of the ReviveInjector found automatically.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    /// How long the launcher waits for the helper service to start.
+    pub service_start_secs: u64,
+    /// How long the launcher waits for the service to lift the IFEO hook.
+    pub hook_removal_secs: u64,
+    /// How long the service leaves the hook lifted if the program doesn't start.
+    pub bypass_lease_secs: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self { service_start_secs: 5, hook_removal_secs: 5, bypass_lease_secs: 60 }
+    }
+}
+
+impl Timeouts {
+    pub fn service_start(&self) -> Duration {
+        Duration::from_secs(self.service_start_secs)
+    }
+
+    pub fn hook_removal(&self) -> Duration {
+        Duration::from_secs(self.hook_removal_secs)
+    }
+
+    pub fn bypass_lease(&self) -> Duration {
+        Duration::from_secs(self.bypass_lease_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaunchConfig {
+    /// Whether the launcher offers flatscreen launches, through its chooser or by holding Shift.
+    pub allow_flatscreen: bool,
+    /// Whether launches are refused when Revive is older than the helper supports.
+    pub block_old_revive: bool,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        Self { allow_flatscreen: true, block_old_revive: true }
+    }
+}
+
+/// How the helper service runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceMode {
+    /// Started by each launch, handling that one bypass before stopping.
+    #[default]
+    Demand,
+    /// Started with Windows and kept running, handling launches in the order they come.
+    Persistent,
+}
+
+impl fmt::Display for ServiceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Demand => write!(f, "demand start"),
+            Self::Persistent => write!(f, "automatic start, always running"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServiceConfig {
+    pub mode: ServiceMode,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub log_level: LogLevel,
+    pub injector: InjectorConfig,
+    pub timeouts: Timeouts,
+    pub launch: LaunchConfig,
+    pub service: ServiceConfig,
+    /// The executables routed through the launcher, as `[[executable]]` tables. If there are
+    /// none, only `Condor.exe` is hooked.
+    #[serde(rename = "executable", skip_serializing_if = "Vec::is_empty")]
+    pub executables: Vec<HookedExe>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, error: io::Error },
+    /// Not valid TOML, or a value of the wrong type.
+    Parse { path: PathBuf, message: String },
+    /// Well-formed, but a value is out of range or inconsistent.
+    Invalid { path: PathBuf, message: String },
+    /// Standard users could have written the file, so it can't be trusted.
+    Untrusted(PathBuf),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            Self::Parse { path, message } => write!(f, "{} could not be read: {}", path.display(), message.trim_end()),
+            Self::Invalid { path, message } => write!(f, "{}: {}", path.display(), message),
+            Self::Untrusted(path) => write!(
+                f,
+                "{} is ignored because standard users can change it; run the configurer to rewrite it",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// `%ProgramData%\CondorVR\config.toml`
+    pub fn path() -> PathBuf {
+        let program_data = std::env::var_os("ProgramData").map_or_else(|| PathBuf::from(r"C:\ProgramData"), PathBuf::from);
+        program_data.join(CONFIG_DIR).join(CONFIG_FILE)
+    }
+
+    /// Parses and validates the contents of a config file.
+    pub fn parse(path: &Path, text: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text)
+            .map_err(|e| ConfigError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
+        config
+            .validate()
+            .map_err(|message| ConfigError::Invalid { path: path.to_path_buf(), message })?;
+        Ok(config)
+    }
+
+    /// Loads a config file, or returns `None` if there isn't one.
+    pub fn load(path: &Path) -> Result<Option<Self>, ConfigError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(path, &text).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(ConfigError::Io { path: path.to_path_buf(), error }),
+        }
+    }
+
+    /// The machine config, or the settings of an older version if it hasn't been written yet.
+    /// The file is only trusted if it's owned by, and only writable by, administrators.
+    pub fn load_machine() -> Result<Self, ConfigError> {
+        let path = Self::path();
+        if path.exists() && !(is_safe_path(&path) && has_strict_permissions(&path) && is_admin_owned(&path)) {
+            return Err(ConfigError::Untrusted(path));
+        }
+        Ok(Self::load(&path)?.unwrap_or_else(Self::migrate_legacy))
+    }
+
+    /// Checks values that parse but make no sense, explaining what is wrong.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, secs) in [
+            ("timeouts.service_start_secs", self.timeouts.service_start_secs),
+            ("timeouts.hook_removal_secs", self.timeouts.hook_removal_secs),
+            ("timeouts.bypass_lease_secs", self.timeouts.bypass_lease_secs),
+        ] {
+            if !(1..=MAX_TIMEOUT_SECS).contains(&secs) {
+                return Err(format!("{} must be between 1 and {} seconds, not {}", name, MAX_TIMEOUT_SECS, secs));
+            }
+        }
+        if let Some(path) = &self.injector.path {
+            check_injector_path("injector.path", path)?;
+        }
+        for (i, exe) in self.executables.iter().enumerate() {
+            validate_exe_name(&exe.name).map_err(|e| format!("executable {}: {}", exe.name, e))?;
+            if self.executables[..i].iter().any(|other| other.is_named(&exe.name)) {
+                return Err(format!("executable {} is listed more than once", exe.name));
+            }
+            if let Some(path) = &exe.injector_path {
+                check_injector_path(&format!("injector_path of {}", exe.name), path)?;
+            }
+            for path in &exe.paths {
+                validate_install_path(&exe.name, path).map_err(|e| format!("paths of {}: {}", exe.name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(path, self.to_toml().as_bytes())
+    }
+
+    /// The hooked executables, or just `Condor.exe` if none are configured.
+    pub fn hooked_exes(&self) -> Vec<HookedExe> {
+        if self.executables.is_empty() {
+            hooked_exe::default_hooked_exes()
+        } else {
+            self.executables.clone()
+        }
+    }
+
+    /// How to run the injector for `exe`: its own injector and arguments if it has them, otherwise
+    /// the configured default, otherwise the injector that was found automatically.
+    pub fn injector_for(&self, exe: &HookedExe, detected: Option<&Path>) -> Option<InjectorSettings> {
+        let mut injector = exe.injector(self.injector.path.as_deref().or(detected))?;
+        if exe.injector_args.is_empty() {
+            injector.args = self.injector.args.clone();
+        }
+        Some(injector)
+    }
+
+    /// Builds a config from the settings of older versions: the injector path in `.env`, or failing
+    /// that the one saved in the registry when VR was last enabled.
+    pub fn migrate(env_file: Option<&str>, registry_path: Option<&str>) -> Self {
+        let injector_path = env_file
+            .and_then(|contents| env_value(contents, LEGACY_ENV_KEY))
+            .or_else(|| registry_path.map(str::to_string));
+        Self {
+            injector: InjectorConfig { path: injector_path.map(PathBuf::from), args: Vec::new() },
+            ..Self::default()
+        }
+    }
+
+    /// [`Config::migrate`] from this machine's `.env` and registry. The path is only kept if
+    /// standard users can't have planted it.
+    pub fn migrate_legacy() -> Self {
+        let env_file = get_companion_exe_path(LEGACY_ENV_FILE).and_then(|p| std::fs::read_to_string(p).ok());
+        let mut config = Self::migrate(env_file.as_deref(), legacy_registry_injector().as_deref());
+        if let Some(path) = &config.injector.path {
+            let trusted = path.to_string_lossy().to_lowercase().starts_with(r"c:\program files\revive");
+            if !trusted && !has_strict_permissions(path) {
+                config.injector.path = None;
+            }
+        }
+        config
+    }
+}
+
+/// The injector path older versions saved in the registry.
+fn legacy_registry_injector() -> Option<String> {
+    #[cfg(windows)]
+    {
+        use crate::ifeo::{LocalMachine, Registry};
+        LocalMachine.string(crate::SETTINGS_PATH, LEGACY_INJECTOR_VALUE)
+    }
+    #[cfg(not(windows))]
+    None
+}
+
+fn check_injector_path(name: &str, path: &Path) -> Result<(), String> {
+    let is_exe = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+    if !is_exe {
+        return Err(format!("{} should be the full path to ReviveInjector.exe, not {}", name, path.display()));
+    }
+    Ok(())
+}
+
+/// The value of `key` in a `.env` file, without surrounding quotes.
+pub fn env_value(contents: &str, key: &str) -> Option<String> {
+    contents.lines().map(str::trim).filter(|line| !line.starts_with('#')).find_map(|line| {
+        let (k, value) = line.split_once('=')?;
+        if k.trim() != key {
+            return None;
+        }
+        let value = value.trim();
+        let unquoted = ['"', '\'']
+            .iter()
+            .find_map(|&q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)))
+            .unwrap_or(value);
+        Some(unquoted.to_string())
+    })
+}
@@ -0,0 +1,151 @@
+//! Checking that files the helper trusts can't be changed by standard users.
+
+use std::path::Path;
+
+/// Checks if a file has strict permissions (not writable by non-admins/standard users).
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn has_strict_permissions(path: &Path) -> bool {
+    #[cfg(windows)]
+    if !acl::dacl_is_strict(path) {
+        return false;
+    }
+    true
+}
+
+/// Checks that a file is owned by Administrators or SYSTEM. A file created by a standard user
+/// keeps full access for its owner even if no group can write to it.
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn is_admin_owned(path: &Path) -> bool {
+    #[cfg(windows)]
+    if !acl::owner_is_admin(path) {
+        return false;
+    }
+    true
+}
+
+#[cfg(windows)]
+mod acl {
+    use std::path::Path;
+
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::*;
+    use windows::Win32::Security::Authorization::{ConvertSidToStringSidW, GetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows::Win32::Security::{ACCESS_ALLOWED_ACE, ACE_HEADER, DACL_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION};
+    use windows::Win32::System::SystemServices::ACCESS_ALLOWED_ACE_TYPE;
+
+    /// Administrators and LocalSystem.
+    const ADMIN_SIDS: [&str; 2] = ["S-1-5-32-544", "S-1-5-18"];
+
+    /// Whether no Access Allowed ACE grants write access to Everyone, Authenticated Users or Users.
+    pub fn dacl_is_strict(path: &Path) -> bool {
+        unsafe {
+            let path_w: Vec<u16> = path.to_str().unwrap_or("").encode_utf16().chain(Some(0)).collect();
+            let mut p_psid_owner = windows::Win32::Security::PSID::default();
+            let mut p_psid_group = windows::Win32::Security::PSID::default();
+            let mut p_dacl = std::ptr::null_mut();
+            let mut p_security_descriptor = windows::Win32::Security::PSECURITY_DESCRIPTOR::default();
+
+            let res = GetNamedSecurityInfoW(
+                PCWSTR(path_w.as_ptr()),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION,
+                Some(&mut p_psid_owner),
+                Some(&mut p_psid_group),
+                Some(&mut p_dacl),
+                None,
+                &mut p_security_descriptor,
+            );
+
+            if res != ERROR_SUCCESS {
+                return false;
+            }
+
+            if p_dacl.is_null() {
+                let _ = LocalFree(Some(HLOCAL(p_security_descriptor.0)));
+                return false; // A NULL DACL means Everyone has full access, so not strict.
+            }
+
+            // Check the DACL for entries that grant write access to non-privileged groups
+            let mut acl_size_info = windows::Win32::Security::ACL_SIZE_INFORMATION::default();
+            if windows::Win32::Security::GetAclInformation(
+                p_dacl,
+                &mut acl_size_info as *mut _ as *mut _,
+                std::mem::size_of::<windows::Win32::Security::ACL_SIZE_INFORMATION>() as u32,
+                windows::Win32::Security::AclSizeInformation,
+            ).is_err() {
+                let _ = LocalFree(Some(HLOCAL(p_security_descriptor.0)));
+                return false;
+            }
+
+            for i in 0..acl_size_info.AceCount {
+                let mut p_ace = std::ptr::null_mut();
+                if windows::Win32::Security::GetAce(p_dacl, i, &mut p_ace).is_ok() {
+                    let header = &*(p_ace as *const ACE_HEADER);
+                    // We only care about Access Allowed ACEs for this check
+                    if header.AceType as u32 == ACCESS_ALLOWED_ACE_TYPE {
+                        let ace = &*(p_ace as *const ACCESS_ALLOWED_ACE);
+                        let mask = ace.Mask;
+
+                        // Check if this ACE grants write permissions
+                        let write_mask = 0x00000002 | 0x00000004 | 0x00010000 | 0x00100000; // FILE_WRITE_DATA | FILE_APPEND_DATA | DELETE | GENERIC_WRITE
+                        if (mask & write_mask) != 0 {
+                            let sid = &ace.SidStart as *const _ as *const windows::Win32::Security::SID;
+
+                            // Check if the SID is a non-privileged group (like Everyone, Users, Authenticated Users)
+                            // S-1-1-0 (Everyone)
+                            // S-1-5-11 (Authenticated Users)
+                            // S-1-5-32-545 (Users)
+                            let mut sid_string = windows::core::PWSTR::null();
+                            if ConvertSidToStringSidW(windows::Win32::Security::PSID(sid as *mut _), &mut sid_string).is_ok() {
+                                let s = String::from_utf16_lossy(sid_string.as_wide());
+                                let _ = LocalFree(Some(HLOCAL(sid_string.0 as *mut _)));
+
+                                if s == "S-1-1-0" || s == "S-1-5-11" || s == "S-1-5-32-545" {
+                                    // Found a non-privileged SID with write access
+                                    let _ = LocalFree(Some(HLOCAL(p_security_descriptor.0)));
+                                    return false;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            let _ = LocalFree(Some(HLOCAL(p_security_descriptor.0)));
+        }
+        true
+    }
+
+    pub fn owner_is_admin(path: &Path) -> bool {
+        unsafe {
+            let path_w: Vec<u16> = path.to_str().unwrap_or("").encode_utf16().chain(Some(0)).collect();
+            let mut p_psid_owner = windows::Win32::Security::PSID::default();
+            let mut p_security_descriptor = windows::Win32::Security::PSECURITY_DESCRIPTOR::default();
+
+            let res = GetNamedSecurityInfoW(
+                PCWSTR(path_w.as_ptr()),
+                SE_FILE_OBJECT,
+                OWNER_SECURITY_INFORMATION,
+                Some(&mut p_psid_owner),
+                None,
+                None,
+                None,
+                &mut p_security_descriptor,
+            );
+            if res != ERROR_SUCCESS {
+                return false;
+            }
+
+            let mut sid_string = windows::core::PWSTR::null();
+            let is_admin = if ConvertSidToStringSidW(p_psid_owner, &mut sid_string).is_ok() {
+                let s = String::from_utf16_lossy(sid_string.as_wide());
+                let _ = LocalFree(Some(HLOCAL(sid_string.0 as *mut _)));
+                ADMIN_SIDS.contains(&s.as_str())
+            } else {
+                false
+            };
+            let _ = LocalFree(Some(HLOCAL(p_security_descriptor.0)));
+            is_admin
+        }
+    }
+}
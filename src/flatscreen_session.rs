@@ -0,0 +1,127 @@
+//! A one-off flatscreen launch while the hook stays active: the Setup.ini files that are in VR are
+//! switched to flatscreen for the session, then switched back once Condor exits.
+//!
+//! Before anything is switched, the session is recorded in `%LOCALAPPDATA%\CondorVR\Sessions`
+//! under the launcher's PID. If the launcher is killed before switching back, the record is left
+//! behind, and the next launch or GUI start ends the session with [`FlatscreenSession::recover`].
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write::write_atomic;
+use crate::backup::BackupStore;
+use crate::condor_version::CondorVersion;
+use crate::outcome::SetupIniOutcome;
+use crate::plan::{SetupIniChange, plan_setup_file};
+use crate::profiles::{CondorProfileSet, SetupFile};
+use crate::settings::{SETTINGS_DIR, UserSettings};
+use crate::{apply_setup_ini_changes, read_vr_enabled};
+
+pub const SESSIONS_DIR: &str = "Sessions";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlatscreenSession {
+    /// The launcher that switched the files; its session is only recovered once it has exited.
+    pid: u32,
+    /// The files switched to flatscreen, which go back to VR when the session ends.
+    files: Vec<SetupFile>,
+}
+
+impl FlatscreenSession {
+    /// The default location of session records, `%LOCALAPPDATA%\CondorVR\Sessions`, falling back
+    /// to the temp directory.
+    pub fn default_dir() -> PathBuf {
+        let root = BaseDirs::new().map_or_else(std::env::temp_dir, |dirs| dirs.data_local_dir().to_path_buf());
+        root.join(SETTINGS_DIR).join(SESSIONS_DIR)
+    }
+
+    /// Switches every Setup.ini currently in VR to flatscreen, swapping graphics profiles as a
+    /// normal toggle would. If the launched Condor's version is known, only its files are touched.
+    /// Nothing is switched if the session can't be recorded in `dir` first.
+    pub fn begin(
+        profiles: &CondorProfileSet,
+        version: Option<CondorVersion>,
+        settings: &mut UserSettings,
+        backups: &BackupStore,
+        dir: &Path,
+    ) -> io::Result<(Self, Vec<SetupIniOutcome>)> {
+        let changes: Vec<SetupIniChange> = profiles
+            .setup_files()
+            .into_iter()
+            .filter(|file| version.is_none_or(|v| file.version == v || file.version == CondorVersion::Unknown))
+            .filter(|file| read_vr_enabled(&file.path))
+            .map(|file| plan_setup_file(file, settings, false))
+            .collect();
+        let mut session = Self { pid: std::process::id(), files: changes.iter().map(|c| c.file.clone()).collect() };
+        session.save(dir)?;
+
+        let results = apply_setup_ini_changes(&changes, settings, backups);
+        session.files = results.iter().filter(|r| r.is_ok()).map(|r| r.file.clone()).collect();
+        // The record already written covers these files and more, which is still safe to end.
+        let _ = session.save(dir);
+        Ok((session, results))
+    }
+
+    pub fn files(&self) -> &[SetupFile] {
+        &self.files
+    }
+
+    /// Puts the files back into VR, along with their VR graphics profile. The record is removed
+    /// once every file is back; files that couldn't be switched stay recorded for a later retry.
+    pub fn end(mut self, settings: &mut UserSettings, backups: &BackupStore, dir: &Path) -> Vec<SetupIniOutcome> {
+        let changes: Vec<SetupIniChange> = self
+            .files
+            .drain(..)
+            .map(|file| plan_setup_file(file, settings, true))
+            .collect();
+        let results = apply_setup_ini_changes(&changes, settings, backups);
+        self.files = results.iter().filter(|r| !r.is_ok()).map(|r| r.file.clone()).collect();
+        let _ = self.save(dir);
+        results
+    }
+
+    /// Ends every session recorded in `dir` whose launcher is no longer running.
+    pub fn recover(
+        dir: &Path,
+        is_running: impl Fn(u32) -> bool,
+        settings: &mut UserSettings,
+        backups: &BackupStore,
+    ) -> Vec<SetupIniOutcome> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut results = Vec::new();
+        for entry in entries.flatten() {
+            let Some(session) = std::fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|text| serde_json::from_str::<Self>(&text).ok())
+            else {
+                continue;
+            };
+            if !is_running(session.pid) {
+                results.extend(session.end(settings, backups, dir));
+            }
+        }
+        results
+    }
+
+    fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", self.pid))
+    }
+
+    /// Writes the record, or removes it once no files are left to switch back.
+    fn save(&self, dir: &Path) -> io::Result<()> {
+        let path = self.path(dir);
+        if self.files.is_empty() {
+            return match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        std::fs::create_dir_all(dir)?;
+        write_atomic(&path, serde_json::to_string(self).map_err(io::Error::other)?.as_bytes())
+    }
+}
@@ -0,0 +1,101 @@
+//! VR and flatscreen graphics profiles: a user-chosen set of `[Graphics]` keys whose values are
+//! saved when leaving a mode and put back when returning to it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{GRAPHICS_SECTION, VR_KEY, ini_edit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsMode {
+    Vr,
+    Flatscreen,
+}
+
+impl GraphicsMode {
+    /// The mode a Setup.ini is in, from its `VROculusRift` value. A missing value means flatscreen.
+    pub fn from_vr_value(value: Option<&str>) -> Self {
+        if value.is_some_and(|v| v.trim() == "1") { Self::Vr } else { Self::Flatscreen }
+    }
+
+    pub fn vr_value(self) -> &'static str {
+        match self {
+            Self::Vr => "1",
+            Self::Flatscreen => "0",
+        }
+    }
+
+    /// The key prefix used for this mode's snapshot in the user settings.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Vr => "VR",
+            Self::Flatscreen => "Flatscreen",
+        }
+    }
+
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        [Self::Vr, Self::Flatscreen].into_iter().find(|m| m.prefix() == prefix)
+    }
+}
+
+impl fmt::Display for GraphicsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Vr => write!(f, "VR"),
+            Self::Flatscreen => write!(f, "flatscreen"),
+        }
+    }
+}
+
+/// A planned change to one `[Graphics]` key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyChange {
+    pub key: String,
+    /// The current value, or `None` if the key is missing.
+    pub before: Option<String>,
+    pub after: String,
+}
+
+impl fmt::Display for KeyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let before = self.before.as_deref().unwrap_or("(missing)");
+        write!(f, "{} {} -> {}", self.key, before, self.after)
+    }
+}
+
+/// The `[Graphics]` keys of a Setup.ini that can be part of a profile, i.e. all but `VROculusRift`.
+pub fn profile_candidates(contents: &[u8]) -> Vec<String> {
+    ini_edit::keys(contents, GRAPHICS_SECTION)
+        .into_iter()
+        .filter(|k| !k.eq_ignore_ascii_case(VR_KEY))
+        .collect()
+}
+
+/// The current values of the profile keys, skipping any the file doesn't have.
+pub fn capture(contents: &[u8], keys: &[String]) -> BTreeMap<String, String> {
+    keys.iter()
+        .filter_map(|key| {
+            let value = ini_edit::get_value(contents, GRAPHICS_SECTION, key)?;
+            Some((key.clone(), value))
+        })
+        .collect()
+}
+
+/// The changes needed to bring the profile keys in line with a saved snapshot. Keys that are no
+/// longer part of the profile, or have no saved value, are left alone.
+pub fn restore(contents: &[u8], keys: &[String], snapshot: &BTreeMap<String, String>) -> Vec<KeyChange> {
+    keys.iter()
+        .filter_map(|key| {
+            let after = snapshot.get(key)?;
+            let before = ini_edit::get_value(contents, GRAPHICS_SECTION, key);
+            (before.as_ref() != Some(after)).then(|| KeyChange {
+                key: key.clone(),
+                before,
+                after: after.clone(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,131 @@
+//! The executables routed through the launcher, each with its own IFEO hook and injector settings.
+//!
+//! The list is kept in the machine [`Config`].
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::condor_version::CondorVersion;
+use crate::config::{Config, ConfigError};
+use crate::launch_plan::InjectorSettings;
+use crate::{IFEO_PATH, TARGET_EXE};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookedExe {
+    /// The file name IFEO matches on, e.g. `Condor.exe`.
+    pub name: String,
+    /// Disabled executables keep their settings but aren't hooked.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// Overrides the ReviveInjector found by [`crate::find_revive_injector`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub injector_path: Option<PathBuf>,
+    /// Injector arguments placed before the target.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub injector_args: Vec<String>,
+    /// Full paths of the installs to route through the launcher. If there are none, every
+    /// executable with this name is, except that `Condor.exe` is scoped to the installs found.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathBuf>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl HookedExe {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), enabled: true, injector_path: None, injector_args: Vec::new(), paths: Vec::new() }
+    }
+
+    /// `IFEO_PATH\<name>`, where the `Debugger` value goes.
+    pub fn ifeo_key(&self) -> String {
+        ifeo_key(&self.name)
+    }
+
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// How to run the injector for this executable, given the default ReviveInjector if one was found.
+    pub fn injector(&self, default_injector: Option<&Path>) -> Option<InjectorSettings> {
+        let path = self.injector_path.as_deref().or(default_injector)?;
+        let mut injector = InjectorSettings::new(path);
+        injector.args = self.injector_args.clone();
+        Some(injector)
+    }
+}
+
+/// `IFEO_PATH\<exe name>`
+pub fn ifeo_key(exe_name: &str) -> String {
+    format!(r"{}\{}", IFEO_PATH, exe_name)
+}
+
+/// Checks that `name` is a bare executable name IFEO can match, such as `Condor.exe`.
+pub fn validate_exe_name(name: &str) -> Result<(), String> {
+    if name.contains(['\\', '/', ':']) {
+        return Err(format!("'{}' should be a file name like Condor.exe, not a path", name));
+    }
+    let has_exe = name.len() > 4 && name.get(name.len() - 4..).is_some_and(|ext| ext.eq_ignore_ascii_case(".exe"));
+    if !has_exe {
+        return Err(format!("'{}' should end in .exe", name));
+    }
+    Ok(())
+}
+
+/// The entry for an executable name, if it's in the list.
+pub fn find<'a>(exes: &'a [HookedExe], name: &str) -> Option<&'a HookedExe> {
+    exes.iter().find(|e| e.is_named(name))
+}
+
+/// Checks that `path` is the full path of an executable called `name`, as `FilterFullPath` needs.
+pub fn validate_install_path(name: &str, path: &Path) -> Result<(), String> {
+    let text = path.to_string_lossy();
+    let absolute = text.starts_with(r"\\") || text.get(1..3) == Some(r":\");
+    if !absolute {
+        return Err(format!(r"'{}' should be a full path such as C:\Condor3\{}", text, name));
+    }
+    let file_name = text.rsplit('\\').next().unwrap_or_default();
+    if !file_name.eq_ignore_ascii_case(name) {
+        return Err(format!("'{}' is not a path to {}", text, name));
+    }
+    Ok(())
+}
+
+/// The `Condor.exe` of each Condor version installed on this machine.
+pub fn discover_install_paths() -> Vec<PathBuf> {
+    [CondorVersion::Condor3, CondorVersion::Condor2].into_iter().filter_map(CondorVersion::find_condor_exe).collect()
+}
+
+/// The `discovered` installs a scoped hook doesn't cover, e.g. a Condor moved or installed since
+/// VR was activated. They start without the launcher until they are added to `paths`.
+pub fn unlisted_installs(exe: &HookedExe, discovered: &[PathBuf]) -> Vec<PathBuf> {
+    if exe.paths.is_empty() || !exe.is_named(TARGET_EXE) {
+        return Vec::new();
+    }
+    let listed = |path: &PathBuf| exe.paths.iter().any(|p| p.to_string_lossy().eq_ignore_ascii_case(&path.to_string_lossy()));
+    discovered.iter().filter(|path| !listed(path)).cloned().collect()
+}
+
+/// Scopes `Condor.exe` to the `discovered` installs if no paths have been configured for it.
+pub fn with_install_paths(mut exes: Vec<HookedExe>, discovered: &[PathBuf]) -> Vec<HookedExe> {
+    for exe in &mut exes {
+        if exe.paths.is_empty() && exe.is_named(TARGET_EXE) {
+            exe.paths = discovered.to_vec();
+        }
+    }
+    exes
+}
+
+/// The list used when none has been configured.
+pub fn default_hooked_exes() -> Vec<HookedExe> {
+    vec![HookedExe::new(TARGET_EXE)]
+}
+
+/// The configured list. An unreadable or untrusted config is an error rather than a fallback to
+/// `Condor.exe`, as the service decides from this list which hooks it may lift.
+pub fn load_hooked_exes() -> Result<Vec<HookedExe>, ConfigError> {
+    Ok(Config::load_machine()?.hooked_exes())
+}
@@ -0,0 +1,314 @@
+//! Reading and laying out the Image File Execution Options keys that route programs through the
+//! launcher.
+//!
+//! A plain hook puts a `Debugger` value on `IFEO_PATH\<name>`, which catches every executable of
+//! that name. A hook scoped to particular installs uses Windows' filter layout instead: `UseFilter`
+//! is set on that key and each install gets a subkey whose `FilterFullPath` names it and which holds
+//! the `Debugger`, so other executables of the same name start normally. Our subkeys are named
+//! [`FILTER_KEY_PREFIX`] and a number, and filters added by anything else are left alone.
+//!
+//! Everything here goes through [`Registry`], so the layouts can be tried out on a
+//! [`MemoryRegistry`].
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::hooked_exe::{HookedExe, ifeo_key};
+use crate::plan::{DEBUGGER_VALUE, RegistryChange};
+
+pub const USE_FILTER_VALUE: &str = "UseFilter";
+pub const FILTER_FULL_PATH_VALUE: &str = "FilterFullPath";
+pub const FILTER_KEY_PREFIX: &str = "CondorVR";
+
+/// The parts of `HKEY_LOCAL_MACHINE` the hooks are read from and written to.
+pub trait Registry {
+    /// A `REG_SZ` value, if the key and value exist.
+    fn string(&self, key: &str, name: &str) -> Option<String>;
+    /// A `REG_DWORD` value, if the key and value exist.
+    fn dword(&self, key: &str, name: &str) -> Option<u32>;
+    /// The names of a key's direct subkeys, or none if it doesn't exist.
+    fn subkeys(&self, key: &str) -> Vec<String>;
+    /// Makes a change. Deleting something that isn't there is not an error.
+    fn apply(&mut self, change: &RegistryChange) -> io::Result<()>;
+}
+
+/// The name of our filter subkey for the `index`th install.
+pub fn filter_key_name(index: usize) -> String {
+    format!("{}{}", FILTER_KEY_PREFIX, index + 1)
+}
+
+fn is_our_filter_key(name: &str) -> bool {
+    name.get(..FILTER_KEY_PREFIX.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(FILTER_KEY_PREFIX))
+        && name[FILTER_KEY_PREFIX.len()..].parse::<usize>().is_ok()
+}
+
+/// Whether an executable's key is set up for filtering.
+pub fn uses_filter(registry: &impl Registry, exe_name: &str) -> bool {
+    registry.dword(&ifeo_key(exe_name), USE_FILTER_VALUE).is_some_and(|v| v != 0)
+}
+
+/// Our filter subkeys of an executable's key, each with the install it names.
+pub fn filter_keys(registry: &impl Registry, exe_name: &str) -> Vec<(String, PathBuf)> {
+    let key = ifeo_key(exe_name);
+    registry
+        .subkeys(&key)
+        .into_iter()
+        .filter(|name| is_our_filter_key(name))
+        .filter_map(|name| {
+            let subkey = format!(r"{}\{}", key, name);
+            let path = registry.string(&subkey, FILTER_FULL_PATH_VALUE)?;
+            Some((subkey, PathBuf::from(path)))
+        })
+        .collect()
+}
+
+/// The keys whose `Debugger` value routes an executable through the launcher: our filter subkeys
+/// if the key uses filtering and we have any, otherwise the key itself. A plain hook can sit beside
+/// someone else's filters, in which case `UseFilter` is set but the `Debugger` is on the key.
+pub fn hook_keys(registry: &impl Registry, exe_name: &str) -> Vec<String> {
+    let ours = match uses_filter(registry, exe_name) {
+        true => filter_keys(registry, exe_name),
+        false => Vec::new(),
+    };
+    if ours.is_empty() { vec![ifeo_key(exe_name)] } else { ours.into_iter().map(|(key, _)| key).collect() }
+}
+
+/// Whether an executable is currently routed through the launcher, in either layout.
+pub fn is_hooked(registry: &impl Registry, exe_name: &str) -> bool {
+    hook_keys(registry, exe_name).iter().any(|key| registry.string(key, DEBUGGER_VALUE).is_some())
+}
+
+/// Hooks an enabled executable, scoped to its `paths` if it has any, and unhooks a disabled one.
+/// Whatever the other layout left behind is cleared up.
+pub fn hook_changes(registry: &impl Registry, launcher_path: &Path, exe: &HookedExe) -> Vec<RegistryChange> {
+    if !exe.enabled {
+        return unhook_changes(registry, &exe.name);
+    }
+    let key = exe.ifeo_key();
+    let debugger = format!("\"{}\"", launcher_path.display());
+    let existing = filter_keys(registry, &exe.name);
+    let mut changes = Vec::new();
+
+    if exe.paths.is_empty() {
+        changes.extend(existing.into_iter().map(|(key, _)| RegistryChange::DeleteKey { key }));
+        changes.extend(clear_use_filter(registry, &exe.name));
+        changes.push(RegistryChange::Set { key, name: DEBUGGER_VALUE.to_string(), value: debugger });
+        return changes;
+    }
+
+    if registry.string(&key, DEBUGGER_VALUE).is_some() {
+        changes.push(RegistryChange::Delete { key: key.clone(), name: DEBUGGER_VALUE.to_string() });
+    }
+    changes.push(RegistryChange::SetDword { key: key.clone(), name: USE_FILTER_VALUE.to_string(), value: 1 });
+    for (i, path) in exe.paths.iter().enumerate() {
+        let subkey = format!(r"{}\{}", key, filter_key_name(i));
+        changes.push(RegistryChange::Set {
+            key: subkey.clone(),
+            name: FILTER_FULL_PATH_VALUE.to_string(),
+            value: path.display().to_string(),
+        });
+        changes.push(RegistryChange::Set { key: subkey, name: DEBUGGER_VALUE.to_string(), value: debugger.clone() });
+    }
+    // Installs that are no longer listed.
+    let wanted: Vec<String> = (0..exe.paths.len()).map(|i| format!(r"{}\{}", key, filter_key_name(i))).collect();
+    changes.extend(
+        existing
+            .into_iter()
+            .filter(|(existing, _)| !wanted.iter().any(|w| w.eq_ignore_ascii_case(existing)))
+            .map(|(key, _)| RegistryChange::DeleteKey { key }),
+    );
+    changes
+}
+
+/// Removes an executable's hook in either layout.
+pub fn unhook_changes(registry: &impl Registry, exe_name: &str) -> Vec<RegistryChange> {
+    let mut changes = vec![RegistryChange::Delete { key: ifeo_key(exe_name), name: DEBUGGER_VALUE.to_string() }];
+    let ours = filter_keys(registry, exe_name);
+    if !ours.is_empty() {
+        changes.extend(ours.into_iter().map(|(key, _)| RegistryChange::DeleteKey { key }));
+        changes.extend(clear_use_filter(registry, exe_name));
+    }
+    changes
+}
+
+/// Turns filtering off, unless filters other than ours still need it.
+fn clear_use_filter(registry: &impl Registry, exe_name: &str) -> Option<RegistryChange> {
+    let key = ifeo_key(exe_name);
+    registry.dword(&key, USE_FILTER_VALUE)?;
+    let others = registry.subkeys(&key).into_iter().filter(|name| !is_our_filter_key(name)).any(|name| {
+        registry.string(&format!(r"{}\{}", key, name), FILTER_FULL_PATH_VALUE).is_some()
+    });
+    (!others).then(|| RegistryChange::Delete { key, name: USE_FILTER_VALUE.to_string() })
+}
+
+/// What the service deletes so an executable starts without the launcher.
+pub fn lift_changes(registry: &impl Registry, exe_name: &str) -> Vec<RegistryChange> {
+    hook_keys(registry, exe_name)
+        .into_iter()
+        .map(|key| RegistryChange::Delete { key, name: DEBUGGER_VALUE.to_string() })
+        .collect()
+}
+
+/// What the service sets to put a lifted hook back, in whichever layout it was lifted from.
+pub fn restore_changes(registry: &impl Registry, exe_name: &str, launcher_path: &Path) -> Vec<RegistryChange> {
+    let debugger = format!("\"{}\"", launcher_path.display());
+    hook_keys(registry, exe_name)
+        .into_iter()
+        .map(|key| RegistryChange::Set { key, name: DEBUGGER_VALUE.to_string(), value: debugger.clone() })
+        .collect()
+}
+
+/// Applies changes in order, stopping at the first that fails.
+pub fn apply_all(registry: &mut impl Registry, changes: &[RegistryChange]) -> io::Result<()> {
+    changes.iter().try_for_each(|change| registry.apply(change))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    String(String),
+    Dword(u32),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct MemoryKey {
+    /// The path as first written, as the registry keeps the case keys were created with.
+    path: String,
+    /// By lower-case name.
+    values: BTreeMap<String, Value>,
+}
+
+/// A registry kept in memory. Like the real one, key and value names ignore case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryRegistry {
+    /// By lower-case path.
+    keys: BTreeMap<String, MemoryKey>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn value(&self, key: &str, name: &str) -> Option<&Value> {
+        self.keys.get(&key.to_ascii_lowercase())?.values.get(&name.to_ascii_lowercase())
+    }
+
+    fn set(&mut self, key: &str, name: &str, value: Value) {
+        let entry = self.keys.entry(key.to_ascii_lowercase()).or_insert_with(|| MemoryKey {
+            path: key.to_string(),
+            values: BTreeMap::new(),
+        });
+        entry.values.insert(name.to_ascii_lowercase(), value);
+    }
+}
+
+impl Registry for MemoryRegistry {
+    fn string(&self, key: &str, name: &str) -> Option<String> {
+        match self.value(key, name)? {
+            Value::String(value) => Some(value.clone()),
+            Value::Dword(_) => None,
+        }
+    }
+
+    fn dword(&self, key: &str, name: &str) -> Option<u32> {
+        match self.value(key, name)? {
+            Value::Dword(value) => Some(*value),
+            Value::String(_) => None,
+        }
+    }
+
+    fn subkeys(&self, key: &str) -> Vec<String> {
+        let prefix = format!(r"{}\", key.to_ascii_lowercase());
+        let mut names: Vec<String> = self
+            .keys
+            .iter()
+            .filter(|(lower, _)| lower.starts_with(&prefix))
+            .filter_map(|(_, entry)| entry.path[prefix.len()..].split('\\').next().map(str::to_string))
+            .collect();
+        names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        names
+    }
+
+    fn apply(&mut self, change: &RegistryChange) -> io::Result<()> {
+        match change {
+            RegistryChange::Set { key, name, value } => self.set(key, name, Value::String(value.clone())),
+            RegistryChange::SetDword { key, name, value } => self.set(key, name, Value::Dword(*value)),
+            RegistryChange::Delete { key, name } => {
+                if let Some(entry) = self.keys.get_mut(&key.to_ascii_lowercase()) {
+                    entry.values.remove(&name.to_ascii_lowercase());
+                }
+            }
+            RegistryChange::DeleteKey { key } => {
+                let lower = key.to_ascii_lowercase();
+                let prefix = format!(r"{}\", lower);
+                self.keys.retain(|path, _| *path != lower && !path.starts_with(&prefix));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+pub use os::LocalMachine;
+
+#[cfg(windows)]
+mod os {
+    use std::io;
+
+    use winreg::RegKey;
+    use winreg::enums::*;
+
+    use super::Registry;
+    use crate::plan::RegistryChange;
+
+    /// The real `HKEY_LOCAL_MACHINE`.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct LocalMachine;
+
+    fn hklm() -> RegKey {
+        RegKey::predef(HKEY_LOCAL_MACHINE)
+    }
+
+    impl Registry for LocalMachine {
+        fn string(&self, key: &str, name: &str) -> Option<String> {
+            hklm().open_subkey(key).ok()?.get_value(name).ok()
+        }
+
+        fn dword(&self, key: &str, name: &str) -> Option<u32> {
+            hklm().open_subkey(key).ok()?.get_value(name).ok()
+        }
+
+        fn subkeys(&self, key: &str) -> Vec<String> {
+            match hklm().open_subkey(key) {
+                Ok(key) => key.enum_keys().flatten().collect(),
+                Err(_) => Vec::new(),
+            }
+        }
+
+        fn apply(&mut self, change: &RegistryChange) -> io::Result<()> {
+            match change {
+                RegistryChange::Set { key, name, value } => {
+                    let (subkey, _) = hklm().create_subkey_with_flags(key, KEY_ALL_ACCESS)?;
+                    subkey.set_value(name, value)
+                }
+                RegistryChange::SetDword { key, name, value } => {
+                    let (subkey, _) = hklm().create_subkey_with_flags(key, KEY_ALL_ACCESS)?;
+                    subkey.set_value(name, value)
+                }
+                RegistryChange::Delete { key, name } => match hklm().open_subkey_with_flags(key, KEY_ALL_ACCESS) {
+                    Ok(subkey) => match subkey.delete_value(name) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                        _ => Ok(()),
+                    },
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                    Err(e) => Err(e),
+                },
+                RegistryChange::DeleteKey { key } => match hklm().delete_subkey_all(key) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                    _ => Ok(()),
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Minimal-diff editing of Condor's Setup.ini files.
+//!
+//! Condor owns these files, so rather than parsing and re-serialising them we work on the raw
+//! bytes and touch only the line being changed. Comments, ordering, whitespace, line endings,
+//! BOMs and non-UTF-8 (e.g. Windows-1252) text elsewhere in the file are left exactly as they were.
+
+use std::ops::Range;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+
+/// One physical line of the file, as byte offsets.
+struct Line {
+    /// The line's text, excluding the line terminator.
+    content: Range<usize>,
+    /// Offset just past the line terminator (equal to `content.end` on the last line if it has none).
+    end: usize,
+}
+
+/// Whether the file is text we can safely edit: valid UTF-16 if it has a UTF-16 BOM, otherwise
+/// free of NUL bytes (which would suggest UTF-16 without a BOM, or a corrupt file).
+pub fn is_editable(contents: &[u8]) -> bool {
+    match contents.strip_prefix(UTF16LE_BOM) {
+        Some(utf16) => decode_utf16le(utf16).is_some(),
+        None => !contents.contains(&0),
+    }
+}
+
+/// Reads a value, matching section and key names case-insensitively as Windows does.
+pub fn get_value(contents: &[u8], section: &str, key: &str) -> Option<String> {
+    if contents.starts_with(UTF16LE_BOM) {
+        let utf8 = decode_utf16le(&contents[UTF16LE_BOM.len()..])?;
+        return get_value(utf8.as_bytes(), section, key);
+    }
+
+    let value = find_key(contents, section, key)?;
+    Some(String::from_utf8_lossy(&contents[value]).into_owned())
+}
+
+/// Lists the keys of a section in file order.
+pub fn keys(contents: &[u8], section: &str) -> Vec<String> {
+    if contents.starts_with(UTF16LE_BOM) {
+        return decode_utf16le(&contents[UTF16LE_BOM.len()..])
+            .map(|utf8| keys(utf8.as_bytes(), section))
+            .unwrap_or_default();
+    }
+
+    let mut keys = Vec::new();
+    let mut in_section = false;
+    for line in lines(contents) {
+        let text = &contents[line.content];
+        if let Some(name) = section_header(text) {
+            in_section = name.eq_ignore_ascii_case(section.as_bytes());
+            continue;
+        }
+        if !in_section || is_comment(text) {
+            continue;
+        }
+        if let Some(eq) = text.iter().position(|&b| b == b'=') {
+            keys.push(String::from_utf8_lossy(text[..eq].trim_ascii()).into_owned());
+        }
+    }
+    keys
+}
+
+/// Sets a value, replacing only the existing value's text. If the key is missing it is added at the
+/// end of the section, and if the section is missing it is appended to the file.
+pub fn set_value(contents: &[u8], section: &str, key: &str, value: &str) -> Vec<u8> {
+    if contents.starts_with(UTF16LE_BOM) {
+        let Some(utf8) = decode_utf16le(&contents[UTF16LE_BOM.len()..]) else {
+            // Not valid UTF-16, so we can't safely edit it.
+            return contents.to_vec();
+        };
+        let edited = set_value(utf8.as_bytes(), section, key, value);
+        let mut out = UTF16LE_BOM.to_vec();
+        for unit in String::from_utf8_lossy(&edited).encode_utf16() {
+            out.extend_from_slice(&unit.to_le_bytes());
+        }
+        return out;
+    }
+
+    if let Some(range) = find_key(contents, section, key) {
+        return splice(contents, range, value.as_bytes());
+    }
+
+    let nl = line_ending(contents);
+    let entry = format!("{}={}", key, value);
+
+    if let Some(line) = last_line_of_section(contents, section) {
+        // Insert after the section's last non-blank line, keeping any blank separator lines below it.
+        return if line.end > line.content.end {
+            splice(contents, line.end..line.end, format!("{}{}", entry, nl).as_bytes())
+        } else {
+            splice(contents, line.end..line.end, format!("{}{}", nl, entry).as_bytes())
+        };
+    }
+
+    let mut out = contents.to_vec();
+    let body_len = out.len() - bom_len(&out);
+    if body_len > 0 && !out.ends_with(b"\n") {
+        out.extend_from_slice(nl.as_bytes());
+    }
+    out.extend_from_slice(format!("[{}]{}{}{}", section, nl, entry, nl).as_bytes());
+    out
+}
+
+fn bom_len(contents: &[u8]) -> usize {
+    if contents.starts_with(UTF8_BOM) { UTF8_BOM.len() } else { 0 }
+}
+
+fn lines(contents: &[u8]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = bom_len(contents);
+    while start < contents.len() {
+        let (content_end, end) = match contents[start..].iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                let nl = start + i;
+                let content_end = if nl > start && contents[nl - 1] == b'\r' { nl - 1 } else { nl };
+                (content_end, nl + 1)
+            }
+            None => (contents.len(), contents.len()),
+        };
+        lines.push(Line { content: start..content_end, end });
+        start = end;
+    }
+    lines
+}
+
+/// Returns the section name if the line is a `[Section]` header.
+fn section_header(line: &[u8]) -> Option<&[u8]> {
+    let line = line.trim_ascii();
+    let rest = line.strip_prefix(b"[")?;
+    let close = rest.iter().position(|&b| b == b']')?;
+    Some(rest[..close].trim_ascii())
+}
+
+fn is_comment(line: &[u8]) -> bool {
+    matches!(line.trim_ascii().first(), Some(b';') | Some(b'#'))
+}
+
+/// Finds the byte range of the value for `key` in `section`, excluding surrounding whitespace.
+fn find_key(contents: &[u8], section: &str, key: &str) -> Option<Range<usize>> {
+    let mut in_section = false;
+    for line in lines(contents) {
+        let text = &contents[line.content.clone()];
+        if let Some(name) = section_header(text) {
+            in_section = name.eq_ignore_ascii_case(section.as_bytes());
+            continue;
+        }
+        if !in_section || is_comment(text) {
+            continue;
+        }
+        let Some(eq) = text.iter().position(|&b| b == b'=') else {
+            continue;
+        };
+        if !text[..eq].trim_ascii().eq_ignore_ascii_case(key.as_bytes()) {
+            continue;
+        }
+
+        let value = &text[eq + 1..];
+        let leading = value.len() - value.trim_ascii_start().len();
+        let trailing = value.len() - value.trim_ascii_end().len();
+        let start = line.content.start + eq + 1 + leading;
+        let end = (line.content.end - trailing).max(start);
+        return Some(start..end);
+    }
+    None
+}
+
+/// Finds the last non-blank line belonging to `section` (the header itself if the section is empty).
+fn last_line_of_section(contents: &[u8], section: &str) -> Option<Line> {
+    let mut found: Option<Line> = None;
+    let mut in_section = false;
+    for line in lines(contents) {
+        let text = &contents[line.content.clone()];
+        if let Some(name) = section_header(text) {
+            if in_section {
+                break;
+            }
+            in_section = name.eq_ignore_ascii_case(section.as_bytes());
+            if in_section {
+                found = Some(line);
+            }
+            continue;
+        }
+        if in_section && !text.trim_ascii().is_empty() {
+            found = Some(line);
+        }
+    }
+    found
+}
+
+/// The file's line ending, defaulting to CRLF as Condor is a Windows program.
+fn line_ending(contents: &[u8]) -> &'static str {
+    match contents.iter().position(|&b| b == b'\n') {
+        Some(i) if i > 0 && contents[i - 1] == b'\r' => "\r\n",
+        Some(_) => "\n",
+        None => "\r\n",
+    }
+}
+
+fn splice(contents: &[u8], range: Range<usize>, replacement: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(contents.len() + replacement.len());
+    out.extend_from_slice(&contents[..range.start]);
+    out.extend_from_slice(replacement);
+    out.extend_from_slice(&contents[range.end..]);
+    out
+}
+
+fn decode_utf16le(bytes: &[u8]) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]]));
+    char::decode_utf16(units).collect::<Result<String, _>>().ok()
+}
@@ -0,0 +1,122 @@
+//! Working out what Windows asked to run when it handed a launch to us as the IFEO debugger.
+//!
+//! Windows starts the debugger with the original command line appended to it, so our own command
+//! line is `CondorVR.exe <target> <args...>`, where the target may be quoted, unquoted, or even
+//! unquoted with spaces in it, exactly as whoever launched Condor wrote it.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::LAUNCHER_EXE_NAME;
+use crate::command_line::{RawArg, split_command_line};
+use crate::condor_version::CondorVersion;
+
+/// The program whose launch was intercepted, and the arguments it was given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterceptedLaunch {
+    pub target: PathBuf,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchParseError {
+    /// Nothing follows the launcher's own name, e.g. it was opened from the Start menu.
+    NoTarget,
+    /// The target is an empty string, e.g. `CondorVR.exe ""`.
+    EmptyTarget,
+    /// The first argument is an option rather than a program, e.g. `CondorVR.exe --help`.
+    UnexpectedOption(String),
+    /// The target is the launcher itself, which would loop forever.
+    TargetIsLauncher(PathBuf),
+}
+
+impl fmt::Display for LaunchParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoTarget => write!(f, "no program to launch was given"),
+            Self::EmptyTarget => write!(f, "the program to launch is an empty string"),
+            Self::UnexpectedOption(opt) => write!(f, "expected a program to launch, found option '{}'", opt),
+            Self::TargetIsLauncher(path) => write!(f, "refusing to launch {} from itself", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for LaunchParseError {}
+
+impl InterceptedLaunch {
+    /// Parses the launcher's full command line, including its own program name.
+    pub fn parse(command_line: &str) -> Result<Self, LaunchParseError> {
+        let raw = split_command_line(command_line);
+        let rest = raw.get(1..).unwrap_or_default();
+        let Some(first) = rest.first() else {
+            return Err(LaunchParseError::NoTarget);
+        };
+
+        if first.value.is_empty() {
+            return Err(LaunchParseError::EmptyTarget);
+        }
+        if !first.quoted && first.value.starts_with(['-', '/']) {
+            return Err(LaunchParseError::UnexpectedOption(first.value.clone()));
+        }
+
+        let target_len = unquoted_target_len(command_line, rest);
+        let target = match target_len {
+            1 => PathBuf::from(&first.value),
+            n => PathBuf::from(&command_line[first.span.start..rest[n - 1].span.end]),
+        };
+        if is_launcher(&target) {
+            return Err(LaunchParseError::TargetIsLauncher(target));
+        }
+
+        Ok(Self {
+            target,
+            args: rest[target_len..].iter().map(|a| a.value.clone()).collect(),
+        })
+    }
+
+    /// The target's file name, e.g. `Condor.exe`, which is what IFEO matched on.
+    pub fn exe_name(&self) -> String {
+        file_name(&self.target).to_string()
+    }
+
+    /// The Condor version, if the target's folder says which, e.g. `C:\Condor3\Condor.exe`.
+    pub fn condor_version(&self) -> Option<CondorVersion> {
+        let text = self.target.to_str()?;
+        let (dir, _) = text.rsplit_once(['\\', '/'])?;
+        CondorVersion::from_folder_name(file_name(Path::new(dir)))
+    }
+}
+
+/// The last component of a path, splitting on either separator so Windows paths work everywhere.
+pub(crate) fn file_name(path: &Path) -> &str {
+    let text = path.to_str().unwrap_or_default();
+    text.rsplit(['\\', '/']).next().unwrap_or_default()
+}
+
+/// How many arguments make up the target. An unquoted path with spaces, such as
+/// `C:\Program Files\Condor3\Condor.exe`, is split into several arguments; like `CreateProcess`,
+/// we rejoin them up to the first one that ends in `.exe`.
+fn unquoted_target_len(command_line: &str, args: &[RawArg]) -> usize {
+    if args[0].quoted || has_exe_extension(&args[0].value) {
+        return 1;
+    }
+    for (i, arg) in args.iter().enumerate().skip(1) {
+        if arg.quoted {
+            break;
+        }
+        if has_exe_extension(&command_line[args[0].span.start..arg.span.end]) {
+            return i + 1;
+        }
+    }
+    1
+}
+
+fn has_exe_extension(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+}
+
+fn is_launcher(path: &Path) -> bool {
+    file_name(path).eq_ignore_ascii_case(LAUNCHER_EXE_NAME)
+}
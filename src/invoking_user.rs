@@ -0,0 +1,136 @@
+//! The user the helper is acting for.
+//!
+//! The GUI runs as the pilot, but the configurer may be elevated with an administrator's
+//! credentials, in which case its own Documents and AppData belong to the wrong person. The GUI
+//! therefore passes the pilot's identity and folders on the configurer's command line.
+//!
+//! Those folders are writable by the pilot, so the configurer refuses any that goes through a
+//! symbolic link or junction, which could otherwise point its writes at files the pilot can't
+//! change themselves.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::atomic_write::{LinkRefused, is_link};
+use crate::backup::{BackupStore, DEFAULT_RETENTION};
+use crate::profiles::{CondorProfileSet, default_documents_dir};
+use crate::settings::UserSettings;
+
+const USER_ARG: &str = "--user";
+const DOCUMENTS_ARG: &str = "--documents";
+const SETTINGS_ARG: &str = "--settings";
+const BACKUPS_ARG: &str = "--backups";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvokingUser {
+    /// `DOMAIN\name`, for logs.
+    pub name: String,
+    pub documents: Option<PathBuf>,
+    /// The user's `settings.ini`.
+    pub settings_path: Option<PathBuf>,
+    pub backups_root: PathBuf,
+}
+
+impl InvokingUser {
+    /// The user this process is running as.
+    pub fn current() -> Self {
+        let name = match (std::env::var("USERDOMAIN"), std::env::var("USERNAME")) {
+            (Ok(domain), Ok(user)) => format!(r"{}\{}", domain, user),
+            (_, Ok(user)) => user,
+            _ => std::env::var("USER").unwrap_or_default(),
+        };
+        Self {
+            name,
+            documents: default_documents_dir(),
+            settings_path: UserSettings::default_path(),
+            backups_root: BackupStore::open_default().root().to_path_buf(),
+        }
+    }
+
+    /// The arguments that hand this user over to another process, see [`InvokingUser::from_args`].
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![USER_ARG.to_string(), self.name.clone()];
+        if let Some(documents) = &self.documents {
+            args.extend([DOCUMENTS_ARG.to_string(), documents.to_string_lossy().into_owned()]);
+        }
+        if let Some(settings) = &self.settings_path {
+            args.extend([SETTINGS_ARG.to_string(), settings.to_string_lossy().into_owned()]);
+        }
+        args.extend([BACKUPS_ARG.to_string(), self.backups_root.to_string_lossy().into_owned()]);
+        args
+    }
+
+    /// Takes the user arguments out of `args`, returning the user (if `--user` was given) and the
+    /// remaining arguments in order. A folder that goes through a link is an error.
+    pub fn from_args(args: &[String]) -> io::Result<(Option<Self>, Vec<String>)> {
+        let mut name = None;
+        let mut documents = None;
+        let mut settings_path = None;
+        let mut backups_root = None;
+        let mut rest = Vec::new();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let slot = match arg.as_str() {
+                USER_ARG => &mut name,
+                DOCUMENTS_ARG => &mut documents,
+                SETTINGS_ARG => &mut settings_path,
+                BACKUPS_ARG => &mut backups_root,
+                _ => {
+                    rest.push(arg.clone());
+                    continue;
+                }
+            };
+            let value = iter.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("{} needs a value", arg))
+            })?;
+            *slot = Some(value.clone());
+        }
+
+        let Some(name) = name else {
+            return Ok((None, rest));
+        };
+        let user = Self {
+            name,
+            documents: documents.map(PathBuf::from),
+            settings_path: settings_path.map(PathBuf::from),
+            backups_root: match backups_root {
+                Some(root) => PathBuf::from(root),
+                None => BackupStore::open_default().root().to_path_buf(),
+            },
+        };
+        for path in user.documents.iter().chain(&user.settings_path).chain([&user.backups_root]) {
+            check_no_links(path)?;
+        }
+        Ok((Some(user), rest))
+    }
+
+    pub fn load_settings(&self) -> UserSettings {
+        self.settings_path.as_deref().map(UserSettings::load).unwrap_or_default()
+    }
+
+    pub fn save_settings(&self, settings: &UserSettings) -> io::Result<()> {
+        let path = self
+            .settings_path
+            .as_deref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No settings directory"))?;
+        settings.save(path)
+    }
+
+    /// The user's Condor data directories: their Documents plus the folders in their settings.
+    pub fn profiles(&self, settings: &UserSettings) -> CondorProfileSet {
+        CondorProfileSet::discover_configured(self.documents.as_deref(), settings)
+    }
+
+    pub fn backups(&self) -> BackupStore {
+        BackupStore::new(self.backups_root.clone(), DEFAULT_RETENTION)
+    }
+}
+
+/// Fails if `path` or any folder above it is a symbolic link or junction.
+fn check_no_links(path: &Path) -> io::Result<()> {
+    match path.ancestors().find(|p| is_link(p)) {
+        Some(link) => Err(io::Error::new(io::ErrorKind::InvalidInput, LinkRefused { path: link.to_path_buf() })),
+        None => Ok(()),
+    }
+}
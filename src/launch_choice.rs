@@ -0,0 +1,46 @@
+//! The launcher's "VR or flatscreen?" question: a default that is picked by itself once the
+//! timeout runs out, unless the user chooses first.
+
+use std::time::Duration;
+
+use crate::graphics_profile::GraphicsMode;
+use crate::settings::UserSettings;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchChooser {
+    pub default: GraphicsMode,
+    pub timeout: Duration,
+    chosen: Option<GraphicsMode>,
+}
+
+impl LaunchChooser {
+    pub fn new(default: GraphicsMode, timeout: Duration) -> Self {
+        Self { default, timeout, chosen: None }
+    }
+
+    /// The chooser for one hooked executable: its remembered choice if there is one and choices
+    /// are remembered, otherwise the configured default.
+    pub fn for_exe(settings: &UserSettings, exe_name: &str) -> Self {
+        let remembered = settings.remember_launch_mode().then(|| settings.last_launch_mode(exe_name)).flatten();
+        Self::new(
+            remembered.unwrap_or(settings.default_launch_mode()),
+            Duration::from_secs(settings.launch_choice_timeout().into()),
+        )
+    }
+
+    /// Records the user's choice. Only the first one counts.
+    pub fn choose(&mut self, mode: GraphicsMode) {
+        self.chosen.get_or_insert(mode);
+    }
+
+    /// The mode to launch in, once decided: the user's choice, or the default after the timeout.
+    pub fn decision(&self, elapsed: Duration) -> Option<GraphicsMode> {
+        self.chosen.or((elapsed >= self.timeout).then_some(self.default))
+    }
+
+    /// Whole seconds until the default is picked, rounded up for display.
+    pub fn seconds_left(&self, elapsed: Duration) -> u64 {
+        let left = self.timeout.saturating_sub(elapsed);
+        left.as_secs() + u64::from(left.subsec_nanos() > 0)
+    }
+}
@@ -0,0 +1,219 @@
+//! A record of each launch through the launcher, kept so the GUI can show why one failed.
+//!
+//! Records are appended one JSON object per line to `%LOCALAPPDATA%\CondorVR\launches.jsonl`, so
+//! launchers running at the same time never overwrite each other's. A record saved again is
+//! appended again, and the last copy with the same start time and id wins. Only the most recent [`DEFAULT_RETENTION`] are shown,
+//! and [`LaunchHistory::trim`] drops the rest from the file.
+
+use std::collections::BTreeMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write::{self, write_atomic};
+use crate::backup::format_utc;
+use crate::graphics_profile::GraphicsMode;
+use crate::intercepted_launch::{InterceptedLaunch, file_name};
+use crate::process_watch::ProcessRun;
+use crate::settings::SETTINGS_DIR;
+
+pub const HISTORY_FILE: &str = "launches.jsonl";
+/// The number of launches kept.
+pub const DEFAULT_RETENTION: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchOutcome {
+    /// The program started and was still running after the early-exit check.
+    Launched,
+    Failed,
+}
+
+/// How long one step of a launch took, such as waiting for the service or running the injector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stage {
+    pub name: String,
+    pub millis: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    /// Milliseconds since the Unix epoch when the launch began.
+    pub started_at: u64,
+    /// Tells apart launches that began in the same millisecond. Records from older versions have 0.
+    #[serde(default)]
+    pub id: u64,
+    pub target: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<GraphicsMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub injector_path: Option<PathBuf>,
+    #[serde(default)]
+    pub stages: Vec<Stage>,
+    pub outcome: LaunchOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Filled in once the launched program exits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ran_for_secs: Option<u64>,
+}
+
+impl LaunchRecord {
+    pub fn new(launch: &InterceptedLaunch, started_at: SystemTime) -> Self {
+        Self {
+            started_at: started_at.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            id: new_id(),
+            target: launch.target.clone(),
+            args: launch.args.clone(),
+            mode: None,
+            injector_path: None,
+            stages: Vec::new(),
+            outcome: LaunchOutcome::Launched,
+            error: None,
+            exit_code: None,
+            ran_for_secs: None,
+        }
+    }
+
+    pub fn add_stage(&mut self, name: &str, took: Duration) {
+        self.stages.push(Stage { name: name.to_string(), millis: took.as_millis() as u64 });
+    }
+
+    /// Marks the launch as failed with the message shown to the pilot.
+    pub fn fail(&mut self, error: &str) {
+        self.outcome = LaunchOutcome::Failed;
+        self.error = Some(error.to_string());
+    }
+
+    /// Records how the launched program ran; a run that counts as a problem fails the launch.
+    pub fn set_run(&mut self, run: &ProcessRun) {
+        self.exit_code = Some(run.exit_code);
+        self.ran_for_secs = Some(run.lifetime().as_secs());
+        if let Some(problem) = run.problem() {
+            self.fail(&problem);
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.outcome == LaunchOutcome::Failed
+    }
+
+    /// The target's file name, e.g. `Condor.exe`.
+    pub fn exe_name(&self) -> String {
+        file_name(&self.target).to_string()
+    }
+
+    /// The start time as "YYYY-MM-DD HH:MM:SS UTC".
+    pub fn timestamp(&self) -> String {
+        format_utc(UNIX_EPOCH + Duration::from_millis(self.started_at))
+    }
+
+    pub fn total_duration(&self) -> Duration {
+        Duration::from_millis(self.stages.iter().map(|s| s.millis).sum())
+    }
+
+    /// Whether the record mentions `text` in its target, arguments or error, ignoring case.
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.trim().to_lowercase();
+        text.is_empty()
+            || self.target.to_string_lossy().to_lowercase().contains(&text)
+            || self.args.iter().any(|a| a.to_lowercase().contains(&text))
+            || self.error.as_deref().is_some_and(|e| e.to_lowercase().contains(&text))
+    }
+}
+
+/// A random id for a new record, mixed with this process's PID and a counter so launchers
+/// starting together, or records made in quick succession, never share one.
+fn new_id() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    RandomState::new().hash_one((std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)))
+}
+
+#[derive(Debug, Clone)]
+pub struct LaunchHistory {
+    path: PathBuf,
+    retention: usize,
+}
+
+impl LaunchHistory {
+    pub fn new(path: PathBuf, retention: usize) -> Self {
+        Self { path, retention: retention.max(1) }
+    }
+
+    /// The default location, `%LOCALAPPDATA%\CondorVR\launches.jsonl`.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(BaseDirs::new()?.data_local_dir().join(SETTINGS_DIR).join(HISTORY_FILE))
+    }
+
+    /// Opens the history at the default location, falling back to the temp directory.
+    pub fn open_default() -> Self {
+        let path = Self::default_path().unwrap_or_else(|| std::env::temp_dir().join(SETTINGS_DIR).join(HISTORY_FILE));
+        Self::new(path, DEFAULT_RETENTION)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every stored launch, newest first. Lines that can't be read are skipped.
+    pub fn load(&self) -> Vec<LaunchRecord> {
+        let (mut records, _) = self.load_oldest_first();
+        records.reverse();
+        records
+    }
+
+    /// The newest `retention` records, oldest first, and the number of lines they were read from.
+    fn load_oldest_first(&self) -> (Vec<LaunchRecord>, usize) {
+        let Ok(text) = std::fs::read_to_string(&self.path) else {
+            return (Vec::new(), 0);
+        };
+        let mut by_start = BTreeMap::new();
+        let mut lines = 0;
+        for line in text.lines() {
+            lines += 1;
+            if let Ok(record) = serde_json::from_str::<LaunchRecord>(line) {
+                by_start.insert((record.started_at, record.id), record);
+            }
+        }
+        let mut records: Vec<LaunchRecord> = by_start.into_values().collect();
+        let excess = records.len().saturating_sub(self.retention);
+        records.drain(..excess);
+        (records, lines)
+    }
+
+    /// Stores a launch by appending it; a later save of the same record supersedes this one.
+    pub fn save(&self, record: &LaunchRecord) -> io::Result<()> {
+        let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+        line.push('\n');
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        atomic_write::append(&self.path, line.as_bytes())
+    }
+
+    /// Rewrites the file with only the records [`LaunchHistory::load`] returns, once superseded
+    /// copies and old records make it more than twice as long as needed. A launch saved while the
+    /// file is rewritten can be lost, so this is left to the GUI rather than every launcher.
+    pub fn trim(&self) -> io::Result<()> {
+        let (records, lines) = self.load_oldest_first();
+        if lines <= self.retention * 2 {
+            return Ok(());
+        }
+        let mut text = String::new();
+        for record in &records {
+            text.push_str(&serde_json::to_string(record).map_err(io::Error::other)?);
+            text.push('\n');
+        }
+        write_atomic(&self.path, text.as_bytes())
+    }
+}
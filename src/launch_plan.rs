@@ -0,0 +1,116 @@
+//! Exactly what the launcher runs for an intercepted launch, worked out up front so it can be
+//! logged and tested, then handed to a thin spawner.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+
+use crate::command_line::join_args;
+use crate::intercepted_launch::InterceptedLaunch;
+
+/// `CREATE_NO_WINDOW`: the injector is a console program, but nobody needs to see its console.
+pub const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// How to run the injector that starts the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectorSettings {
+    pub path: PathBuf,
+    /// Arguments placed before the target, e.g. injector options.
+    pub args: Vec<String>,
+    /// Environment variables set for the injector (and so for the game it starts).
+    pub env: BTreeMap<String, String>,
+}
+
+impl InjectorSettings {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), args: Vec::new(), env: BTreeMap::new() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchPlan {
+    pub exe: PathBuf,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    /// Variables added to (or replacing those in) the launcher's own environment.
+    pub env: BTreeMap<String, String>,
+    pub creation_flags: u32,
+}
+
+impl LaunchPlan {
+    /// Runs the intercepted target through the injector, from the injector's own directory so it
+    /// can find the Revive DLLs next to it.
+    pub fn for_injector(injector: &InjectorSettings, launch: &InterceptedLaunch) -> Self {
+        let mut args = injector.args.clone();
+        args.push(launch.target.to_string_lossy().into_owned());
+        args.extend(launch.args.iter().cloned());
+
+        Self {
+            exe: injector.path.clone(),
+            args,
+            cwd: parent_dir(&injector.path),
+            env: injector.env.clone(),
+            creation_flags: CREATE_NO_WINDOW,
+        }
+    }
+
+    /// Runs the intercepted target as it was asked for, without the injector, e.g. for a
+    /// flatscreen launch while the hook is active.
+    pub fn direct(launch: &InterceptedLaunch) -> Self {
+        Self {
+            exe: launch.target.clone(),
+            args: launch.args.clone(),
+            cwd: parent_dir(&launch.target),
+            env: BTreeMap::new(),
+            creation_flags: 0,
+        }
+    }
+
+    /// The `Command` that carries out the plan.
+    pub fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.exe);
+        cmd.args(&self.args).envs(&self.env);
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(self.creation_flags);
+        }
+        cmd
+    }
+
+    pub fn spawn(&self) -> io::Result<Child> {
+        self.to_command().spawn()
+    }
+}
+
+/// The folder containing `path`, splitting on either separator so Windows paths work everywhere.
+fn parent_dir(path: &Path) -> Option<PathBuf> {
+    let text = path.to_string_lossy();
+    let (dir, _) = text.rsplit_once(['\\', '/'])?;
+    match dir {
+        "" => None,
+        // `C:` alone would mean the current directory on drive C.
+        drive if drive.ends_with(':') => Some(PathBuf::from(format!("{}\\", drive))),
+        dir => Some(PathBuf::from(dir)),
+    }
+}
+
+impl fmt::Display for LaunchPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut command_line = vec![self.exe.to_string_lossy().into_owned()];
+        command_line.extend(self.args.iter().cloned());
+        write!(f, "{}", join_args(&command_line))?;
+        if let Some(cwd) = &self.cwd {
+            write!(f, "\n  in {}", cwd.display())?;
+        }
+        for (key, value) in &self.env {
+            write!(f, "\n  with {}={}", key, value)?;
+        }
+        write!(f, "\n  creation flags {:#010x}", self.creation_flags)
+    }
+}
@@ -1,7 +1,52 @@
 use std::env;
 use std::path::{Path, PathBuf};
-use directories::UserDirs;
-use ini::Ini;
+
+pub mod atomic_write;
+pub mod backup;
+pub mod bypass_lease;
+pub mod bypass_protocol;
+pub mod bypass_queue;
+pub mod command_line;
+pub mod condor_version;
+pub mod config;
+pub mod file_security;
+pub mod flatscreen_session;
+pub mod graphics_profile;
+pub mod hooked_exe;
+pub mod ifeo;
+pub mod ini_edit;
+pub mod intercepted_launch;
+pub mod invoking_user;
+pub mod launch_choice;
+pub mod launch_history;
+pub mod launch_plan;
+pub mod logging;
+pub mod outcome;
+pub mod pe_version;
+pub mod plan;
+pub mod process_watch;
+pub mod profiles;
+pub mod revive_version;
+pub mod settings;
+
+pub use atomic_write::write_atomic;
+pub use backup::BackupStore;
+pub use condor_version::CondorVersion;
+pub use config::{Config, ConfigError};
+pub use flatscreen_session::FlatscreenSession;
+pub use graphics_profile::GraphicsMode;
+pub use hooked_exe::HookedExe;
+pub use intercepted_launch::{InterceptedLaunch, LaunchParseError};
+pub use launch_choice::LaunchChooser;
+pub use launch_history::{LaunchHistory, LaunchRecord};
+pub use launch_plan::{InjectorSettings, LaunchPlan};
+pub use logging::{Component, LogLevel, Logger};
+pub use invoking_user::InvokingUser;
+pub use outcome::{SetupIniError, SetupIniOutcome};
+pub use plan::{ActivationPlan, SetupIniChange};
+pub use profiles::{CondorProfileSet, SetupFile};
+pub use revive_version::Compatibility;
+pub use settings::UserSettings;
 
 pub const TARGET_EXE: &str = "Condor.exe";
 pub const IFEO_PATH: &str = r#"Software\Microsoft\Windows NT\CurrentVersion\Image File Execution Options"#;
@@ -9,70 +54,111 @@ pub const SETTINGS_PATH: &str = r#"Software\CondorVR"#;
 pub const SERVICE_NAME: &str = "CondorReviveHelperService";
 pub const LAUNCHER_EXE_NAME: &str = "CondorVR.exe";
 pub const CONFIGURER_EXE_NAME: &str = "Condor-VR-Configurer.exe";
+pub const GRAPHICS_SECTION: &str = "Graphics";
+pub const VR_KEY: &str = "VROculusRift";
 
-/// Updates VROculusRift in Setup.ini files across all Condor directories in Documents.
-pub fn update_condor_setup_ini(vr_enabled: bool) -> Vec<(String, bool)> {
-    let mut results = Vec::new();
-    let val = if vr_enabled { "1" } else { "0" };
-
-    if let Some(user_dirs) = UserDirs::new()
-        && let Some(docs) = user_dirs.document_dir()
-        && let Ok(entries) = std::fs::read_dir(docs) {
-        for entry in entries.flatten() {
-            if !entry.path().is_dir() {
-                continue;
-            }
+/// Updates VROculusRift in every Setup.ini of the given profile set, swapping graphics profiles as it goes.
+/// Setup.ini files the user has opted out of VR for are always set to flatscreen.
+/// Snapshots taken along the way are stored in `settings`, which the caller should save.
+pub fn update_condor_setup_ini(
+    profiles: &CondorProfileSet,
+    settings: &mut UserSettings,
+    backups: &BackupStore,
+    vr_enabled: bool,
+) -> Vec<SetupIniOutcome> {
+    let changes = plan::plan_setup_ini(profiles, settings, vr_enabled);
+    apply_setup_ini_changes(&changes, settings, backups)
+}
 
-            let cond_dir_name = entry.file_name().to_string_lossy().into_owned();
-            if !cond_dir_name.contains("Condor") {
-                continue;
-            }
+/// Records whether the given Setup.ini files should use VR, e.g. one pilot or a whole data directory.
+/// If VR is currently enabled the files are updated straight away; otherwise only the choice is stored.
+pub fn select_vr_for(
+    settings: &mut UserSettings,
+    backups: &BackupStore,
+    files: &[SetupFile],
+    selected: bool,
+    vr_enabled: bool,
+) -> Vec<SetupIniOutcome> {
+    for file in files {
+        settings.set_vr_selected(&file.path, selected);
+    }
+    if !vr_enabled {
+        return Vec::new();
+    }
 
-            let base_dir = entry.path();
-            
-            // 1. Update global Setup.ini
-            let global_setup = base_dir.join("Setup.ini");
-            if global_setup.exists() {
-                if update_ini_file(&global_setup, val) {
-                    results.push((format!("Global Settings ({})", cond_dir_name), true));
-                } else {
-                    results.push((format!("Global Settings ({})", cond_dir_name), false));
-                }
-            }
+    let changes: Vec<SetupIniChange> = files
+        .iter()
+        .map(|file| plan::plan_setup_file(file.clone(), settings, selected))
+        .collect();
+    apply_setup_ini_changes(&changes, settings, backups)
+}
 
-            // 2. Update all pilot Setup.ini files
-            let pilots_dir = base_dir.join("Pilots");
-            if let Ok(p_entries) = std::fs::read_dir(pilots_dir) {
-                for p_entry in p_entries.flatten() {
-                    if p_entry.path().is_dir() {
-                        let p_name = p_entry.file_name().to_string_lossy().into_owned();
-                        let p_setup = p_entry.path().join("Setup.ini");
-                        if p_setup.exists() {
-                            if update_ini_file(&p_setup, val) {
-                                results.push((format!("Pilot: {} ({})", p_name, cond_dir_name), true));
-                            } else {
-                                results.push((format!("Pilot: {} ({})", p_name, cond_dir_name), false));
-                            }
-                        }
-                    }
-                }
+/// Applies planned Setup.ini changes, e.g. from an [`ActivationPlan`], and stores the graphics
+/// snapshots of every file that was written in `settings`.
+pub fn apply_setup_ini_changes(
+    changes: &[SetupIniChange],
+    settings: &mut UserSettings,
+    backups: &BackupStore,
+) -> Vec<SetupIniOutcome> {
+    changes
+        .iter()
+        .map(|change| {
+            let (previous, error) = match update_ini_file(change, backups) {
+                Ok(previous) => (previous, None),
+                Err(e) => (change.before.clone(), Some(e)),
+            };
+            if error.is_none()
+                && let Some((mode, values)) = &change.snapshot
+            {
+                settings.set_snapshot(&change.file.path, *mode, values.clone());
             }
-        }
-    }
-    results
+            SetupIniOutcome {
+                file: change.file.clone(),
+                previous,
+                new: change.after.clone(),
+                profile: change.profile.clone(),
+                error,
+            }
+        })
+        .collect()
 }
 
-fn update_ini_file(path: &Path, val: &str) -> bool {
-    if let Ok(mut conf) = Ini::load_from_file(path) {
-        conf.with_section(Some("Graphics"))
-            .set("VROculusRift", val);
-        conf.write_to_file(path).is_ok()
-    } else {
-        false
+/// Reads `[Graphics] VROculusRift` from a Setup.ini, treating a missing file or key as disabled.
+pub fn read_vr_enabled(path: &Path) -> bool {
+    std::fs::read(path)
+        .ok()
+        .and_then(|contents| ini_edit::get_value(&contents, GRAPHICS_SECTION, VR_KEY))
+        .is_some_and(|val| val.trim() == "1")
+}
+
+/// Sets VROculusRift and the graphics profile in one Setup.ini with a single write, backing it up
+/// first. The file is left alone if the backup fails. Returns the VR value the file held just
+/// before, which may differ from the plan's if something else changed it in between.
+fn update_ini_file(change: &SetupIniChange, backups: &BackupStore) -> Result<Option<String>, SetupIniError> {
+    let file = &change.file;
+    let contents = std::fs::read(&file.path).map_err(|e| SetupIniError::from_io(&e))?;
+    if !ini_edit::is_editable(&contents) {
+        return Err(SetupIniError::Parse);
+    }
+
+    let (vr_section, vr_key) = file.version.vr_key();
+    let previous = ini_edit::get_value(&contents, vr_section, vr_key);
+    let mut updated = ini_edit::set_value(&contents, vr_section, vr_key, &change.after);
+    for key in &change.profile {
+        updated = ini_edit::set_value(&updated, GRAPHICS_SECTION, &key.key, &key.after);
     }
+    if updated == contents {
+        return Ok(previous);
+    }
+    backups
+        .backup(file)
+        .map_err(|e| SetupIniError::BackupFailed(e.to_string()))?;
+    write_atomic(&file.path, &updated).map_err(|e| SetupIniError::from_io(&e))?;
+    Ok(previous)
 }
 
 /// Validates that a path is not a symbolic link or junction (reparse point).
+#[cfg_attr(not(windows), allow(unused_variables))]
 pub fn is_safe_path(path: &Path) -> bool {
     #[cfg(windows)]
     {
@@ -146,20 +232,9 @@ pub fn handle_version_args(program_name: &str) -> bool {
     }
 }
 
-/// Finds the ReviveInjector executable path from registry or common locations.
+/// Finds the ReviveInjector executable in its usual install locations. A path set in the config
+/// takes precedence over this.
 pub fn find_revive_injector() -> Option<String> {
-    #[cfg(windows)]
-    {
-        use winreg::RegKey;
-        use winreg::enums::*;
-        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-        if let Ok(settings_key) = hklm.open_subkey(SETTINGS_PATH)
-            && let Ok(revive_path) = settings_key.get_value::<String, _>("ReviveInjectorPath")
-            && Path::new(&revive_path).exists() {
-            return Some(revive_path);
-        }
-    }
-
     let fallbacks = [
         r#"C:\Program Files\Revive\Revive\ReviveInjector.exe"#,
         r#"C:\Program Files\Revive\Revive\x64\ReviveInjector.exe"#,
@@ -174,17 +249,16 @@ pub fn find_revive_injector() -> Option<String> {
     None
 }
 
-/// Checks if the IFEO hook for Condor.exe is present in the registry.
-pub fn is_ifeo_hook_present() -> bool {
+/// Checks if the IFEO hook for an executable, e.g. Condor.exe, is present in the registry, whether
+/// it catches every copy or only the filtered installs.
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn is_ifeo_hook_present(exe_name: &str) -> bool {
     #[cfg(windows)]
     {
-        use winreg::RegKey;
-        use winreg::enums::*;
-        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-        let target_key_path = format!(r#"{}\{}"#, IFEO_PATH, TARGET_EXE);
-        if let Ok(key) = hklm.open_subkey(target_key_path) {
-            return key.get_value::<String, _>("Debugger").is_ok();
-        }
+        ifeo::is_hooked(&ifeo::LocalMachine, exe_name)
+    }
+    #[cfg(not(windows))]
+    {
+        false
     }
-    false
 }
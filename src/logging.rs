@@ -0,0 +1,261 @@
+//! Logging shared by the four programs: one JSON object per line in `<component>.jsonl`, rotated by
+//! size. The configurer and service run elevated and log under `%ProgramData%\CondorVR`; the launcher
+//! and GUI run as the signed-in user, who can't rotate files an administrator created there, so they
+//! log under `%LOCALAPPDATA%\CondorVR\Logs` instead.
+//!
+//! Messages are always echoed to the console; files are only written with the `logging` feature.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write;
+use crate::settings::SETTINGS_DIR;
+use crate::{get_secure_log_path, is_safe_path};
+
+pub const LOG_DIR: &str = "CondorVR";
+/// The per-user log folder under `%LOCALAPPDATA%\CondorVR`.
+pub const USER_LOG_DIR: &str = "Logs";
+/// A log file is rotated once it would grow past this size.
+pub const DEFAULT_MAX_BYTES: u64 = 256 * 1024;
+/// The number of rotated files kept, e.g. `launcher.1.jsonl` to `launcher.3.jsonl`.
+pub const DEFAULT_KEEP: usize = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    const ALL: [Self; 5] = [Self::Off, Self::Error, Self::Warn, Self::Info, Self::Debug];
+
+    /// Whether a message at `level` should be written.
+    pub fn allows(self, level: LogLevel) -> bool {
+        level != Self::Off && level <= self
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Off => "OFF",
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+        };
+        f.pad(name)
+    }
+}
+
+/// Which program wrote a record, which also names its log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Component {
+    Launcher,
+    Configurer,
+    Service,
+    Gui,
+}
+
+impl Component {
+    /// Whether the program runs elevated or as SYSTEM, and so logs to the machine-wide folder.
+    pub fn is_elevated(self) -> bool {
+        matches!(self, Self::Configurer | Self::Service)
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Launcher => "launcher",
+            Self::Configurer => "configurer",
+            Self::Service => "service",
+            Self::Gui => "gui",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Local time with its UTC offset, e.g. `2026-10-17T19:04:05.123+01:00`.
+    pub time: String,
+    pub level: LogLevel,
+    pub component: Component,
+    pub pid: u32,
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:<5} {}[{}]: {}", self.time, self.level, self.component, self.pid, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub struct Logger {
+    component: Component,
+    level: AtomicU8,
+    /// `None` to only log to the console.
+    path: Option<PathBuf>,
+    max_bytes: u64,
+    keep: usize,
+    /// Set after the first failed write, which is reported once on the console.
+    write_failed: AtomicBool,
+}
+
+impl Logger {
+    /// Logs to the component's file in the shared log directory, if the `logging` feature is on.
+    pub fn new(component: Component) -> Self {
+        let path = cfg!(feature = "logging").then(|| Self::default_path(component));
+        Self::with_path(component, path, DEFAULT_MAX_BYTES, DEFAULT_KEEP)
+    }
+
+    /// Logs only to the console, e.g. for dry runs.
+    pub fn console_only(component: Component) -> Self {
+        Self::with_path(component, None, DEFAULT_MAX_BYTES, DEFAULT_KEEP)
+    }
+
+    pub fn with_path(component: Component, path: Option<PathBuf>, max_bytes: u64, keep: usize) -> Self {
+        Self {
+            component,
+            level: AtomicU8::new(LogLevel::default() as u8),
+            path,
+            max_bytes,
+            keep,
+            write_failed: AtomicBool::new(false),
+        }
+    }
+
+    /// `%ProgramData%\CondorVR\<component>.jsonl` for elevated programs, otherwise
+    /// `%LOCALAPPDATA%\CondorVR\Logs\<component>.jsonl`.
+    pub fn default_path(component: Component) -> PathBuf {
+        let file_name = format!("{}.jsonl", component);
+        if component.is_elevated() {
+            return get_secure_log_path(LOG_DIR, &file_name);
+        }
+        match BaseDirs::new() {
+            Some(dirs) => dirs.data_local_dir().join(SETTINGS_DIR).join(USER_LOG_DIR).join(file_name),
+            None => std::env::temp_dir().join(format!("{}_{}", LOG_DIR, file_name)),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn level(&self) -> LogLevel {
+        LogLevel::ALL[self.level.load(Ordering::Relaxed) as usize]
+    }
+
+    pub fn set_level(&self, level: LogLevel) {
+        self.level.store(level as u8, Ordering::Relaxed);
+    }
+
+    pub fn log(&self, level: LogLevel, message: &str) {
+        if !self.level().allows(level) {
+            return;
+        }
+        if level <= LogLevel::Warn {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+        if let Some(path) = &self.path {
+            let record = LogRecord {
+                time: now(),
+                level,
+                component: self.component,
+                pid: std::process::id(),
+                message: message.to_string(),
+            };
+            if let Err(e) = self.write(path, &record)
+                && !self.write_failed.swap(true, Ordering::Relaxed)
+            {
+                eprintln!("Warning: could not write to the log file {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    pub fn error(&self, message: &str) {
+        self.log(LogLevel::Error, message);
+    }
+
+    pub fn warn(&self, message: &str) {
+        self.log(LogLevel::Warn, message);
+    }
+
+    pub fn info(&self, message: &str) {
+        self.log(LogLevel::Info, message);
+    }
+
+    pub fn debug(&self, message: &str) {
+        self.log(LogLevel::Debug, message);
+    }
+
+    fn write(&self, path: &Path, record: &LogRecord) -> io::Result<()> {
+        if path.exists() && !is_safe_path(path) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the log file is a link or junction"));
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+        line.push('\n');
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > self.max_bytes {
+            rotate(path, self.keep);
+        }
+        atomic_write::append(path, line.as_bytes())
+    }
+}
+
+/// `launcher.jsonl` becomes `launcher.<n>.jsonl`.
+pub fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}.{}.{}", stem, n, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}.{}", stem, n)),
+    }
+}
+
+/// Shifts each rotated file up by one, dropping the oldest, and moves the current file to `.1`.
+fn rotate(path: &Path, keep: usize) {
+    let _ = std::fs::remove_file(rotated_path(path, keep.max(1)));
+    for n in (1..keep).rev() {
+        let _ = std::fs::rename(rotated_path(path, n), rotated_path(path, n + 1));
+    }
+    if keep == 0 {
+        let _ = std::fs::remove_file(path);
+    } else {
+        let _ = std::fs::rename(path, rotated_path(path, 1));
+    }
+}
+
+/// The records in a log file, oldest first. Lines that can't be read are skipped.
+pub fn read_records(path: &Path) -> Vec<LogRecord> {
+    std::fs::read_to_string(path)
+        .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "logging")]
+fn now() -> String {
+    chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+}
+
+#[cfg(not(feature = "logging"))]
+fn now() -> String {
+    crate::backup::format_utc(std::time::SystemTime::now())
+}
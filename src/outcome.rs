@@ -0,0 +1,103 @@
+//! Typed results of changing a Setup.ini, shared by the configurer, GUI and logs.
+
+use std::fmt;
+use std::io;
+
+use crate::atomic_write::LinkRefused;
+use crate::graphics_profile::KeyChange;
+use crate::profiles::SetupFile;
+
+/// Why a Setup.ini could not be updated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetupIniError {
+    NotFound,
+    /// The file isn't text we can safely edit, e.g. corrupt UTF-16.
+    Parse,
+    PermissionDenied,
+    /// Another process (usually a running Condor) has the file open.
+    LockedByCondor,
+    /// The file or its folder is a symbolic link or junction.
+    ReparsePointRefused,
+    /// The backup taken before the change failed, so the file was left alone.
+    BackupFailed(String),
+    Other(String),
+}
+
+impl SetupIniError {
+    pub fn from_io(e: &io::Error) -> Self {
+        if e.get_ref().is_some_and(|inner| inner.is::<LinkRefused>()) {
+            return Self::ReparsePointRefused;
+        }
+
+        // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+        #[cfg(windows)]
+        if matches!(e.raw_os_error(), Some(32) | Some(33)) {
+            return Self::LockedByCondor;
+        }
+
+        match e.kind() {
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            _ => Self::Other(e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for SetupIniError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "file not found"),
+            Self::Parse => write!(f, "file could not be parsed"),
+            Self::PermissionDenied => write!(f, "permission denied"),
+            Self::LockedByCondor => write!(f, "file is in use, please close Condor and try again"),
+            Self::ReparsePointRefused => write!(f, "refused to write through a symbolic link or junction"),
+            Self::BackupFailed(e) => write!(f, "backup failed, file left unchanged ({})", e),
+            Self::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SetupIniError {}
+
+/// What happened to one Setup.ini.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetupIniOutcome {
+    /// The Condor data directory, pilot (or global) and path.
+    pub file: SetupFile,
+    /// `VROculusRift` before the change, or `None` if it was missing.
+    pub previous: Option<String>,
+    /// The value that was (or would have been) written.
+    pub new: String,
+    /// Graphics profile keys restored alongside `VROculusRift`.
+    pub profile: Vec<KeyChange>,
+    pub error: Option<SetupIniError>,
+}
+
+impl SetupIniOutcome {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl fmt::Display for SetupIniOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let previous = self.previous.as_deref().unwrap_or("(missing)");
+        match &self.error {
+            None => {
+                write!(f, "Updated {}: {} {} -> {}", self.file.label(), self.file.version.vr_key().1, previous, self.new)?;
+                match self.profile.len() {
+                    0 => Ok(()),
+                    1 => write!(f, ", restored 1 graphics setting"),
+                    n => write!(f, ", restored {} graphics settings", n),
+                }
+            }
+            Some(e) => write!(
+                f,
+                "Failed to update {} ({}): {}",
+                self.file.label(),
+                self.file.path.display(),
+                e
+            ),
+        }
+    }
+}
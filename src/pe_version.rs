@@ -0,0 +1,162 @@
+//! Reading the file version from a Windows executable's version resource, in plain Rust so it
+//! works (and can be tested) anywhere.
+//!
+//! The version lives in the `VS_FIXEDFILEINFO` of the `RT_VERSION` resource, which is found by
+//! walking the PE headers to the resource directory.
+
+use std::fmt;
+
+const RT_VERSION: u32 = 16;
+const RESOURCE_DIRECTORY_INDEX: usize = 2;
+const SUBDIRECTORY_FLAG: u32 = 0x8000_0000;
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF_04BD;
+const VERSION_INFO_KEY: &str = "VS_VERSION_INFO";
+
+/// A four-part Windows file version, e.g. `3.2.0.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub build: u16,
+}
+
+impl FileVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16, build: u16) -> Self {
+        Self { major, minor, patch, build }
+    }
+
+    /// Parses `3.2.0`, `v3.2.0` or `3.2.0.1`. Missing parts are zero.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+        let mut parts = [0u16; 4];
+        for (i, part) in text.split('.').enumerate() {
+            *parts.get_mut(i)? = part.trim().parse().ok()?;
+        }
+        Some(Self::new(parts[0], parts[1], parts[2], parts[3]))
+    }
+}
+
+impl fmt::Display for FileVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if self.build != 0 {
+            write!(f, ".{}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+/// The file version of a PE image, or `None` if it isn't a PE file or has no version resource.
+pub fn file_version(image: &[u8]) -> Option<FileVersion> {
+    parse_version_info(version_resource(image)?)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset.checked_add(2)?)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset.checked_add(4)?)?.try_into().ok()?))
+}
+
+struct Section {
+    virtual_address: usize,
+    virtual_size: usize,
+    raw_offset: usize,
+    raw_size: usize,
+}
+
+/// The raw bytes of the first `RT_VERSION` resource.
+fn version_resource(image: &[u8]) -> Option<&[u8]> {
+    if image.get(..2)? != b"MZ" {
+        return None;
+    }
+    let pe = u32_at(image, 0x3C)? as usize;
+    if image.get(pe..pe.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+    let coff = pe + 4;
+    let section_count = u16_at(image, coff + 2)? as usize;
+    let optional_size = u16_at(image, coff + 16)? as usize;
+    let optional = coff + 20;
+    // The data directories follow the fixed part of the optional header, which is longer in PE32+.
+    let directories = match u16_at(image, optional)? {
+        0x10B => optional + 96,
+        0x20B => optional + 112,
+        _ => return None,
+    };
+    let directory_count = u32_at(image, directories - 4)? as usize;
+    if directory_count <= RESOURCE_DIRECTORY_INDEX {
+        return None;
+    }
+    let resource_rva = u32_at(image, directories + RESOURCE_DIRECTORY_INDEX * 8)? as usize;
+    if resource_rva == 0 {
+        return None;
+    }
+
+    let section_table = optional + optional_size;
+    let sections: Vec<Section> = (0..section_count)
+        .map(|i| {
+            let header = section_table + i * 40;
+            Some(Section {
+                virtual_size: u32_at(image, header + 8)? as usize,
+                virtual_address: u32_at(image, header + 12)? as usize,
+                raw_size: u32_at(image, header + 16)? as usize,
+                raw_offset: u32_at(image, header + 20)? as usize,
+            })
+        })
+        .collect::<Option<_>>()?;
+    let file_offset = |rva: usize| {
+        sections.iter().find_map(|s| {
+            let within = rva.checked_sub(s.virtual_address)?;
+            (within < s.virtual_size.max(s.raw_size)).then_some(s.raw_offset + within)
+        })
+    };
+
+    // Offsets inside the resource tree are relative to its root: type, then name, then language.
+    let resources = image.get(file_offset(resource_rva)?..)?;
+    let names = subdirectory(resource_entry(resources, 0, Some(RT_VERSION))?)?;
+    let languages = subdirectory(resource_entry(resources, names, None)?)?;
+    let data_entry = resource_entry(resources, languages, None)?;
+    if data_entry & SUBDIRECTORY_FLAG != 0 {
+        return None;
+    }
+    let data_entry = data_entry as usize;
+    let data_rva = u32_at(resources, data_entry)? as usize;
+    let data_size = u32_at(resources, data_entry + 4)? as usize;
+    let start = file_offset(data_rva)?;
+    image.get(start..start.checked_add(data_size)?)
+}
+
+/// The target of the entry with the given ID in a resource directory, or of its first entry.
+fn resource_entry(resources: &[u8], directory: usize, id: Option<u32>) -> Option<u32> {
+    let named = u16_at(resources, directory + 12)? as usize;
+    let ids = u16_at(resources, directory + 14)? as usize;
+    (0..named + ids).find_map(|i| {
+        let entry = directory + 16 + i * 8;
+        let name = u32_at(resources, entry)?;
+        id.is_none_or(|id| name == id).then(|| u32_at(resources, entry + 4)).flatten()
+    })
+}
+
+fn subdirectory(target: u32) -> Option<usize> {
+    (target & SUBDIRECTORY_FLAG != 0).then_some((target & !SUBDIRECTORY_FLAG) as usize)
+}
+
+/// Reads the file version from a `VS_VERSIONINFO` block: a small header, the UTF-16 key
+/// `VS_VERSION_INFO`, padding to a 4-byte boundary, then `VS_FIXEDFILEINFO`.
+fn parse_version_info(info: &[u8]) -> Option<FileVersion> {
+    let key: Vec<u8> = VERSION_INFO_KEY.encode_utf16().chain(Some(0)).flat_map(u16::to_le_bytes).collect();
+    if info.get(6..6 + key.len())? != key.as_slice() {
+        return None;
+    }
+    let fixed = (6 + key.len() + 3) & !3;
+    if u32_at(info, fixed)? != FIXED_FILE_INFO_SIGNATURE {
+        return None;
+    }
+    let most = u32_at(info, fixed + 8)?;
+    let least = u32_at(info, fixed + 12)?;
+    Some(FileVersion::new((most >> 16) as u16, most as u16, (least >> 16) as u16, least as u16))
+}
@@ -0,0 +1,251 @@
+//! The list of changes `activate` and `deactivate` make, built up front so it can be printed for
+//! review (`--dry-run`) and then executed exactly as shown.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::config::{LEGACY_INJECTOR_VALUE, ServiceMode};
+use crate::graphics_profile::{self, GraphicsMode, KeyChange};
+use crate::hooked_exe::{self, HookedExe};
+use crate::ifeo::{self, Registry};
+use crate::profiles::{CondorProfileSet, SetupFile};
+use crate::settings::UserSettings;
+use crate::{SERVICE_NAME, SETTINGS_PATH, ini_edit};
+
+/// The service DACL: Local System and Administrators get full control, Authenticated Users may
+/// start the service and query its status (so the launcher can trigger the bypass).
+pub const SERVICE_SDDL: &str = "D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;RPLC;;;AU)";
+
+/// The name of the IFEO value that routes a program through our launcher.
+pub const DEBUGGER_VALUE: &str = "Debugger";
+
+/// A change to a value under `HKEY_LOCAL_MACHINE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryChange {
+    Set { key: String, name: String, value: String },
+    SetDword { key: String, name: String, value: u32 },
+    Delete { key: String, name: String },
+    /// Deletes a key and everything under it.
+    DeleteKey { key: String },
+}
+
+impl fmt::Display for RegistryChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Set { key, name, value } => write!(f, r"Set HKLM\{}\{} = {}", key, name, value),
+            Self::SetDword { key, name, value } => write!(f, r"Set HKLM\{}\{} = {} (DWORD)", key, name, value),
+            Self::Delete { key, name } => write!(f, r"Delete HKLM\{}\{}", key, name),
+            Self::DeleteKey { key } => write!(f, r"Delete key HKLM\{}", key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceChange {
+    Install { path: String, mode: ServiceMode },
+    /// Points the installed service at `path` and sets how it starts.
+    Update { path: String, mode: ServiceMode },
+    Uninstall,
+}
+
+impl ServiceChange {
+    /// Installs the service, or updates it if it is already installed.
+    pub fn install_or_update(path: &Path, exists: bool, mode: ServiceMode) -> Self {
+        let path = path.to_string_lossy().into_owned();
+        if exists { Self::Update { path, mode } } else { Self::Install { path, mode } }
+    }
+}
+
+impl fmt::Display for ServiceChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Install { path, mode } => write!(f, "Install service {} ({}) at \"{}\"", SERVICE_NAME, mode, path),
+            Self::Update { path, mode } => write!(f, "Update service {} to run \"{}\" ({})", SERVICE_NAME, path, mode),
+            Self::Uninstall => write!(f, "Stop and uninstall service {}", SERVICE_NAME),
+        }
+    }
+}
+
+/// The planned `VROculusRift` value of one Setup.ini, and the graphics profile swap that goes with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetupIniChange {
+    pub file: SetupFile,
+    /// The current value, or `None` if the file has no `VROculusRift` key.
+    pub before: Option<String>,
+    pub after: String,
+    /// Graphics profile keys restored from the saved snapshot of the mode being switched to.
+    pub profile: Vec<KeyChange>,
+    /// The profile values of the mode being left, saved to the user settings once the file is written.
+    pub snapshot: Option<(GraphicsMode, BTreeMap<String, String>)>,
+}
+
+impl fmt::Display for SetupIniChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let before = self.before.as_deref().unwrap_or("(missing)");
+        let unchanged = if self.before.as_deref() == Some(self.after.as_str()) { " (unchanged)" } else { "" };
+        write!(
+            f,
+            "{}: {} {} -> {}{}\n    {}",
+            self.file.label(),
+            self.file.version.vr_key().1,
+            before,
+            self.after,
+            unchanged,
+            self.file.path.display()
+        )?;
+        for change in &self.profile {
+            write!(f, "\n    {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything `activate` or `deactivate` will change, in the order it is applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivationPlan {
+    pub service: ServiceChange,
+    /// The service security descriptor to apply, if any.
+    pub service_sddl: Option<String>,
+    pub registry: Vec<RegistryChange>,
+    pub setup_ini: Vec<SetupIniChange>,
+}
+
+impl ActivationPlan {
+    /// The plan for routing Condor through the launcher. The hooks are laid out relative to what
+    /// `current` holds now.
+    pub fn activate(
+        current: &impl Registry,
+        launcher_path: &Path,
+        service: ServiceChange,
+        exes: &[HookedExe],
+        setup_ini: Vec<SetupIniChange>,
+    ) -> Self {
+        let mut registry = hook_changes(current, launcher_path, exes);
+        // The injector path has been moved into config.toml by now, so drop the old copy.
+        if current.string(SETTINGS_PATH, LEGACY_INJECTOR_VALUE).is_some() {
+            registry.push(RegistryChange::Delete {
+                key: SETTINGS_PATH.to_string(),
+                name: LEGACY_INJECTOR_VALUE.to_string(),
+            });
+        }
+
+        Self {
+            service,
+            service_sddl: Some(SERVICE_SDDL.to_string()),
+            registry,
+            setup_ini,
+        }
+    }
+
+    /// The plan for removing the hooks and service.
+    pub fn deactivate(current: &impl Registry, exes: &[HookedExe], setup_ini: Vec<SetupIniChange>) -> Self {
+        Self {
+            service: ServiceChange::Uninstall,
+            service_sddl: None,
+            registry: exes.iter().flat_map(|exe| ifeo::unhook_changes(current, &exe.name)).collect(),
+            setup_ini,
+        }
+    }
+
+    fn fmt_service(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Service:")?;
+        writeln!(f, "  {}", self.service)?;
+        if let Some(sddl) = &self.service_sddl {
+            writeln!(f, "  Set service security descriptor: {}", sddl)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_registry(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Registry:")?;
+        for change in &self.registry {
+            writeln!(f, "  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ActivationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Deactivation removes the hook before the service, activation the other way round.
+        if self.service == ServiceChange::Uninstall {
+            self.fmt_registry(f)?;
+            self.fmt_service(f)?;
+        } else {
+            self.fmt_service(f)?;
+            self.fmt_registry(f)?;
+        }
+
+        writeln!(f, "Setup.ini files:")?;
+        if self.setup_ini.is_empty() {
+            writeln!(f, "  (none found)")?;
+        }
+        for change in &self.setup_ini {
+            writeln!(f, "  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hooks every enabled executable and unhooks every disabled one (see [`ifeo`] for the layouts).
+pub fn hook_changes(current: &impl Registry, launcher_path: &Path, exes: &[HookedExe]) -> Vec<RegistryChange> {
+    exes.iter().flat_map(|exe| ifeo::hook_changes(current, launcher_path, exe)).collect()
+}
+
+/// Unhooks the executables that were dropped from the list.
+pub fn removal_changes(current: &impl Registry, old: &[HookedExe], new: &[HookedExe]) -> Vec<RegistryChange> {
+    old.iter()
+        .filter(|o| hooked_exe::find(new, &o.name).is_none())
+        .flat_map(|o| ifeo::unhook_changes(current, &o.name))
+        .collect()
+}
+
+/// Works out the new `VROculusRift` value and graphics profile of every Setup.ini, without changing anything.
+pub fn plan_setup_ini(
+    profiles: &CondorProfileSet,
+    settings: &UserSettings,
+    vr_enabled: bool,
+) -> Vec<SetupIniChange> {
+    profiles
+        .setup_files()
+        .into_iter()
+        .map(|file| {
+            let selected = vr_enabled && settings.vr_selected(&file.path);
+            plan_setup_file(file, settings, selected)
+        })
+        .collect()
+}
+
+/// Plans setting one Setup.ini to VR or flatscreen. The graphics profile is only swapped when the
+/// mode actually changes, so re-running a plan never overwrites the user's tweaks with a stale snapshot.
+pub fn plan_setup_file(file: SetupFile, settings: &UserSettings, vr_enabled: bool) -> SetupIniChange {
+    let target = if vr_enabled { GraphicsMode::Vr } else { GraphicsMode::Flatscreen };
+    let contents = std::fs::read(&file.path).ok();
+    let (vr_section, vr_key) = file.version.vr_key();
+    let before = contents
+        .as_deref()
+        .and_then(|contents| ini_edit::get_value(contents, vr_section, vr_key));
+    let current = GraphicsMode::from_vr_value(before.as_deref());
+
+    let mut profile = Vec::new();
+    let mut snapshot = None;
+    let keys = settings.profile_keys();
+    if let Some(contents) = &contents
+        && current != target
+        && !keys.is_empty()
+    {
+        snapshot = Some((current, graphics_profile::capture(contents, keys)));
+        if let Some(saved) = settings.snapshot(&file.path, target) {
+            profile = graphics_profile::restore(contents, keys, saved);
+        }
+    }
+
+    SetupIniChange {
+        file,
+        before,
+        after: target.vr_value().to_string(),
+        profile,
+        snapshot,
+    }
+}
@@ -0,0 +1,217 @@
+//! Following the program ReviveInjector starts, so the launcher can tell when it didn't get far.
+//!
+//! The injector starts the target as its own child and exits straight away, so the target is found
+//! among the running processes by its parent's PID.
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A program that exits this soon after starting almost certainly couldn't open VR.
+pub const EARLY_EXIT: Duration = Duration::from_secs(5);
+
+/// Seconds from 1601-01-01, where Windows file times start, to the Unix epoch.
+const FILETIME_EPOCH_OFFSET_SECS: u64 = 11_644_473_600;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub parent_pid: u32,
+    pub exe_name: String,
+}
+
+/// The PID of the `exe_name` process the injector started: its child of that name, or failing that
+/// the only process of that name.
+pub fn find_started_process(processes: &[ProcessEntry], injector_pid: u32, exe_name: &str) -> Option<u32> {
+    let mut named = processes.iter().filter(|p| p.exe_name.eq_ignore_ascii_case(exe_name));
+    if let Some(child) = named.clone().find(|p| p.parent_pid == injector_pid) {
+        return Some(child.pid);
+    }
+    match (named.next(), named.next()) {
+        (Some(only), None) => Some(only.pid),
+        _ => None,
+    }
+}
+
+/// Converts a Windows `FILETIME`, in 100ns ticks since 1601, to a `SystemTime`.
+pub fn from_filetime(ticks: u64) -> SystemTime {
+    let since_1601 = Duration::from_nanos(ticks.saturating_mul(100));
+    let offset = Duration::from_secs(FILETIME_EPOCH_OFFSET_SECS);
+    match since_1601.checked_sub(offset) {
+        Some(since_epoch) => UNIX_EPOCH + since_epoch,
+        None => UNIX_EPOCH - (offset - since_1601),
+    }
+}
+
+/// One run of a watched program, from start to exit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessRun {
+    pub pid: u32,
+    pub exe_name: String,
+    pub started: SystemTime,
+    pub exited: SystemTime,
+    pub exit_code: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessEnding {
+    /// Exited within [`EARLY_EXIT`] of starting, whatever the exit code.
+    Immediate,
+    /// Ended by an unhandled exception, whose `NTSTATUS` is the exit code.
+    Crashed,
+    Exited,
+}
+
+impl ProcessRun {
+    pub fn lifetime(&self) -> Duration {
+        self.exited.duration_since(self.started).unwrap_or_default()
+    }
+
+    pub fn ending(&self) -> ProcessEnding {
+        if self.lifetime() < EARLY_EXIT {
+            ProcessEnding::Immediate
+        } else if self.exit_code >= 0xC000_0000 {
+            ProcessEnding::Crashed
+        } else {
+            ProcessEnding::Exited
+        }
+    }
+
+    /// What to tell the pilot if the run went wrong, or `None` if it ended normally.
+    pub fn problem(&self) -> Option<String> {
+        match self.ending() {
+            ProcessEnding::Immediate => Some(format!(
+                "{} exited immediately (exit code {}). Is SteamVR running and your headset connected?",
+                self.exe_name,
+                exit_code_text(self.exit_code)
+            )),
+            ProcessEnding::Crashed => {
+                Some(format!("{} crashed (exit code {}).", self.exe_name, exit_code_text(self.exit_code)))
+            }
+            ProcessEnding::Exited => None,
+        }
+    }
+}
+
+impl fmt::Display for ProcessRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.lifetime().as_secs();
+        write!(
+            f,
+            "{} (PID {}) ran for {}:{:02}:{:02} and exited with code {}",
+            self.exe_name,
+            self.pid,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            exit_code_text(self.exit_code)
+        )
+    }
+}
+
+/// Small exit codes in decimal, `NTSTATUS` values in hex as Windows documents them.
+pub fn exit_code_text(code: u32) -> String {
+    if code >= 0x1_0000 { format!("0x{:08X}", code) } else { code.to_string() }
+}
+
+#[cfg(windows)]
+pub use os::{WatchedProcess, list_processes};
+
+#[cfg(windows)]
+mod os {
+    use std::io;
+    use std::time::{Duration, SystemTime};
+
+    use windows::Win32::Foundation::{CloseHandle, FILETIME, HANDLE, WAIT_TIMEOUT};
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW, TH32CS_SNAPPROCESS,
+    };
+    use windows::Win32::System::Threading::{
+        GetExitCodeProcess, GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE,
+        WaitForSingleObject,
+    };
+
+    use super::{ProcessEntry, ProcessRun, from_filetime};
+
+    pub fn list_processes() -> Vec<ProcessEntry> {
+        let mut processes = Vec::new();
+        unsafe {
+            let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+                return processes;
+            };
+            let mut entry = PROCESSENTRY32W { dwSize: size_of::<PROCESSENTRY32W>() as u32, ..Default::default() };
+            let mut more = Process32FirstW(snapshot, &mut entry).is_ok();
+            while more {
+                let end = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+                processes.push(ProcessEntry {
+                    pid: entry.th32ProcessID,
+                    parent_pid: entry.th32ParentProcessID,
+                    exe_name: String::from_utf16_lossy(&entry.szExeFile[..end]),
+                });
+                more = Process32NextW(snapshot, &mut entry).is_ok();
+            }
+            let _ = CloseHandle(snapshot);
+        }
+        processes
+    }
+
+    /// A process held open, so its exit code and times stay readable after it exits.
+    pub struct WatchedProcess {
+        handle: HANDLE,
+        pid: u32,
+        exe_name: String,
+        started: SystemTime,
+    }
+
+    impl WatchedProcess {
+        pub fn open(pid: u32, exe_name: &str) -> io::Result<Self> {
+            let handle = unsafe { OpenProcess(PROCESS_SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION, false, pid)? };
+            let started = process_times(handle).map_or_else(SystemTime::now, |(created, _)| created);
+            Ok(Self { handle, pid, exe_name: exe_name.to_string(), started })
+        }
+
+        pub fn pid(&self) -> u32 {
+            self.pid
+        }
+
+        pub fn started(&self) -> SystemTime {
+            self.started
+        }
+
+        /// Waits up to `timeout` for the process to exit, returning how it ran if it has.
+        pub fn wait_timeout(&self, timeout: Duration) -> Option<ProcessRun> {
+            // Anything too long for the API's milliseconds becomes INFINITE.
+            let millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+            if unsafe { WaitForSingleObject(self.handle, millis) } == WAIT_TIMEOUT {
+                return None;
+            }
+            let mut exit_code = 0;
+            let _ = unsafe { GetExitCodeProcess(self.handle, &mut exit_code) };
+            Some(ProcessRun {
+                pid: self.pid,
+                exe_name: self.exe_name.clone(),
+                started: self.started,
+                exited: process_times(self.handle).map_or_else(SystemTime::now, |(_, exited)| exited),
+                exit_code,
+            })
+        }
+
+        pub fn wait(&self) -> ProcessRun {
+            self.wait_timeout(Duration::MAX).expect("an INFINITE wait only returns once the process exits")
+        }
+    }
+
+    impl Drop for WatchedProcess {
+        fn drop(&mut self) {
+            let _ = unsafe { CloseHandle(self.handle) };
+        }
+    }
+
+    /// The creation and exit times of a process.
+    fn process_times(handle: HANDLE) -> Option<(SystemTime, SystemTime)> {
+        let (mut created, mut exited, mut kernel, mut user) =
+            (FILETIME::default(), FILETIME::default(), FILETIME::default(), FILETIME::default());
+        unsafe { GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user).ok()? };
+        let ticks = |time: FILETIME| (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+        Some((from_filetime(ticks(created)), from_filetime(ticks(exited))))
+    }
+}
@@ -0,0 +1,186 @@
+//! Discovery of Condor data directories (e.g. `Documents/Condor3`) and the pilots within them.
+
+use std::path::{Path, PathBuf};
+
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::condor_version::CondorVersion;
+use crate::settings::UserSettings;
+
+pub const SETUP_INI: &str = "Setup.ini";
+pub const PILOTS_DIR: &str = "Pilots";
+
+/// A pilot folder under `<data dir>/Pilots` that contains a Setup.ini.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pilot {
+    pub name: String,
+    pub setup_ini: PathBuf,
+}
+
+/// A Condor data directory, with its global settings and pilots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CondorDataDir {
+    pub name: String,
+    pub path: PathBuf,
+    pub version: CondorVersion,
+    /// The global `Setup.ini`, if present.
+    pub global: Option<PathBuf>,
+    pub pilots: Vec<Pilot>,
+}
+
+/// A single Setup.ini belonging to a data directory, either global or for one pilot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupFile {
+    pub data_dir: String,
+    /// The Condor version the data directory belongs to.
+    pub version: CondorVersion,
+    /// `None` for the data directory's global Setup.ini.
+    pub pilot: Option<String>,
+    pub path: PathBuf,
+}
+
+impl SetupFile {
+    /// A human-readable name such as "Pilot: John (Condor3)".
+    pub fn label(&self) -> String {
+        match &self.pilot {
+            Some(pilot) => format!("Pilot: {} ({})", pilot, self.data_dir),
+            None => format!("Global Settings ({})", self.data_dir),
+        }
+    }
+}
+
+/// All Condor data directories found under one or more Documents roots, plus any added explicitly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CondorProfileSet {
+    pub data_dirs: Vec<CondorDataDir>,
+}
+
+impl CondorProfileSet {
+    /// Scans `documents` for directories whose name contains "Condor".
+    pub fn discover(documents: &Path) -> Self {
+        let mut data_dirs = Vec::new();
+
+        if let Ok(entries) = std::fs::read_dir(documents) {
+            for entry in entries.flatten() {
+                if !entry.path().is_dir() {
+                    continue;
+                }
+
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if !name.contains("Condor") {
+                    continue;
+                }
+
+                data_dirs.push(CondorDataDir::load(name, entry.path()));
+            }
+        }
+
+        data_dirs.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { data_dirs }
+    }
+
+    /// Scans the current user's Documents folder.
+    pub fn discover_user_documents() -> Self {
+        match default_documents_dir() {
+            Some(docs) => Self::discover(&docs),
+            None => Self::default(),
+        }
+    }
+
+    /// Scans each root for `Condor*` folders and adds the explicit data directories, skipping any
+    /// directory found twice.
+    pub fn discover_all(roots: &[PathBuf], explicit_dirs: &[PathBuf]) -> Self {
+        let mut set = Self::default();
+        let found = roots.iter().flat_map(|root| Self::discover(root).data_dirs);
+        let explicit = explicit_dirs.iter().filter(|path| path.is_dir()).map(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.to_string_lossy().into_owned());
+            CondorDataDir::load(name, path.clone())
+        });
+        for dir in found.chain(explicit) {
+            if !set.data_dirs.iter().any(|d| d.path == dir.path) {
+                set.data_dirs.push(dir);
+            }
+        }
+
+        set.data_dirs.sort_by(|a, b| a.name.cmp(&b.name));
+        set
+    }
+
+    /// Scans a user's Documents folder and the extra folders in their settings.
+    pub fn discover_configured(documents: Option<&Path>, settings: &UserSettings) -> Self {
+        let roots: Vec<PathBuf> = documents
+            .map(Path::to_path_buf)
+            .into_iter()
+            .chain(settings.search_roots().iter().cloned())
+            .collect();
+        Self::discover_all(&roots, settings.data_dirs())
+    }
+
+    /// Every Setup.ini in the set, global settings first within each data directory.
+    pub fn setup_files(&self) -> Vec<SetupFile> {
+        self.data_dirs.iter().flat_map(|d| d.setup_files()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data_dirs.iter().all(|d| d.global.is_none() && d.pilots.is_empty())
+    }
+}
+
+impl CondorDataDir {
+    /// Reads the global Setup.ini and `Pilots/*/Setup.ini` of a data directory.
+    pub fn load(name: String, path: PathBuf) -> Self {
+        let global_setup = path.join(SETUP_INI);
+        let global = global_setup.is_file().then_some(global_setup);
+
+        let mut pilots = Vec::new();
+        if let Ok(p_entries) = std::fs::read_dir(path.join(PILOTS_DIR)) {
+            for p_entry in p_entries.flatten() {
+                if !p_entry.path().is_dir() {
+                    continue;
+                }
+                let setup_ini = p_entry.path().join(SETUP_INI);
+                if setup_ini.is_file() {
+                    pilots.push(Pilot {
+                        name: p_entry.file_name().to_string_lossy().into_owned(),
+                        setup_ini,
+                    });
+                }
+            }
+        }
+        pilots.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let setup_inis: Vec<PathBuf> = global
+            .iter()
+            .cloned()
+            .chain(pilots.iter().map(|p| p.setup_ini.clone()))
+            .collect();
+        let version = CondorVersion::detect(&name, &setup_inis);
+
+        Self { name, path, version, global, pilots }
+    }
+
+    pub fn setup_files(&self) -> Vec<SetupFile> {
+        let global = self.global.iter().map(|path| SetupFile {
+            data_dir: self.name.clone(),
+            version: self.version,
+            pilot: None,
+            path: path.clone(),
+        });
+        let pilots = self.pilots.iter().map(|p| SetupFile {
+            data_dir: self.name.clone(),
+            version: self.version,
+            pilot: Some(p.name.clone()),
+            path: p.setup_ini.clone(),
+        });
+        global.chain(pilots).collect()
+    }
+}
+
+/// The current user's Documents folder, as reported by the shell.
+pub fn default_documents_dir() -> Option<PathBuf> {
+    UserDirs::new()?.document_dir().map(Path::to_path_buf)
+}
@@ -0,0 +1,73 @@
+//! Which Revive release an injector belongs to, and whether the helper works with it.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::pe_version::{self, FileVersion};
+
+/// The oldest Revive release the helper works with.
+pub const MIN_SUPPORTED: FileVersion = FileVersion::new(3, 2, 0, 0);
+/// The first Revive release that hasn't been tried yet.
+pub const UNTESTED_FROM: FileVersion = FileVersion::new(4, 0, 0, 0);
+/// A text file next to the injector holding its version, for builds without a version resource.
+pub const VERSION_SIDECAR: &str = "version.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Supported(FileVersion),
+    /// Older than [`MIN_SUPPORTED`]; launching would fail in VR.
+    TooOld(FileVersion),
+    /// [`UNTESTED_FROM`] or newer; it may work, but nobody has checked.
+    Untested(FileVersion),
+    /// The version couldn't be read.
+    Unknown,
+}
+
+impl Compatibility {
+    pub fn check(version: Option<FileVersion>) -> Self {
+        match version {
+            Some(v) if v < MIN_SUPPORTED => Self::TooOld(v),
+            Some(v) if v >= UNTESTED_FROM => Self::Untested(v),
+            Some(v) => Self::Supported(v),
+            None => Self::Unknown,
+        }
+    }
+
+    /// Reads the version of the injector at `path` and checks it.
+    pub fn of_injector(path: &Path) -> Self {
+        Self::check(injector_version(path))
+    }
+
+    /// Whether the launcher should refuse to start Condor with this injector.
+    pub fn blocks_launch(&self) -> bool {
+        matches!(self, Self::TooOld(_))
+    }
+
+    pub fn is_supported(&self) -> bool {
+        matches!(self, Self::Supported(_))
+    }
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Supported(v) => write!(f, "Revive {}", v),
+            Self::TooOld(v) => write!(f, "Revive {} is too old; please update to Revive {} or later", v, MIN_SUPPORTED),
+            Self::Untested(v) => write!(f, "Revive {} is newer than this helper has been tested with", v),
+            Self::Unknown => write!(f, "the Revive version could not be read; Revive {} or later is needed", MIN_SUPPORTED),
+        }
+    }
+}
+
+/// The version of the ReviveInjector at `path`, from its version resource or else from a
+/// [`VERSION_SIDECAR`] file next to it or in the folder above (Revive keeps the x64 injector in a subfolder).
+pub fn injector_version(path: &Path) -> Option<FileVersion> {
+    if let Some(version) = std::fs::read(path).ok().and_then(|image| pe_version::file_version(&image)) {
+        return Some(version);
+    }
+    path.ancestors()
+        .skip(1)
+        .take(2)
+        .find_map(|dir| std::fs::read_to_string(dir.join(VERSION_SIDECAR)).ok())
+        .and_then(|text| FileVersion::parse(text.lines().next()?))
+}
@@ -0,0 +1,330 @@
+//! Per-user helper settings, stored as an INI file in `%APPDATA%\CondorVR`.
+//!
+//! Each Setup.ini the helper manages gets its own section, named by the Setup.ini path, holding
+//! whether it follows the VR toggle and its saved VR and flatscreen graphics snapshots.
+//! Extra places to look for Condor data live in the `[Folders]` section, and launcher options in
+//! `[Launcher]`, with the last launch mode chosen for each executable in `[Last Launch Mode]`.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+use ini::{EscapePolicy, Ini};
+
+use crate::atomic_write::write_atomic;
+use crate::graphics_profile::GraphicsMode;
+
+pub const SETTINGS_DIR: &str = "CondorVR";
+pub const SETTINGS_FILE: &str = "settings.ini";
+
+const VR_KEY: &str = "VR";
+const PROFILE_SECTION: &str = "Graphics Profile";
+const PROFILE_KEYS: &str = "Keys";
+const FOLDERS_SECTION: &str = "Folders";
+const SEARCH_ROOT_KEY: &str = "SearchRoot";
+const DATA_DIR_KEY: &str = "DataDir";
+const LAUNCHER_SECTION: &str = "Launcher";
+const CHOICE_TIMEOUT_KEY: &str = "ChoiceTimeout";
+const DEFAULT_MODE_KEY: &str = "DefaultMode";
+const REMEMBER_CHOICE_KEY: &str = "RememberChoice";
+const FLATSCREEN_DISABLES_VR_KEY: &str = "FlatscreenDisablesVR";
+const LAST_LAUNCH_SECTION: &str = "Last Launch Mode";
+
+/// How long the launcher asks "VR or flatscreen?" before starting the default, by default.
+pub const DEFAULT_CHOICE_TIMEOUT: u32 = 3;
+
+/// What is remembered about a single Setup.ini.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct FileSettings {
+    vr_selected: Option<bool>,
+    /// `[Graphics]` values saved for each mode, restored when switching back to it.
+    snapshots: BTreeMap<GraphicsMode, BTreeMap<String, String>>,
+}
+
+/// Settings remembered for the current user between runs of the helper.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserSettings {
+    files: BTreeMap<PathBuf, FileSettings>,
+    /// The `[Graphics]` keys swapped along with `VROculusRift`.
+    profile_keys: Vec<String>,
+    /// Folders scanned for `Condor*` data directories in addition to Documents.
+    search_roots: Vec<PathBuf>,
+    /// Condor data directories used whatever their name or location.
+    data_dirs: Vec<PathBuf>,
+    /// Seconds the launcher waits for a VR or flatscreen choice, if not the default.
+    choice_timeout: Option<u32>,
+    /// The mode launched when nobody chooses, if not VR.
+    default_launch_mode: Option<GraphicsMode>,
+    remember_launch_mode: bool,
+    /// The last mode chosen for each hooked executable, by lower-case name.
+    last_launch_modes: BTreeMap<String, GraphicsMode>,
+    /// Whether a one-off flatscreen launch also turns VR off in Setup.ini, if not the default.
+    flatscreen_disables_vr: Option<bool>,
+}
+
+impl UserSettings {
+    /// The default settings location, `%APPDATA%\CondorVR\settings.ini`.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(BaseDirs::new()?.config_dir().join(SETTINGS_DIR).join(SETTINGS_FILE))
+    }
+
+    /// Loads settings from `path`, returning defaults if the file is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        let mut settings = Self::default();
+        let Ok(conf) = Ini::load_from_file_noescape(path) else {
+            return settings;
+        };
+
+        for (section, props) in conf.iter() {
+            let Some(section) = section else { continue };
+            if section == PROFILE_SECTION {
+                settings.profile_keys = props
+                    .get(PROFILE_KEYS)
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|k| !k.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                continue;
+            }
+            if section == FOLDERS_SECTION {
+                settings.search_roots = props.get_all(SEARCH_ROOT_KEY).map(PathBuf::from).collect();
+                settings.data_dirs = props.get_all(DATA_DIR_KEY).map(PathBuf::from).collect();
+                continue;
+            }
+            if section == LAUNCHER_SECTION {
+                settings.choice_timeout = props.get(CHOICE_TIMEOUT_KEY).and_then(|v| v.trim().parse().ok());
+                settings.default_launch_mode = props.get(DEFAULT_MODE_KEY).and_then(|v| GraphicsMode::from_prefix(v.trim()));
+                settings.remember_launch_mode = props.get(REMEMBER_CHOICE_KEY).is_some_and(|v| v.trim() == "1");
+                settings.flatscreen_disables_vr = props.get(FLATSCREEN_DISABLES_VR_KEY).map(|v| v.trim() == "1");
+                continue;
+            }
+            if section == LAST_LAUNCH_SECTION {
+                settings.last_launch_modes = props
+                    .iter()
+                    .filter_map(|(exe, mode)| Some((exe.to_ascii_lowercase(), GraphicsMode::from_prefix(mode.trim())?)))
+                    .collect();
+                continue;
+            }
+
+            let file = settings.files.entry(PathBuf::from(section)).or_default();
+            for (key, val) in props.iter() {
+                if key == VR_KEY {
+                    file.vr_selected = Some(val.trim() == "1");
+                } else if let Some((prefix, graphics_key)) = key.split_once('.')
+                    && let Some(mode) = GraphicsMode::from_prefix(prefix)
+                {
+                    file.snapshots
+                        .entry(mode)
+                        .or_default()
+                        .insert(graphics_key.to_string(), val.to_string());
+                }
+            }
+        }
+        settings
+    }
+
+    /// Loads settings from the default location.
+    pub fn load_default() -> Self {
+        Self::default_path().map(|p| Self::load(&p)).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut conf = Ini::new();
+        if !self.profile_keys.is_empty() {
+            conf.with_section(Some(PROFILE_SECTION))
+                .set(PROFILE_KEYS, self.profile_keys.join(","));
+        }
+        if !self.search_roots.is_empty() || !self.data_dirs.is_empty() {
+            let mut section = conf.with_section(Some(FOLDERS_SECTION));
+            for root in &self.search_roots {
+                section.add(SEARCH_ROOT_KEY, root.to_string_lossy());
+            }
+            for dir in &self.data_dirs {
+                section.add(DATA_DIR_KEY, dir.to_string_lossy());
+            }
+        }
+        if self.choice_timeout.is_some()
+            || self.default_launch_mode.is_some()
+            || self.remember_launch_mode
+            || self.flatscreen_disables_vr.is_some()
+        {
+            let mut section = conf.with_section(Some(LAUNCHER_SECTION));
+            if let Some(seconds) = self.choice_timeout {
+                section.set(CHOICE_TIMEOUT_KEY, seconds.to_string());
+            }
+            if let Some(mode) = self.default_launch_mode {
+                section.set(DEFAULT_MODE_KEY, mode.prefix());
+            }
+            if self.remember_launch_mode {
+                section.set(REMEMBER_CHOICE_KEY, "1");
+            }
+            if let Some(disables_vr) = self.flatscreen_disables_vr {
+                section.set(FLATSCREEN_DISABLES_VR_KEY, if disables_vr { "1" } else { "0" });
+            }
+        }
+        if !self.last_launch_modes.is_empty() {
+            let mut section = conf.with_section(Some(LAST_LAUNCH_SECTION));
+            for (exe, mode) in &self.last_launch_modes {
+                section.set(exe.as_str(), mode.prefix());
+            }
+        }
+        for (setup_ini, file) in &self.files {
+            let mut section = conf.with_section(Some(setup_ini.to_string_lossy()));
+            if let Some(selected) = file.vr_selected {
+                section.set(VR_KEY, if selected { "1" } else { "0" });
+            }
+            for (mode, snapshot) in &file.snapshots {
+                for (key, val) in snapshot {
+                    section.set(format!("{}.{}", mode.prefix(), key), val.as_str());
+                }
+            }
+        }
+
+        let mut contents = Vec::new();
+        conf.write_to_policy(&mut contents, EscapePolicy::Nothing)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(path, &contents)
+    }
+
+    /// Saves settings to the default location.
+    pub fn save_default(&self) -> io::Result<()> {
+        let path = Self::default_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No settings directory"))?;
+        self.save(&path)
+    }
+
+    /// Whether VR should be turned on for this Setup.ini when VR is enabled.
+    pub fn vr_selected(&self, setup_ini: &Path) -> bool {
+        self.files
+            .get(setup_ini)
+            .and_then(|f| f.vr_selected)
+            .unwrap_or(true)
+    }
+
+    pub fn set_vr_selected(&mut self, setup_ini: &Path, selected: bool) {
+        self.files.entry(setup_ini.to_path_buf()).or_default().vr_selected = Some(selected);
+    }
+
+    /// The `[Graphics]` keys that make up a VR or flatscreen profile.
+    pub fn profile_keys(&self) -> &[String] {
+        &self.profile_keys
+    }
+
+    pub fn set_profile_keys(&mut self, keys: Vec<String>) {
+        self.profile_keys = keys;
+    }
+
+    /// The saved `[Graphics]` values of a Setup.ini for the given mode, if any.
+    pub fn snapshot(&self, setup_ini: &Path, mode: GraphicsMode) -> Option<&BTreeMap<String, String>> {
+        self.files.get(setup_ini)?.snapshots.get(&mode)
+    }
+
+    /// Extra folders to scan for `Condor*` data directories.
+    pub fn search_roots(&self) -> &[PathBuf] {
+        &self.search_roots
+    }
+
+    /// Data directories added explicitly, e.g. on another drive.
+    pub fn data_dirs(&self) -> &[PathBuf] {
+        &self.data_dirs
+    }
+
+    /// Returns false if the folder was already in the list.
+    pub fn add_search_root(&mut self, path: &Path) -> bool {
+        add_unique(&mut self.search_roots, path)
+    }
+
+    /// Returns false if the folder wasn't in the list.
+    pub fn remove_search_root(&mut self, path: &Path) -> bool {
+        remove_path(&mut self.search_roots, path)
+    }
+
+    /// Returns false if the folder was already in the list.
+    pub fn add_data_dir(&mut self, path: &Path) -> bool {
+        add_unique(&mut self.data_dirs, path)
+    }
+
+    /// Returns false if the folder wasn't in the list.
+    pub fn remove_data_dir(&mut self, path: &Path) -> bool {
+        remove_path(&mut self.data_dirs, path)
+    }
+
+    /// Seconds the launcher asks "VR or flatscreen?" before starting the default; 0 starts it
+    /// straight away, though holding Shift still launches flatscreen.
+    pub fn launch_choice_timeout(&self) -> u32 {
+        self.choice_timeout.unwrap_or(DEFAULT_CHOICE_TIMEOUT)
+    }
+
+    pub fn set_launch_choice_timeout(&mut self, seconds: u32) {
+        self.choice_timeout = Some(seconds);
+    }
+
+    /// The mode the launcher picks when nobody chooses.
+    pub fn default_launch_mode(&self) -> GraphicsMode {
+        self.default_launch_mode.unwrap_or(GraphicsMode::Vr)
+    }
+
+    pub fn set_default_launch_mode(&mut self, mode: GraphicsMode) {
+        self.default_launch_mode = Some(mode);
+    }
+
+    /// Whether the last choice for each executable replaces the default next time.
+    pub fn remember_launch_mode(&self) -> bool {
+        self.remember_launch_mode
+    }
+
+    pub fn set_remember_launch_mode(&mut self, remember: bool) {
+        self.remember_launch_mode = remember;
+    }
+
+    /// The mode last launched for a hooked executable. The pilot isn't known until Condor has
+    /// started, so choices are remembered per executable.
+    pub fn last_launch_mode(&self, exe_name: &str) -> Option<GraphicsMode> {
+        self.last_launch_modes.get(&exe_name.to_ascii_lowercase()).copied()
+    }
+
+    pub fn set_last_launch_mode(&mut self, exe_name: &str, mode: GraphicsMode) {
+        self.last_launch_modes.insert(exe_name.to_ascii_lowercase(), mode);
+    }
+
+    /// Whether a one-off flatscreen launch switches Setup.ini to flatscreen until Condor exits.
+    pub fn flatscreen_disables_vr(&self) -> bool {
+        self.flatscreen_disables_vr.unwrap_or(true)
+    }
+
+    pub fn set_flatscreen_disables_vr(&mut self, disables_vr: bool) {
+        self.flatscreen_disables_vr = Some(disables_vr);
+    }
+
+    pub fn set_snapshot(&mut self, setup_ini: &Path, mode: GraphicsMode, values: BTreeMap<String, String>) {
+        self.files
+            .entry(setup_ini.to_path_buf())
+            .or_default()
+            .snapshots
+            .insert(mode, values);
+    }
+}
+
+/// Windows paths are case-insensitive, so compare them that way.
+fn same_path(a: &Path, b: &Path) -> bool {
+    a == b || a.to_string_lossy().eq_ignore_ascii_case(&b.to_string_lossy())
+}
+
+fn add_unique(paths: &mut Vec<PathBuf>, path: &Path) -> bool {
+    if paths.iter().any(|p| same_path(p, path)) {
+        return false;
+    }
+    paths.push(path.to_path_buf());
+    true
+}
+
+fn remove_path(paths: &mut Vec<PathBuf>, path: &Path) -> bool {
+    let len = paths.len();
+    paths.retain(|p| !same_path(p, path));
+    paths.len() != len
+}
@@ -0,0 +1,81 @@
+use std::fs;
+
+use condor3_revive_helper::atomic_write;
+use condor3_revive_helper::write_atomic;
+
+#[test]
+fn write_atomic_replaces_the_file_and_leaves_no_temp_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Setup.ini");
+    fs::write(&path, "[VR]\nEnabled=0\nA much longer line than the new contents\n").unwrap();
+
+    write_atomic(&path, b"[VR]\nEnabled=1\n").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[VR]\nEnabled=1\n");
+    let names: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+    assert_eq!(names, ["Setup.ini"]);
+}
+
+#[test]
+fn append_adds_to_the_end_and_creates_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("launcher.jsonl");
+
+    atomic_write::append(&path, b"one\n").unwrap();
+    atomic_write::append(&path, b"two\n").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+}
+
+#[test]
+fn concurrent_appends_keep_every_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("service.jsonl");
+
+    std::thread::scope(|s| {
+        for t in 0..8 {
+            let path = &path;
+            s.spawn(move || {
+                for i in 0..50 {
+                    atomic_write::append(path, format!("{}-{}\n", t, i).as_bytes()).unwrap();
+                }
+            });
+        }
+    });
+
+    let text = fs::read_to_string(&path).unwrap();
+    assert_eq!(text.lines().count(), 8 * 50);
+    assert!(text.lines().all(|line| line.split_once('-').is_some()));
+}
+
+#[cfg(unix)]
+#[test]
+fn writes_through_a_symlink_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("elsewhere.ini");
+    fs::write(&target, "original").unwrap();
+    let link = dir.path().join("Setup.ini");
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    for result in [write_atomic(&link, b"replaced"), atomic_write::append(&link, b"appended")] {
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let refused = err.get_ref().and_then(|e| e.downcast_ref::<atomic_write::LinkRefused>()).unwrap();
+        assert_eq!(refused.path, link);
+    }
+    assert_eq!(fs::read_to_string(&target).unwrap(), "original");
+}
+
+#[cfg(unix)]
+#[test]
+fn writes_into_a_linked_directory_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let real = dir.path().join("real");
+    fs::create_dir(&real).unwrap();
+    let linked = dir.path().join("Pilots");
+    std::os::unix::fs::symlink(&real, &linked).unwrap();
+
+    assert!(write_atomic(&linked.join("Setup.ini"), b"x").is_err());
+    assert!(atomic_write::append(&linked.join("log.jsonl"), b"x").is_err());
+    assert_eq!(fs::read_dir(&real).unwrap().count(), 0);
+}
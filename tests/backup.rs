@@ -0,0 +1,71 @@
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+use condor3_revive_helper::backup::format_utc;
+use condor3_revive_helper::{BackupStore, CondorVersion, SetupFile};
+
+fn pilot_file(dir: &std::path::Path) -> SetupFile {
+    let path = dir.join("Condor3/Pilots/Adam/Setup.ini");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    SetupFile { data_dir: "Condor3".into(), version: CondorVersion::Condor3, pilot: Some("Adam".into()), path }
+}
+
+#[test]
+fn keeps_only_the_newest_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = pilot_file(dir.path());
+    let store = BackupStore::new(dir.path().join("Backups"), 3);
+
+    for i in 0..5 {
+        fs::write(&file.path, format!("version {}", i)).unwrap();
+        store.backup(&file).unwrap();
+    }
+
+    let backups = store.list(&file);
+    assert_eq!(backups.len(), 3);
+    assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "version 4");
+    assert_eq!(fs::read_to_string(&backups[2].path).unwrap(), "version 2");
+}
+
+#[test]
+fn restore_puts_backup_back_and_keeps_the_replaced_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = pilot_file(dir.path());
+    let store = BackupStore::new(dir.path().join("Backups"), 10);
+
+    fs::write(&file.path, "good").unwrap();
+    let good = store.backup(&file).unwrap();
+    fs::write(&file.path, "broken").unwrap();
+
+    store.restore(&good, &file).unwrap();
+
+    assert_eq!(fs::read_to_string(&file.path).unwrap(), "good");
+    let latest = &store.list(&file)[0];
+    assert_eq!(fs::read_to_string(&latest.path).unwrap(), "broken");
+}
+
+#[test]
+fn data_dirs_with_the_same_name_keep_separate_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let c_drive = pilot_file(&dir.path().join("C"));
+    let d_drive = pilot_file(&dir.path().join("D"));
+    fs::write(&c_drive.path, "on C").unwrap();
+    fs::write(&d_drive.path, "on D").unwrap();
+    let store = BackupStore::new(dir.path().join("Backups"), 10);
+
+    store.backup(&c_drive).unwrap();
+    store.backup(&d_drive).unwrap();
+
+    let c_backups = store.list(&c_drive);
+    let d_backups = store.list(&d_drive);
+    assert_eq!((c_backups.len(), d_backups.len()), (1, 1));
+    assert_eq!(fs::read_to_string(&c_backups[0].path).unwrap(), "on C");
+    assert_eq!(fs::read_to_string(&d_backups[0].path).unwrap(), "on D");
+    assert_ne!(c_backups[0].path.parent(), d_backups[0].path.parent());
+}
+
+#[test]
+fn formats_utc_timestamps() {
+    let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+    assert_eq!(format_utc(time), "2024-02-29 12:34:56 UTC");
+}
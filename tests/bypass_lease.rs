@@ -0,0 +1,92 @@
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+use condor3_revive_helper::bypass_lease::{BypassLease, LeaseEnd, POLL_INTERVAL};
+use condor3_revive_helper::process_watch::ProcessEntry;
+
+fn process(pid: u32, exe_name: &str) -> ProcessEntry {
+    ProcessEntry { pid, parent_pid: 1, exe_name: exe_name.to_string() }
+}
+
+#[test]
+fn lease_ends_when_the_program_starts() {
+    let start = Instant::now();
+    let lease = BypassLease::new("Condor.exe", Some(20), &[], start, Duration::from_secs(60));
+    let mut processes = vec![process(20, "CondorVR.exe")];
+
+    assert_eq!(lease.check(start + Duration::from_secs(5), &processes), None);
+    processes.push(process(30, "condor.EXE"));
+    assert_eq!(lease.check(start + Duration::from_secs(6), &processes), Some(LeaseEnd::ProgramStarted));
+    assert!(!LeaseEnd::ProgramStarted.is_abandoned());
+}
+
+#[test]
+fn a_copy_already_running_does_not_end_the_lease() {
+    let start = Instant::now();
+    let running = [process(20, "CondorVR.exe"), process(9, "Condor.exe")];
+    let lease = BypassLease::new("Condor.exe", Some(20), &running, start, Duration::from_secs(60));
+
+    assert_eq!(lease.check(start + Duration::from_secs(5), &running), None);
+    let started = [process(20, "CondorVR.exe"), process(9, "Condor.exe"), process(30, "Condor.exe")];
+    assert_eq!(lease.check(start + Duration::from_secs(6), &started), Some(LeaseEnd::ProgramStarted));
+}
+
+#[test]
+fn lease_ends_when_the_launcher_exits() {
+    let start = Instant::now();
+    let lease = BypassLease::new("Condor.exe", Some(20), &[], start, Duration::from_secs(60));
+
+    let end = lease.check(start, &[process(7, "explorer.exe")]).unwrap();
+    assert_eq!(end, LeaseEnd::LauncherExited(20));
+    assert!(end.is_abandoned());
+    assert_eq!(end.to_string(), "the launcher (PID 20) exited");
+
+    // A reused PID doesn't keep the hook lifted.
+    assert_eq!(lease.check(start, &[process(20, "notepad.exe")]), Some(LeaseEnd::LauncherExited(20)));
+}
+
+#[test]
+fn lease_runs_out() {
+    let start = Instant::now();
+    let lease = BypassLease::new("Condor.exe", None, &[], start, Duration::from_secs(60));
+
+    assert_eq!(lease.check(start + Duration::from_secs(59), &[]), None);
+    let end = lease.check(start + Duration::from_secs(60), &[]).unwrap();
+    assert_eq!(end, LeaseEnd::Expired(Duration::from_secs(60)));
+    assert_eq!(end.to_string(), "it wasn't started within 60 seconds");
+}
+
+#[test]
+fn waiting_polls_a_fake_clock_and_process_list() {
+    let start = Instant::now();
+    let lease = BypassLease::new("Condor.exe", Some(20), &[], start, Duration::from_secs(10));
+    let clock = Cell::new(start);
+    let sleeps = Cell::new(0);
+    let processes = RefCell::new(vec![process(20, "CondorVR.exe")]);
+
+    let end = lease.wait(
+        || clock.get(),
+        || processes.borrow().clone(),
+        || false,
+        |d| {
+            clock.set(clock.get() + d);
+            sleeps.set(sleeps.get() + 1);
+            // The injector starts Condor two seconds in.
+            if clock.get() - start >= Duration::from_secs(2) {
+                processes.borrow_mut().push(process(30, "Condor.exe"));
+            }
+        },
+    );
+    assert_eq!(end, LeaseEnd::ProgramStarted);
+    assert_eq!(sleeps.get(), (Duration::from_secs(2).as_millis() / POLL_INTERVAL.as_millis()) as usize);
+
+    // Without a launcher or program, it gives up on time.
+    let lease = BypassLease::new("Condor.exe", None, &[], start, Duration::from_secs(10));
+    clock.set(start);
+    let end = lease.wait(|| clock.get(), Vec::new, || false, |d| clock.set(clock.get() + d));
+    assert_eq!(end, LeaseEnd::Expired(Duration::from_secs(10)));
+    assert_eq!(clock.get() - start, Duration::from_secs(10));
+
+    let end = lease.wait(|| clock.get(), Vec::new, || true, |_| unreachable!());
+    assert_eq!(end, LeaseEnd::Stopped);
+}
@@ -0,0 +1,183 @@
+use std::io::{self, BufReader, Read, Write};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+use condor3_revive_helper::bypass_lease::LeaseEnd;
+use condor3_revive_helper::bypass_protocol::{
+    BypassClient, BypassRequest, BypassState, ErrorCode, MAX_MESSAGE_BYTES, ProtocolError, ServiceReply, read_message,
+    serve, write_message,
+};
+
+/// One direction of an in-memory pipe.
+struct ChannelReader {
+    rx: Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.rx.recv() {
+                Ok(bytes) => self.pending = bytes,
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+struct ChannelWriter(Sender<Vec<u8>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf.to_vec()).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+type End = (BufReader<ChannelReader>, ChannelWriter);
+
+/// Both ends of an in-memory duplex pipe.
+fn pipe() -> (End, End) {
+    let (a_tx, a_rx) = channel();
+    let (b_tx, b_rx) = channel();
+    let end = |rx, tx| (BufReader::new(ChannelReader { rx, pending: Vec::new() }), ChannelWriter(tx));
+    (end(a_rx, b_tx), end(b_rx, a_tx))
+}
+
+/// Runs the service side on its own thread, lifting and restoring the hook with the given results.
+fn spawn_service(
+    (mut reader, mut writer): End,
+    client_pid: Option<u32>,
+    lifted: Result<(), ErrorCode>,
+    held: Result<LeaseEnd, ErrorCode>,
+) -> thread::JoinHandle<Result<(BypassRequest, LeaseEnd), ProtocolError>> {
+    thread::spawn(move || serve(&mut reader, &mut writer, client_pid, |_| lifted, |_| held))
+}
+
+#[test]
+fn bypass_is_acknowledged_then_restored() {
+    let (launcher, service_end) = pipe();
+    let service = spawn_service(service_end, Some(42), Ok(()), Ok(LeaseEnd::ProgramStarted));
+
+    let (mut reader, mut writer) = launcher;
+    let mut client = BypassClient::new(BypassRequest::new("Condor.exe", 42));
+    client.request_bypass(&mut reader, &mut writer).unwrap();
+    assert_eq!(client.state(), &BypassState::Lifted);
+
+    let restored = client.wait_for_restore(&mut reader).unwrap();
+    assert_eq!(restored, &BypassState::Restored { program_started: true, reason: "the program started".to_string() });
+
+    let (request, end) = service.join().unwrap().unwrap();
+    assert_eq!(&request, client.request());
+    assert_eq!(end, LeaseEnd::ProgramStarted);
+}
+
+#[test]
+fn refusals_carry_an_error_code() {
+    let (launcher, service_end) = pipe();
+    let service = spawn_service(service_end, None, Err(ErrorCode::NotHooked), Ok(LeaseEnd::Stopped));
+    let (mut reader, mut writer) = launcher;
+    let mut client = BypassClient::new(BypassRequest::new("notepad.exe", 42));
+    let error = client.request_bypass(&mut reader, &mut writer).unwrap_err();
+    assert!(matches!(error, ProtocolError::Refused(ErrorCode::NotHooked)), "{}", error);
+    assert!(matches!(service.join().unwrap(), Err(ProtocolError::Refused(ErrorCode::NotHooked))));
+
+    // With a broken config no hook is lifted, and the launcher is told why.
+    let (launcher, service_end) = pipe();
+    let service = spawn_service(service_end, None, Err(ErrorCode::ConfigInvalid), Ok(LeaseEnd::Stopped));
+    let (mut reader, mut writer) = launcher;
+    let error = BypassClient::new(BypassRequest::new("Condor.exe", 42)).request_bypass(&mut reader, &mut writer);
+    assert!(matches!(error, Err(ProtocolError::Refused(ErrorCode::ConfigInvalid))));
+    service.join().unwrap().unwrap_err();
+
+    // A request claiming another process's PID is refused before the hook is touched.
+    let (launcher, service_end) = pipe();
+    let service = spawn_service(service_end, Some(7), Ok(()), Ok(LeaseEnd::Stopped));
+    let (mut reader, mut writer) = launcher;
+    let error = BypassClient::new(BypassRequest::new("Condor.exe", 42)).request_bypass(&mut reader, &mut writer);
+    assert!(matches!(error, Err(ProtocolError::Refused(ErrorCode::WrongPid))));
+    service.join().unwrap().unwrap_err();
+
+    // Failing to put the hook back is reported after the acknowledgement.
+    let (launcher, service_end) = pipe();
+    let service = spawn_service(service_end, None, Ok(()), Err(ErrorCode::HookNotRestored));
+    let (mut reader, mut writer) = launcher;
+    let mut client = BypassClient::new(BypassRequest::new("Condor.exe", 42));
+    client.request_bypass(&mut reader, &mut writer).unwrap();
+    let error = client.wait_for_restore(&mut reader).unwrap_err();
+    assert_eq!(error.to_string(), "the service refused: the IFEO hook couldn't be put back");
+    service.join().unwrap().unwrap_err();
+}
+
+#[test]
+fn other_versions_are_refused() {
+    let (launcher, service_end) = pipe();
+    let service = spawn_service(service_end, None, Ok(()), Ok(LeaseEnd::Stopped));
+    let (mut reader, mut writer) = launcher;
+    writer.write_all(b"{\"version\":2,\"target\":\"Condor.exe\",\"pid\":42,\"nonce\":1,\"priority\":9}\n").unwrap();
+
+    let reply: ServiceReply = read_message(&mut reader).unwrap();
+    assert_eq!(reply, ServiceReply::Refused { nonce: 0, code: ErrorCode::UnsupportedVersion });
+    assert!(matches!(service.join().unwrap(), Err(ProtocolError::UnsupportedVersion(2))));
+}
+
+#[test]
+fn messages_are_single_json_lines() {
+    let mut bytes = Vec::new();
+    write_message(&mut bytes, &ServiceReply::Ack { nonce: 7 }).unwrap();
+    assert_eq!(bytes, b"{\"version\":1,\"type\":\"ack\",\"nonce\":7}\n");
+    let reply: ServiceReply = read_message(&mut &bytes[..]).unwrap();
+    assert_eq!(reply, ServiceReply::Ack { nonce: 7 });
+
+    let read = |text: &[u8]| read_message::<ServiceReply>(&mut &text[..]).unwrap_err();
+    assert!(matches!(read(b""), ProtocolError::Closed));
+    assert!(matches!(read(b"{\"version\":1,\"type\":\"ack\""), ProtocolError::Closed));
+    assert!(matches!(read(b"{\"type\":\"ack\",\"nonce\":7}\n"), ProtocolError::Malformed(_)));
+    assert!(matches!(read(b"{\"version\":1,\"type\":\"shout\",\"nonce\":7}\n"), ProtocolError::Malformed(_)));
+    assert!(matches!(read(&[b' '; MAX_MESSAGE_BYTES + 1]), ProtocolError::TooLong));
+}
+
+#[test]
+fn replies_must_fit_the_conversation() {
+    let request = BypassRequest::new("Condor.exe", 42);
+    let nonce = request.nonce;
+    assert_ne!(nonce, BypassRequest::new("Condor.exe", 42).nonce);
+    let restored = ServiceReply::Restored { nonce, program_started: false, reason: "the service was stopped".to_string() };
+
+    let mut client = BypassClient::new(request);
+    assert!(matches!(client.receive(ServiceReply::Ack { nonce: nonce ^ 1 }), Err(ProtocolError::Unexpected(_))));
+    assert!(matches!(client.receive(restored.clone()), Err(ProtocolError::Unexpected(_))));
+    assert_eq!(client.receive(ServiceReply::Queued { nonce, ahead: 2 }).unwrap(), &BypassState::Queued { ahead: 2 });
+    assert_eq!(client.receive(ServiceReply::Queued { nonce, ahead: 1 }).unwrap(), &BypassState::Queued { ahead: 1 });
+    assert_eq!(client.receive(ServiceReply::Ack { nonce }).unwrap(), &BypassState::Lifted);
+    assert!(matches!(client.receive(ServiceReply::Ack { nonce }), Err(ProtocolError::Unexpected(_))));
+    assert!(matches!(client.receive(ServiceReply::Queued { nonce, ahead: 0 }), Err(ProtocolError::Unexpected(_))));
+    assert!(matches!(client.receive(restored).unwrap(), BypassState::Restored { program_started: false, .. }));
+}
+
+#[test]
+fn service_finishes_even_if_the_launcher_has_gone() {
+    let (launcher, service_end) = pipe();
+    let (mut reader, mut writer) = launcher;
+    let service = thread::spawn(move || {
+        let (mut reader, mut writer) = service_end;
+        serve(&mut reader, &mut writer, None, |_| Ok(()), |_| {
+            thread::sleep(Duration::from_millis(50));
+            Ok(LeaseEnd::LauncherExited(42))
+        })
+    });
+
+    let mut client = BypassClient::new(BypassRequest::new("Condor.exe", 42));
+    client.request_bypass(&mut reader, &mut writer).unwrap();
+    drop((reader, writer));
+    assert_eq!(service.join().unwrap().unwrap().1, LeaseEnd::LauncherExited(42));
+}
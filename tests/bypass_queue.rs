@@ -0,0 +1,131 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use condor3_revive_helper::bypass_lease::LeaseEnd;
+use condor3_revive_helper::bypass_protocol::{BypassClient, BypassRequest, BypassState, ErrorCode, ProtocolError};
+use condor3_revive_helper::bypass_queue::BypassQueue;
+
+/// One direction of an in-memory pipe.
+struct ChannelReader {
+    rx: Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.rx.recv() {
+                Ok(bytes) => self.pending = bytes,
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+struct ChannelWriter(Sender<Vec<u8>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf.to_vec()).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The launcher's end of a pipe whose other end, the service's writer, is returned alongside it.
+fn pipe() -> (BufReader<ChannelReader>, ChannelWriter) {
+    let (tx, rx) = channel();
+    (BufReader::new(ChannelReader { rx, pending: Vec::new() }), ChannelWriter(tx))
+}
+
+/// Reads replies until the hook is back, returning every state the launcher went through.
+fn follow(client: &mut BypassClient, reader: &mut impl BufRead) -> Vec<BypassState> {
+    let mut states = Vec::new();
+    loop {
+        let state = client.next(reader).unwrap().clone();
+        states.push(state.clone());
+        if matches!(state, BypassState::Restored { .. }) {
+            return states;
+        }
+    }
+}
+
+#[test]
+fn requests_are_handled_one_at_a_time_in_order() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (lift_events, hold_events) = (events.clone(), events.clone());
+    let (queue, worker) = BypassQueue::start(
+        move |request: &BypassRequest| {
+            lift_events.lock().unwrap().push(format!("lift {}", request.pid));
+            Ok(())
+        },
+        move |request: &BypassRequest| {
+            thread::sleep(Duration::from_millis(20));
+            hold_events.lock().unwrap().push(format!("restore {}", request.pid));
+            Ok(LeaseEnd::ProgramStarted)
+        },
+        |_, result: Result<LeaseEnd, ProtocolError>| assert_eq!(result.unwrap(), LeaseEnd::ProgramStarted),
+    );
+
+    let mut launchers = Vec::new();
+    for pid in 1..=3 {
+        let (reader, writer) = pipe();
+        let client = BypassClient::new(BypassRequest::new("Condor.exe", pid));
+        assert_eq!(queue.push(client.request().clone(), writer), pid as usize - 1);
+        launchers.push((client, reader));
+    }
+    drop(queue);
+    worker.join().unwrap();
+
+    for (ahead, (mut client, mut reader)) in launchers.into_iter().enumerate() {
+        let states = follow(&mut client, &mut reader);
+        assert_eq!(states[0], BypassState::Queued { ahead });
+        assert_eq!(states[1], BypassState::Lifted);
+        assert!(matches!(states[2], BypassState::Restored { program_started: true, .. }));
+    }
+    assert_eq!(*events.lock().unwrap(), ["lift 1", "restore 1", "lift 2", "restore 2", "lift 3", "restore 3"]);
+}
+
+#[test]
+fn a_refused_request_does_not_hold_up_the_queue() {
+    // The refusal waits until the second launch has queued behind it.
+    let queued = Arc::new(Barrier::new(2));
+    let refusal_queued = queued.clone();
+    let (queue, worker) = BypassQueue::start(
+        move |request: &BypassRequest| {
+            if request.target == "notepad.exe" {
+                refusal_queued.wait();
+                return Err(ErrorCode::NotHooked);
+            }
+            Ok(())
+        },
+        |_: &BypassRequest| Ok(LeaseEnd::Expired(Duration::from_secs(60))),
+        |_, _| {},
+    );
+
+    let (mut refused_reader, writer) = pipe();
+    let mut refused = BypassClient::new(BypassRequest::new("notepad.exe", 1));
+    queue.push(refused.request().clone(), writer);
+    let (mut reader, writer) = pipe();
+    let mut client = BypassClient::new(BypassRequest::new("Condor.exe", 2));
+    assert_eq!(queue.push(client.request().clone(), writer), 1);
+    queued.wait();
+    drop(queue);
+    worker.join().unwrap();
+
+    assert_eq!(refused.next(&mut refused_reader).unwrap(), &BypassState::Queued { ahead: 0 });
+    assert!(matches!(refused.next(&mut refused_reader), Err(ProtocolError::Refused(ErrorCode::NotHooked))));
+    let states = follow(&mut client, &mut reader);
+    assert_eq!(states[..2], [BypassState::Queued { ahead: 1 }, BypassState::Lifted]);
+    assert!(matches!(states[2], BypassState::Restored { program_started: false, .. }));
+}
@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use condor3_revive_helper::config::{ServiceMode, env_value};
+use condor3_revive_helper::logging::LogLevel;
+use condor3_revive_helper::{Config, ConfigError, HookedExe};
+
+fn parse(text: &str) -> Result<Config, ConfigError> {
+    Config::parse(Path::new("config.toml"), text)
+}
+
+#[test]
+fn empty_file_uses_the_defaults() {
+    let config = parse("").unwrap();
+    assert_eq!(config, Config::default());
+    assert_eq!(config.log_level, LogLevel::Info);
+    assert_eq!(config.timeouts.service_start_secs, 5);
+    assert_eq!(config.timeouts.bypass_lease(), std::time::Duration::from_secs(60));
+    assert!(config.launch.allow_flatscreen && config.launch.block_old_revive);
+    assert_eq!(config.service.mode, ServiceMode::Demand);
+    assert_eq!(config.hooked_exes(), [HookedExe::new("Condor.exe")]);
+}
+
+#[test]
+fn full_config_round_trips() {
+    let text = r#"
+log_level = "debug"
+
+[injector]
+path = 'C:\Program Files\Revive\Revive\ReviveInjector.exe'
+args = ["/handle"]
+
+[timeouts]
+service_start_secs = 10
+
+[launch]
+allow_flatscreen = false
+
+[service]
+mode = "persistent"
+
+[[executable]]
+name = "Condor.exe"
+
+[[executable]]
+name = "CondorClub.exe"
+enabled = false
+injector_path = 'D:\Revive Beta\ReviveInjector.exe'
+"#;
+    let config = parse(text).unwrap();
+    assert_eq!(config.log_level, LogLevel::Debug);
+    assert_eq!(config.injector.args, ["/handle"]);
+    assert_eq!(config.timeouts.service_start_secs, 10);
+    assert_eq!(config.timeouts.hook_removal_secs, 5);
+    assert!(!config.launch.allow_flatscreen);
+    assert!(config.launch.block_old_revive);
+    assert_eq!(config.service.mode, ServiceMode::Persistent);
+    assert_eq!(config.executables.len(), 2);
+    assert!(!config.executables[1].enabled);
+
+    assert_eq!(parse(&config.to_toml()).unwrap(), config);
+}
+
+#[test]
+fn bad_values_say_what_is_wrong() {
+    let message = |text: &str| parse(text).unwrap_err().to_string();
+
+    let unknown = message("log_levle = \"debug\"");
+    assert!(unknown.contains("config.toml") && unknown.contains("log_levle"), "{}", unknown);
+
+    let level = message("log_level = \"loud\"");
+    assert!(level.contains("loud") && level.contains("debug"), "{}", level);
+
+    let timeout = message("[timeouts]\nhook_removal_secs = 0");
+    assert!(timeout.contains("timeouts.hook_removal_secs must be between 1 and 300"), "{}", timeout);
+
+    let duplicate = message("[[executable]]\nname = \"Condor.exe\"\n[[executable]]\nname = \"condor.exe\"");
+    assert!(duplicate.contains("listed more than once"), "{}", duplicate);
+
+    let path = message("[[executable]]\nname = 'C:\\Condor3\\Condor.exe'");
+    assert!(path.contains("not a path"), "{}", path);
+
+    let injector = message("[injector]\npath = 'C:\\Program Files\\Revive'");
+    assert!(injector.contains("injector.path should be the full path"), "{}", injector);
+}
+
+#[test]
+fn saved_config_loads_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("CondorVR").join("config.toml");
+    assert!(Config::load(&path).unwrap().is_none());
+
+    let mut config = Config::default();
+    config.executables.push(HookedExe::new("Condor2.exe"));
+    config.save(&path).unwrap();
+    assert_eq!(Config::load(&path).unwrap(), Some(config));
+}
+
+#[test]
+fn older_settings_are_migrated() {
+    let env_file = "# Revive\nC3_REVIVE_INJECTOR_PATH = \"D:\\Revive\\ReviveInjector.exe\"\n";
+
+    let registry = r"C:\Program Files\Revive\Revive\ReviveInjector.exe";
+
+    let config = Config::migrate(Some(env_file), Some(registry));
+    assert_eq!(config.injector.path, Some(PathBuf::from(r"D:\Revive\ReviveInjector.exe")));
+    assert!(config.executables.is_empty());
+    assert_eq!(config.timeouts, Config::default().timeouts);
+
+    // Without a .env, the path saved in the registry when VR was enabled is kept.
+    assert_eq!(Config::migrate(None, Some(registry)).injector.path, Some(PathBuf::from(registry)));
+    assert_eq!(Config::migrate(Some("# empty\n"), Some(registry)).injector.path, Some(PathBuf::from(registry)));
+    assert_eq!(Config::migrate(None, None), Config::default());
+    assert_eq!(env_value("KEY='quoted'\n#KEY=old", "KEY").as_deref(), Some("quoted"));
+    assert_eq!(env_value("OTHER=1", "KEY"), None);
+}
+
+#[test]
+fn injector_comes_from_the_executable_then_the_config_then_detection() {
+    let detected = Path::new(r"C:\Program Files\Revive\Revive\ReviveInjector.exe");
+    let condor = HookedExe::new("Condor.exe");
+    let mut config = Config::default();
+
+    assert_eq!(config.injector_for(&condor, None), None);
+    assert_eq!(config.injector_for(&condor, Some(detected)).unwrap().path, detected);
+
+    config.injector.path = Some(PathBuf::from(r"D:\Revive\ReviveInjector.exe"));
+    config.injector.args = vec!["/handle".to_string()];
+    let injector = config.injector_for(&condor, Some(detected)).unwrap();
+    assert_eq!(injector.path, Path::new(r"D:\Revive\ReviveInjector.exe"));
+    assert_eq!(injector.args, ["/handle"]);
+
+    let mut club = HookedExe::new("CondorClub.exe");
+    club.injector_path = Some(PathBuf::from(r"E:\ReviveInjector.exe"));
+    club.injector_args = vec!["/other".to_string()];
+    let injector = config.injector_for(&club, Some(detected)).unwrap();
+    assert_eq!(injector.path, Path::new(r"E:\ReviveInjector.exe"));
+    assert_eq!(injector.args, ["/other"]);
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+use condor3_revive_helper::{
+    read_vr_enabled, BackupStore, CondorProfileSet, CondorVersion, FlatscreenSession, GraphicsMode, InterceptedLaunch,
+    UserSettings,
+};
+
+fn write_setup(path: &Path, vr: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("[Graphics]\r\nVROculusRift={}\r\nResolution=1920x1080\r\n", vr)).unwrap();
+}
+
+#[test]
+fn switches_vr_files_to_flatscreen_until_the_session_ends() {
+    let docs = tempfile::tempdir().unwrap();
+    let zoe = docs.path().join("Condor3/Pilots/Zoe/Setup.ini");
+    let adam = docs.path().join("Condor3/Pilots/Adam/Setup.ini");
+    let old = docs.path().join("Condor2/Pilots/Old/Setup.ini");
+    write_setup(&zoe, "1");
+    write_setup(&adam, "0");
+    write_setup(&old, "1");
+
+    let profiles = CondorProfileSet::discover(docs.path());
+    let backups = BackupStore::new(docs.path().join("Backups"), 10);
+    let mut settings = UserSettings::default();
+    settings.set_profile_keys(vec!["Resolution".to_string()]);
+    let flatscreen = [("Resolution".to_string(), "2560x1440".to_string())].into();
+    settings.set_snapshot(&zoe, GraphicsMode::Flatscreen, flatscreen);
+
+    let sessions = docs.path().join("Sessions");
+    let (session, results) =
+        FlatscreenSession::begin(&profiles, Some(CondorVersion::Condor3), &mut settings, &backups, &sessions).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(session.files().len(), 1);
+    assert_eq!(session.files()[0].path, zoe);
+    assert_eq!(fs::read_to_string(&zoe).unwrap(), "[Graphics]\r\nVROculusRift=0\r\nResolution=2560x1440\r\n");
+    assert!(!read_vr_enabled(&adam));
+    assert!(read_vr_enabled(&old), "Condor 2 files are left alone when Condor 3 is launched");
+
+    // Whatever was tweaked on the monitor stays with the flatscreen profile.
+    fs::write(&zoe, "[Graphics]\r\nVROculusRift=0\r\nResolution=1280x720\r\n").unwrap();
+    let results = session.end(&mut settings, &backups, &sessions);
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(fs::read_dir(&sessions).unwrap().count(), 0);
+    assert_eq!(fs::read_to_string(&zoe).unwrap(), "[Graphics]\r\nVROculusRift=1\r\nResolution=1920x1080\r\n");
+    assert!(!read_vr_enabled(&adam));
+}
+
+#[test]
+fn unknown_version_switches_every_vr_file() {
+    let docs = tempfile::tempdir().unwrap();
+    write_setup(&docs.path().join("Condor3/Setup.ini"), "1");
+    write_setup(&docs.path().join("Condor2/Setup.ini"), "1");
+
+    let profiles = CondorProfileSet::discover(docs.path());
+    let backups = BackupStore::new(docs.path().join("Backups"), 10);
+    let mut settings = UserSettings::default();
+    let (session, _) = FlatscreenSession::begin(&profiles, None, &mut settings, &backups, &docs.path().join("Sessions")).unwrap();
+    assert_eq!(session.files().len(), 2);
+}
+
+#[test]
+fn a_session_left_by_a_killed_launcher_is_ended_once_it_has_gone() {
+    let docs = tempfile::tempdir().unwrap();
+    let zoe = docs.path().join("Condor3/Pilots/Zoe/Setup.ini");
+    write_setup(&zoe, "1");
+
+    let profiles = CondorProfileSet::discover(docs.path());
+    let backups = BackupStore::new(docs.path().join("Backups"), 10);
+    let sessions = docs.path().join("Sessions");
+    let mut settings = UserSettings::default();
+    let (session, _) = FlatscreenSession::begin(&profiles, None, &mut settings, &backups, &sessions).unwrap();
+    assert!(!read_vr_enabled(&zoe));
+    // The launcher is killed, so `end` never runs.
+    drop(session);
+
+    let ours = std::process::id();
+    assert!(FlatscreenSession::recover(&sessions, |pid| pid == ours, &mut settings, &backups).is_empty());
+    assert!(!read_vr_enabled(&zoe), "a launcher that is still running keeps its session");
+
+    let results = FlatscreenSession::recover(&sessions, |_| false, &mut settings, &backups);
+    assert_eq!(results.len(), 1);
+    assert!(read_vr_enabled(&zoe));
+    assert_eq!(fs::read_dir(&sessions).unwrap().count(), 0);
+}
+
+#[test]
+fn nothing_is_switched_if_the_session_cant_be_recorded() {
+    let docs = tempfile::tempdir().unwrap();
+    let zoe = docs.path().join("Condor3/Pilots/Zoe/Setup.ini");
+    write_setup(&zoe, "1");
+    let blocked = docs.path().join("Sessions");
+    fs::write(&blocked, "not a directory").unwrap();
+
+    let profiles = CondorProfileSet::discover(docs.path());
+    let backups = BackupStore::new(docs.path().join("Backups"), 10);
+    let mut settings = UserSettings::default();
+    assert!(FlatscreenSession::begin(&profiles, None, &mut settings, &backups, &blocked).is_err());
+    assert!(read_vr_enabled(&zoe));
+}
+
+#[test]
+fn version_comes_from_the_install_folder() {
+    let launch = |cmdline: &str| InterceptedLaunch::parse(cmdline).unwrap().condor_version();
+    assert_eq!(launch(r#"CondorVR.exe "C:\Condor3\Condor.exe""#), Some(CondorVersion::Condor3));
+    assert_eq!(launch(r#"CondorVR.exe "D:\Games\Condor 2\Condor.exe" -x"#), Some(CondorVersion::Condor2));
+    assert_eq!(launch(r#"CondorVR.exe "D:\Games\Sim\Condor.exe""#), None);
+    assert_eq!(launch("CondorVR.exe Condor.exe"), None);
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use condor3_revive_helper::hooked_exe::{HookedExe, default_hooked_exes, validate_exe_name};
+use condor3_revive_helper::ifeo::MemoryRegistry;
+use condor3_revive_helper::plan::{RegistryChange, hook_changes, removal_changes};
+use condor3_revive_helper::{ActivationPlan, IFEO_PATH};
+
+fn set(key: &str, name: &str, value: &str) -> RegistryChange {
+    RegistryChange::Set { key: key.to_string(), name: name.to_string(), value: value.to_string() }
+}
+
+fn delete(key: &str, name: &str) -> RegistryChange {
+    RegistryChange::Delete { key: key.to_string(), name: name.to_string() }
+}
+
+#[test]
+fn exe_names_must_be_bare_executables() {
+    assert!(validate_exe_name("Condor.exe").is_ok());
+    assert!(validate_exe_name("CONDOR2.EXE").is_ok());
+    assert!(validate_exe_name(r"C:\Condor3\Condor.exe").is_err());
+    assert!(validate_exe_name("Condor").is_err());
+    assert!(validate_exe_name(".exe").is_err());
+}
+
+#[test]
+fn only_enabled_executables_are_hooked() {
+    let mut disabled = HookedExe::new("Condor2.exe");
+    disabled.enabled = false;
+    let exes = vec![HookedExe::new("Condor.exe"), disabled];
+
+    let registry = MemoryRegistry::new();
+    let changes = hook_changes(&registry, Path::new(r"C:\Program Files\CondorVR\CondorVR.exe"), &exes);
+    let ifeo = IFEO_PATH;
+    assert_eq!(
+        changes,
+        [
+            set(&format!(r"{}\Condor.exe", ifeo), "Debugger", r#""C:\Program Files\CondorVR\CondorVR.exe""#),
+            delete(&format!(r"{}\Condor2.exe", ifeo), "Debugger"),
+        ]
+    );
+
+    let plan = ActivationPlan::deactivate(&registry, &exes, Vec::new());
+    assert_eq!(
+        plan.registry,
+        [delete(&format!(r"{}\Condor.exe", ifeo), "Debugger"), delete(&format!(r"{}\Condor2.exe", ifeo), "Debugger")]
+    );
+}
+
+#[test]
+fn dropped_executables_are_unhooked() {
+    let old = vec![HookedExe::new("Condor.exe"), HookedExe::new("CondorClub.exe")];
+    let new = vec![HookedExe::new("condorclub.EXE")];
+
+    let registry = MemoryRegistry::new();
+    assert_eq!(removal_changes(&registry, &old, &new), [delete(&old[0].ifeo_key(), "Debugger")]);
+    assert_eq!(removal_changes(&registry, &new, &default_hooked_exes()), [delete(&new[0].ifeo_key(), "Debugger")]);
+    assert!(removal_changes(&registry, &old, &old).is_empty());
+}
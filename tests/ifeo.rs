@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use condor3_revive_helper::hooked_exe::{
+    HookedExe, ifeo_key, unlisted_installs, validate_install_path, with_install_paths,
+};
+use condor3_revive_helper::ifeo::{
+    MemoryRegistry, Registry, apply_all, hook_changes, is_hooked, lift_changes, restore_changes, unhook_changes,
+};
+use condor3_revive_helper::plan::RegistryChange;
+
+const LAUNCHER: &str = r"C:\Program Files\CondorVR\CondorVR.exe";
+
+fn scoped(paths: &[&str]) -> HookedExe {
+    let mut exe = HookedExe::new("Condor.exe");
+    exe.paths = paths.iter().map(PathBuf::from).collect();
+    exe
+}
+
+/// Hooks `exe` in `registry` as the configurer would.
+fn hook(registry: &mut MemoryRegistry, exe: &HookedExe) {
+    let changes = hook_changes(registry, Path::new(LAUNCHER), exe);
+    apply_all(registry, &changes).unwrap();
+}
+
+#[test]
+fn scoped_hooks_use_one_filter_subkey_per_install() {
+    let mut registry = MemoryRegistry::new();
+    hook(&mut registry, &scoped(&[r"C:\Condor3\Condor.exe", r"D:\Condor2\Condor.exe"]));
+
+    let key = ifeo_key("Condor.exe");
+    assert_eq!(registry.dword(&key, "UseFilter"), Some(1));
+    assert_eq!(registry.string(&key, "Debugger"), None);
+    assert_eq!(registry.subkeys(&key), ["CondorVR1", "CondorVR2"]);
+    let first = format!(r"{}\CondorVR1", key);
+    assert_eq!(registry.string(&first, "FilterFullPath").as_deref(), Some(r"C:\Condor3\Condor.exe"));
+    assert_eq!(registry.string(&first, "Debugger"), Some(format!("\"{}\"", LAUNCHER)));
+    assert_eq!(registry.string(&format!(r"{}\CondorVR2", key), "FilterFullPath").as_deref(), Some(r"D:\Condor2\Condor.exe"));
+    assert!(is_hooked(&registry, "condor.EXE"));
+
+    // Dropping an install removes its subkey; hooking again with the same list changes nothing.
+    hook(&mut registry, &scoped(&[r"C:\Condor3\Condor.exe"]));
+    assert_eq!(registry.subkeys(&key), ["CondorVR1"]);
+    let before = registry.clone();
+    hook(&mut registry, &scoped(&[r"C:\Condor3\Condor.exe"]));
+    assert_eq!(registry, before);
+}
+
+#[test]
+fn switching_layouts_clears_up_the_old_one() {
+    let key = ifeo_key("Condor.exe");
+    let mut registry = MemoryRegistry::new();
+    hook(&mut registry, &HookedExe::new("Condor.exe"));
+    assert!(registry.string(&key, "Debugger").is_some());
+
+    hook(&mut registry, &scoped(&[r"C:\Condor3\Condor.exe"]));
+    assert_eq!(registry.string(&key, "Debugger"), None);
+    assert!(is_hooked(&registry, "Condor.exe"));
+
+    hook(&mut registry, &HookedExe::new("Condor.exe"));
+    assert!(registry.subkeys(&key).is_empty());
+    assert_eq!(registry.dword(&key, "UseFilter"), None);
+    assert!(is_hooked(&registry, "Condor.exe"));
+
+    let changes = unhook_changes(&registry, "Condor.exe");
+    apply_all(&mut registry, &changes).unwrap();
+    assert!(!is_hooked(&registry, "Condor.exe"));
+}
+
+#[test]
+fn other_filters_are_left_alone() {
+    let key = ifeo_key("Condor.exe");
+    let theirs = format!(r"{}\Tracer", key);
+    let mut registry = MemoryRegistry::new();
+    let setup = [
+        RegistryChange::SetDword { key: key.clone(), name: "UseFilter".to_string(), value: 1 },
+        RegistryChange::Set { key: theirs.clone(), name: "FilterFullPath".to_string(), value: r"E:\Other\Condor.exe".to_string() },
+        RegistryChange::Set { key: theirs.clone(), name: "Debugger".to_string(), value: "tracer.exe".to_string() },
+    ];
+    apply_all(&mut registry, &setup).unwrap();
+    // Someone else's filter isn't our hook.
+    assert!(!is_hooked(&registry, "Condor.exe"));
+
+    // A plain hook beside their filter keeps `UseFilter` on, and is still ours to lift and restore.
+    hook(&mut registry, &HookedExe::new("Condor.exe"));
+    assert_eq!(registry.dword(&key, "UseFilter"), Some(1));
+    assert!(is_hooked(&registry, "Condor.exe"));
+    let hooked = registry.clone();
+    let lift = lift_changes(&registry, "Condor.exe");
+    assert_eq!(lift, [RegistryChange::Delete { key: key.clone(), name: "Debugger".to_string() }]);
+    apply_all(&mut registry, &lift).unwrap();
+    assert!(!is_hooked(&registry, "Condor.exe"));
+    let restore = restore_changes(&registry, "Condor.exe", Path::new(LAUNCHER));
+    apply_all(&mut registry, &restore).unwrap();
+    assert_eq!(registry, hooked);
+
+    hook(&mut registry, &scoped(&[r"C:\Condor3\Condor.exe"]));
+    let changes = unhook_changes(&registry, "Condor.exe");
+    apply_all(&mut registry, &changes).unwrap();
+    assert_eq!(registry.subkeys(&key), ["Tracer"]);
+    assert_eq!(registry.dword(&key, "UseFilter"), Some(1));
+    assert_eq!(registry.string(&theirs, "Debugger").as_deref(), Some("tracer.exe"));
+}
+
+#[test]
+fn the_service_lifts_and_restores_either_layout() {
+    for exe in [HookedExe::new("Condor.exe"), scoped(&[r"C:\Condor3\Condor.exe", r"D:\Condor2\Condor.exe"])] {
+        let mut registry = MemoryRegistry::new();
+        hook(&mut registry, &exe);
+        let hooked = registry.clone();
+
+        let lift = lift_changes(&registry, "Condor.exe");
+        assert_eq!(lift.len(), exe.paths.len().max(1));
+        apply_all(&mut registry, &lift).unwrap();
+        assert!(!is_hooked(&registry, "Condor.exe"));
+
+        let restore = restore_changes(&registry, "Condor.exe", Path::new(LAUNCHER));
+        apply_all(&mut registry, &restore).unwrap();
+        assert_eq!(registry, hooked);
+    }
+}
+
+#[test]
+fn condor_is_scoped_to_the_installs_found_unless_configured() {
+    let found = [PathBuf::from(r"C:\Condor3\Condor.exe")];
+    let configured = scoped(&[r"D:\Condor3\Condor.exe"]);
+    let exes = with_install_paths(vec![HookedExe::new("Condor.exe"), HookedExe::new("CondorClub.exe")], &found);
+    assert_eq!(exes[0].paths, found);
+    assert!(exes[1].paths.is_empty());
+    assert_eq!(with_install_paths(vec![configured.clone()], &found), std::slice::from_ref(&configured));
+
+    // A saved list doesn't follow Condor when it moves, so installs it misses are pointed out.
+    assert_eq!(unlisted_installs(&configured, &found), found);
+    assert!(unlisted_installs(&scoped(&[r"c:\condor3\CONDOR.exe"]), &found).is_empty());
+    assert!(unlisted_installs(&HookedExe::new("Condor.exe"), &found).is_empty());
+
+    assert!(validate_install_path("Condor.exe", Path::new(r"C:\Condor3\Condor.exe")).is_ok());
+    assert!(validate_install_path("Condor.exe", Path::new(r"\\server\share\CONDOR.EXE")).is_ok());
+    assert!(validate_install_path("Condor.exe", Path::new(r"Condor3\Condor.exe")).is_err());
+    assert!(validate_install_path("Condor.exe", Path::new(r"C:\Condor3\CondorClub.exe")).is_err());
+}
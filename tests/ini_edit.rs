@@ -0,0 +1,91 @@
+use condor3_revive_helper::ini_edit::{get_value, set_value};
+
+/// A trimmed-down Condor 3 Setup.ini with CRLF line endings, comments and odd spacing.
+const CRLF_SETUP: &[u8] = b"; Condor settings\r\n\
+[General]\r\n\
+Language = English\r\n\
+\r\n\
+[Graphics]\r\n\
+ResolutionX=2560\r\n\
+VROculusRift = 0  \r\n\
+; keep me\r\n\
+FullScreen=1\r\n\
+\r\n\
+[Sound]\r\n\
+Volume=80\r\n";
+
+#[test]
+fn replaces_only_the_value_on_crlf_files() {
+    let out = set_value(CRLF_SETUP, "Graphics", "VROculusRift", "1");
+    let expected = String::from_utf8_lossy(CRLF_SETUP).replace("VROculusRift = 0  ", "VROculusRift = 1  ");
+    assert_eq!(out, expected.as_bytes());
+    assert_eq!(get_value(&out, "graphics", "vroculusrift").as_deref(), Some("1"));
+}
+
+#[test]
+fn setting_the_current_value_is_a_no_op() {
+    let out = set_value(CRLF_SETUP, "Graphics", "VROculusRift", "0");
+    assert_eq!(out, CRLF_SETUP);
+}
+
+#[test]
+fn inserts_missing_key_at_end_of_section() {
+    let input = b"[Graphics]\r\nResolutionX=2560\r\n\r\n[Sound]\r\nVolume=80\r\n";
+    let out = set_value(input, "Graphics", "VROculusRift", "1");
+    assert_eq!(
+        out,
+        b"[Graphics]\r\nResolutionX=2560\r\nVROculusRift=1\r\n\r\n[Sound]\r\nVolume=80\r\n"
+    );
+}
+
+#[test]
+fn inserts_missing_key_into_last_section_without_trailing_newline() {
+    let input = b"[Graphics]\nResolutionX=2560";
+    let out = set_value(input, "Graphics", "VROculusRift", "1");
+    assert_eq!(out, b"[Graphics]\nResolutionX=2560\nVROculusRift=1");
+}
+
+#[test]
+fn appends_missing_section() {
+    let input = b"[Sound]\r\nVolume=80";
+    let out = set_value(input, "Graphics", "VROculusRift", "1");
+    assert_eq!(out, b"[Sound]\r\nVolume=80\r\n[Graphics]\r\nVROculusRift=1\r\n");
+}
+
+#[test]
+fn ignores_keys_in_other_sections_and_comments() {
+    let input = b"[Sound]\r\nVROculusRift=0\r\n[Graphics]\r\n;VROculusRift=0\r\n";
+    let out = set_value(input, "Graphics", "VROculusRift", "1");
+    assert_eq!(
+        out,
+        b"[Sound]\r\nVROculusRift=0\r\n[Graphics]\r\n;VROculusRift=0\r\nVROculusRift=1\r\n"
+    );
+}
+
+#[test]
+fn preserves_utf8_bom() {
+    let input = b"\xEF\xBB\xBF[Graphics]\r\nVROculusRift=0\r\n";
+    let out = set_value(input, "Graphics", "VROculusRift", "1");
+    assert_eq!(out, b"\xEF\xBB\xBF[Graphics]\r\nVROculusRift=1\r\n");
+}
+
+#[test]
+fn preserves_windows_1252_text() {
+    // "Zoë Müller" in Windows-1252, which is not valid UTF-8.
+    let input = b"[Pilot]\r\nName=Zo\xEB M\xFCller\r\n[Graphics]\r\nVROculusRift=0\r\n";
+    let out = set_value(input, "Graphics", "VROculusRift", "1");
+    assert_eq!(out, b"[Pilot]\r\nName=Zo\xEB M\xFCller\r\n[Graphics]\r\nVROculusRift=1\r\n");
+}
+
+#[test]
+fn edits_utf16le_files() {
+    let encode = |s: &str| {
+        let mut out = vec![0xFF, 0xFE];
+        s.encode_utf16().for_each(|u| out.extend_from_slice(&u.to_le_bytes()));
+        out
+    };
+    let input = encode("[Graphics]\r\nVROculusRift=0\r\n");
+    let out = set_value(&input, "Graphics", "VROculusRift", "1");
+    assert_eq!(out, encode("[Graphics]\r\nVROculusRift=1\r\n"));
+    assert_eq!(get_value(&out, "Graphics", "VROculusRift").as_deref(), Some("1"));
+}
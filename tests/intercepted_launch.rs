@@ -0,0 +1,203 @@
+use std::path::PathBuf;
+
+use condor3_revive_helper::command_line::{join_args, split_command_line};
+use condor3_revive_helper::{InterceptedLaunch, LaunchParseError};
+
+const LAUNCHER: &str = r#""C:\Program Files\CondorVR\CondorVR.exe""#;
+
+fn parse(rest: &str) -> Result<InterceptedLaunch, LaunchParseError> {
+    InterceptedLaunch::parse(&format!("{} {}", LAUNCHER, rest))
+}
+
+fn launch(target: &str, args: &[&str]) -> Result<InterceptedLaunch, LaunchParseError> {
+    Ok(InterceptedLaunch {
+        target: PathBuf::from(target),
+        args: args.iter().map(|a| a.to_string()).collect(),
+    })
+}
+
+fn split(command_line: &str) -> Vec<String> {
+    split_command_line(command_line).into_iter().map(|a| a.value).collect()
+}
+
+#[test]
+fn quoted_target_with_spaces() {
+    assert_eq!(
+        parse(r#""C:\Program Files\Condor3\Condor.exe""#),
+        launch(r"C:\Program Files\Condor3\Condor.exe", &[])
+    );
+}
+
+#[test]
+fn unquoted_target_without_spaces() {
+    assert_eq!(parse(r"C:\Condor3\Condor.exe"), launch(r"C:\Condor3\Condor.exe", &[]));
+}
+
+#[test]
+fn unquoted_target_with_spaces_is_rejoined() {
+    assert_eq!(
+        parse(r"C:\Program Files\Condor3\Condor.exe -flight"),
+        launch(r"C:\Program Files\Condor3\Condor.exe", &["-flight"])
+    );
+}
+
+#[test]
+fn rejoined_target_keeps_its_original_spacing() {
+    assert_eq!(
+        parse("D:\\My  Games\\Condor3\\Condor.exe\t-x"),
+        launch(r"D:\My  Games\Condor3\Condor.exe", &["-x"])
+    );
+}
+
+#[test]
+fn target_without_extension_is_taken_as_is() {
+    assert_eq!(parse("Condor -flight"), launch("Condor", &["-flight"]));
+}
+
+#[test]
+fn extension_is_matched_case_insensitively() {
+    assert_eq!(parse(r"C:\Condor 3\CONDOR.EXE"), launch(r"C:\Condor 3\CONDOR.EXE", &[]));
+}
+
+#[test]
+fn server_list_arguments_are_passed_through() {
+    assert_eq!(
+        parse(r#""C:\Condor3\Condor.exe" -hostname "My Server" -port 56278 -password s3cr3t"#),
+        launch(
+            r"C:\Condor3\Condor.exe",
+            &["-hostname", "My Server", "-port", "56278", "-password", "s3cr3t"]
+        )
+    );
+}
+
+#[test]
+fn flight_plan_path_with_spaces() {
+    assert_eq!(
+        parse(r#""C:\Condor3\Condor.exe" "/fpl:C:\Users\Zoe Smith\Documents\Condor3\FlightPlans\Task 1.fpl""#),
+        launch(
+            r"C:\Condor3\Condor.exe",
+            &[r"/fpl:C:\Users\Zoe Smith\Documents\Condor3\FlightPlans\Task 1.fpl"]
+        )
+    );
+}
+
+#[test]
+fn quote_in_the_middle_of_an_argument() {
+    assert_eq!(
+        parse(r#"C:\Condor3\Condor.exe /name:"Zoe Smith""#),
+        launch(r"C:\Condor3\Condor.exe", &["/name:Zoe Smith"])
+    );
+}
+
+#[test]
+fn escaped_quotes_and_backslashes() {
+    assert_eq!(
+        parse(r#"C:\Condor3\Condor.exe a\"b c\\"d e" f\\\"g h\i"#),
+        launch(r"C:\Condor3\Condor.exe", &[r#"a"b"#, r"c\d e", r#"f\"g"#, r"h\i"])
+    );
+}
+
+#[test]
+fn trailing_backslash_before_closing_quote() {
+    assert_eq!(
+        parse(r#"C:\Condor3\Condor.exe "C:\Data Dir\\" next"#),
+        launch(r"C:\Condor3\Condor.exe", &[r"C:\Data Dir\", "next"])
+    );
+}
+
+#[test]
+fn doubled_quote_inside_quotes_is_literal() {
+    assert_eq!(
+        parse(r#"C:\Condor3\Condor.exe "say ""hi"" now""#),
+        launch(r"C:\Condor3\Condor.exe", &[r#"say "hi" now"#])
+    );
+}
+
+#[test]
+fn empty_quoted_argument_is_kept() {
+    assert_eq!(
+        parse(r#"C:\Condor3\Condor.exe "" -x"#),
+        launch(r"C:\Condor3\Condor.exe", &["", "-x"])
+    );
+}
+
+#[test]
+fn unterminated_quote_runs_to_the_end() {
+    assert_eq!(
+        parse(r#"C:\Condor3\Condor.exe "open ended"#),
+        launch(r"C:\Condor3\Condor.exe", &["open ended"])
+    );
+}
+
+#[test]
+fn quoted_argument_stops_rejoining() {
+    // Only unquoted pieces can belong to an unquoted target.
+    assert_eq!(
+        parse(r#"C:\Condor3\Condor "x.exe""#),
+        launch(r"C:\Condor3\Condor", &["x.exe"])
+    );
+}
+
+#[test]
+fn non_ascii_paths() {
+    assert_eq!(
+        parse(r#""C:\Users\Zoë Müller\Condor3\Condor.exe" -pilot "Zoë""#),
+        launch(r"C:\Users\Zoë Müller\Condor3\Condor.exe", &["-pilot", "Zoë"])
+    );
+}
+
+#[test]
+fn unquoted_launcher_path() {
+    assert_eq!(
+        InterceptedLaunch::parse(r"C:\CondorVR\CondorVR.exe C:\Condor3\Condor.exe"),
+        launch(r"C:\Condor3\Condor.exe", &[])
+    );
+}
+
+#[test]
+fn launcher_name_backslashes_are_not_escapes() {
+    assert_eq!(
+        InterceptedLaunch::parse(r#""C:\CondorVR\"C:\Condor3\Condor.exe"#),
+        launch(r"C:\Condor3\Condor.exe", &[])
+    );
+}
+
+#[test]
+fn run_by_hand_without_arguments() {
+    assert_eq!(InterceptedLaunch::parse(LAUNCHER), Err(LaunchParseError::NoTarget));
+    assert_eq!(parse("   \t "), Err(LaunchParseError::NoTarget));
+    assert_eq!(InterceptedLaunch::parse(""), Err(LaunchParseError::NoTarget));
+}
+
+#[test]
+fn run_by_hand_with_an_option() {
+    assert_eq!(parse("--help"), Err(LaunchParseError::UnexpectedOption("--help".into())));
+    assert_eq!(parse("/?"), Err(LaunchParseError::UnexpectedOption("/?".into())));
+}
+
+#[test]
+fn empty_target() {
+    assert_eq!(parse(r#""" -x"#), Err(LaunchParseError::EmptyTarget));
+}
+
+#[test]
+fn refuses_to_launch_itself() {
+    assert_eq!(
+        parse(r"C:\CondorVR\condorvr.EXE C:\Condor3\Condor.exe"),
+        Err(LaunchParseError::TargetIsLauncher(PathBuf::from(r"C:\CondorVR\condorvr.EXE")))
+    );
+}
+
+#[test]
+fn errors_explain_themselves() {
+    assert_eq!(
+        LaunchParseError::UnexpectedOption("--help".into()).to_string(),
+        "expected a program to launch, found option '--help'"
+    );
+}
+
+#[test]
+fn quoted_arguments_round_trip() {
+    let args = ["Condor.exe", "plain", "with space", r"trailing\", r#"has "quotes""#, "", r"C:\a\\b"];
+    assert_eq!(split(&join_args(&args)), args);
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::Path;
+
+use condor3_revive_helper::command_line::{join_args, split_args};
+use condor3_revive_helper::plan::plan_setup_ini;
+use condor3_revive_helper::{apply_setup_ini_changes, read_vr_enabled, InvokingUser};
+
+fn write_setup(path: &Path, vr: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("[Graphics]\r\nVROculusRift={}\r\n", vr)).unwrap();
+}
+
+fn pilot_on(machine: &Path) -> InvokingUser {
+    InvokingUser {
+        name: r"PC\Zoe Smith".to_string(),
+        documents: Some(machine.join("Users/Zoe Smith/OneDrive/Documents")),
+        settings_path: Some(machine.join("Users/Zoe Smith/AppData/Roaming/CondorVR/settings.ini")),
+        backups_root: machine.join("Users/Zoe Smith/AppData/Local/CondorVR/Backups"),
+    }
+}
+
+#[test]
+fn elevated_configurer_changes_the_invoking_users_documents() {
+    let machine = tempfile::tempdir().unwrap();
+    let admin_setup = machine.path().join("Users/Admin/Documents/Condor3/Setup.ini");
+    let pilot_setup = machine.path().join("Users/Zoe Smith/OneDrive/Documents/Condor3/Pilots/Zoe/Setup.ini");
+    write_setup(&admin_setup, "0");
+    write_setup(&pilot_setup, "0");
+
+    // The parameters the GUI, running as the pilot, passes through the elevation prompt.
+    let pilot = pilot_on(machine.path());
+    let mut params = vec!["activate".to_string()];
+    params.extend(pilot.to_args());
+    let command_line = join_args(&params);
+
+    // The configurer, elevated as the administrator, only sees its arguments.
+    let (user, rest) = InvokingUser::from_args(&split_args(&command_line)).unwrap();
+    let user = user.unwrap();
+    assert_eq!(user, pilot);
+    assert_eq!(rest, ["activate"]);
+
+    let mut settings = user.load_settings();
+    let plan = plan_setup_ini(&user.profiles(&settings), &settings, true);
+    let results = apply_setup_ini_changes(&plan, &mut settings, &user.backups());
+    user.save_settings(&settings).unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_ok());
+    assert!(read_vr_enabled(&pilot_setup));
+    assert!(!read_vr_enabled(&admin_setup));
+    assert_eq!(pilot.backups().list(&results[0].file).len(), 1);
+    assert!(pilot.settings_path.as_deref().unwrap().is_file());
+}
+
+#[test]
+fn without_user_arguments_the_current_user_is_used() {
+    let args = vec!["deactivate".to_string(), "--dry-run".to_string()];
+    let (user, rest) = InvokingUser::from_args(&args).unwrap();
+    assert!(user.is_none());
+    assert_eq!(rest, args);
+}
+
+#[test]
+fn user_argument_without_value_is_an_error() {
+    assert!(InvokingUser::from_args(&["activate".to_string(), "--documents".to_string()]).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn folders_reached_through_a_link_are_refused() {
+    let machine = tempfile::tempdir().unwrap();
+    let pilot = pilot_on(machine.path());
+    let profile = machine.path().join("Users/Zoe Smith");
+    fs::create_dir_all(machine.path().join("Windows/System32")).unwrap();
+    fs::create_dir_all(profile.parent().unwrap()).unwrap();
+    std::os::unix::fs::symlink(machine.path().join("Windows/System32"), &profile).unwrap();
+
+    let err = InvokingUser::from_args(&pilot.to_args()).unwrap_err();
+    let refused = err.get_ref().and_then(|e| e.downcast_ref::<condor3_revive_helper::atomic_write::LinkRefused>()).unwrap();
+    assert_eq!(refused.path, profile);
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use condor3_revive_helper::{GraphicsMode, LaunchChooser, UserSettings};
+
+const SECOND: Duration = Duration::from_secs(1);
+
+#[test]
+fn default_is_picked_when_the_timeout_runs_out() {
+    let chooser = LaunchChooser::new(GraphicsMode::Vr, 3 * SECOND);
+    assert_eq!(chooser.decision(Duration::ZERO), None);
+    assert_eq!(chooser.seconds_left(Duration::ZERO), 3);
+    assert_eq!(chooser.seconds_left(SECOND / 2), 3);
+    assert_eq!(chooser.seconds_left(2 * SECOND), 1);
+    assert_eq!(chooser.decision(3 * SECOND - Duration::from_millis(1)), None);
+    assert_eq!(chooser.decision(3 * SECOND), Some(GraphicsMode::Vr));
+    assert_eq!(chooser.seconds_left(5 * SECOND), 0);
+}
+
+#[test]
+fn first_choice_wins_over_the_default() {
+    let mut chooser = LaunchChooser::new(GraphicsMode::Vr, 3 * SECOND);
+    chooser.choose(GraphicsMode::Flatscreen);
+    chooser.choose(GraphicsMode::Vr);
+    assert_eq!(chooser.decision(Duration::ZERO), Some(GraphicsMode::Flatscreen));
+    assert_eq!(chooser.decision(10 * SECOND), Some(GraphicsMode::Flatscreen));
+}
+
+#[test]
+fn zero_timeout_launches_the_default_straight_away() {
+    let mut settings = UserSettings::default();
+    settings.set_launch_choice_timeout(0);
+    settings.set_default_launch_mode(GraphicsMode::Flatscreen);
+    let chooser = LaunchChooser::for_exe(&settings, "Condor.exe");
+    assert_eq!(chooser.decision(Duration::ZERO), Some(GraphicsMode::Flatscreen));
+}
+
+#[test]
+fn remembered_choice_is_per_executable() {
+    let mut settings = UserSettings::default();
+    settings.set_last_launch_mode("Condor.exe", GraphicsMode::Flatscreen);
+
+    // Only used once remembering is turned on.
+    assert_eq!(LaunchChooser::for_exe(&settings, "Condor.exe").default, GraphicsMode::Vr);
+    settings.set_remember_launch_mode(true);
+    assert_eq!(LaunchChooser::for_exe(&settings, "CONDOR.EXE").default, GraphicsMode::Flatscreen);
+    assert_eq!(LaunchChooser::for_exe(&settings, "Condor2.exe").default, GraphicsMode::Vr);
+}
+
+#[test]
+fn launcher_options_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("settings.ini");
+
+    let defaults = UserSettings::default();
+    assert_eq!(defaults.launch_choice_timeout(), 3);
+    assert_eq!(defaults.default_launch_mode(), GraphicsMode::Vr);
+    assert!(!defaults.remember_launch_mode());
+    assert!(defaults.flatscreen_disables_vr());
+
+    let mut settings = UserSettings::default();
+    settings.set_launch_choice_timeout(10);
+    settings.set_default_launch_mode(GraphicsMode::Flatscreen);
+    settings.set_remember_launch_mode(true);
+    settings.set_last_launch_mode("Condor.exe", GraphicsMode::Vr);
+    settings.set_flatscreen_disables_vr(false);
+    settings.save(&path).unwrap();
+    assert_eq!(UserSettings::load(&path), settings);
+}
@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use condor3_revive_helper::launch_history::LaunchOutcome;
+use condor3_revive_helper::process_watch::ProcessRun;
+use condor3_revive_helper::{GraphicsMode, InterceptedLaunch, LaunchHistory, LaunchRecord};
+
+fn record(started_at_secs: u64) -> LaunchRecord {
+    let launch = InterceptedLaunch {
+        target: PathBuf::from(r"C:\Condor3\Condor.exe"),
+        args: vec!["-flightplan".to_string(), r"C:\Flights\Ridge run.fpl".to_string()],
+    };
+    LaunchRecord::new(&launch, UNIX_EPOCH + Duration::from_secs(started_at_secs))
+}
+
+#[test]
+fn records_round_trip_newest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = LaunchHistory::new(dir.path().join("CondorVR").join("launches.jsonl"), 10);
+    assert!(history.load().is_empty());
+
+    let mut first = record(1_000);
+    first.mode = Some(GraphicsMode::Vr);
+    first.injector_path = Some(PathBuf::from(r"C:\Program Files\Revive\Revive\ReviveInjector.exe"));
+    first.add_stage("Lifting the hook", Duration::from_millis(850));
+    first.add_stage("Running Revive Injector", Duration::from_millis(2150));
+    history.save(&first).unwrap();
+
+    let mut second = record(2_000);
+    second.fail("Revive Injector failed with exit code: 1");
+    history.save(&second).unwrap();
+
+    assert_eq!(history.load(), [second, first.clone()]);
+    assert_eq!(first.total_duration(), Duration::from_secs(3));
+    assert_eq!(first.exe_name(), "Condor.exe");
+    assert_eq!(first.timestamp(), "1970-01-01 00:16:40 UTC");
+}
+
+#[test]
+fn saving_again_updates_the_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = LaunchHistory::new(dir.path().join("launches.jsonl"), 10);
+
+    let mut launch = record(1_000);
+    history.save(&launch).unwrap();
+    let started = UNIX_EPOCH + Duration::from_secs(1_000);
+    let run = ProcessRun {
+        pid: 7,
+        exe_name: "Condor.exe".to_string(),
+        started,
+        exited: started + Duration::from_secs(5400),
+        exit_code: 0,
+    };
+    launch.set_run(&run);
+    history.save(&launch).unwrap();
+
+    let loaded = history.load();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].outcome, LaunchOutcome::Launched);
+    assert_eq!((loaded[0].ran_for_secs, loaded[0].exit_code), (Some(5400), Some(0)));
+
+    // A program that quits straight away turns the launch into a failure.
+    launch.set_run(&ProcessRun { exited: started + Duration::from_secs(1), exit_code: 1, ..run });
+    assert!(launch.is_failure());
+    assert!(launch.error.unwrap().contains("exited immediately"));
+}
+
+#[test]
+fn only_the_newest_records_are_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("launches.jsonl");
+    let history = LaunchHistory::new(path.clone(), 3);
+    for secs in 1..=5 {
+        history.save(&record(secs)).unwrap();
+    }
+    let kept: Vec<u64> = history.load().iter().map(|r| r.started_at).collect();
+    assert_eq!(kept, [5_000, 4_000, 3_000]);
+
+    // A damaged line doesn't hide the rest.
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, format!("{{not json\n{}", text)).unwrap();
+    assert_eq!(history.load().len(), 3);
+}
+
+#[test]
+fn trim_drops_superseded_and_old_records_from_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("launches.jsonl");
+    let history = LaunchHistory::new(path.clone(), 2);
+    let mut records: Vec<LaunchRecord> = (1..=4).map(record).collect();
+    for record in &records {
+        history.save(record).unwrap();
+    }
+    history.trim().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+
+    let mut latest = records.pop().unwrap();
+    latest.fail("Revive Injector failed with exit code: 1");
+    history.save(&latest).unwrap();
+    let before = history.load();
+    history.trim().unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+    assert_eq!(history.load(), before);
+    assert!(before[0].is_failure());
+}
+
+#[test]
+fn concurrent_saves_keep_every_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = LaunchHistory::new(dir.path().join("launches.jsonl"), 100);
+
+    // Every launcher starts in the same millisecond.
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            let history = &history;
+            s.spawn(move || {
+                for _ in 0..10 {
+                    history.save(&record(1_000)).unwrap();
+                }
+            });
+        }
+    });
+
+    assert_eq!(history.load().len(), 40);
+}
+
+#[test]
+fn records_from_before_the_id_still_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("launches.jsonl");
+    std::fs::write(&path, "{\"started_at\":1000000,\"target\":\"C:\\\\Condor3\\\\Condor.exe\",\"outcome\":\"launched\"}\n").unwrap();
+    let history = LaunchHistory::new(path, 10);
+    history.save(&record(2_000)).unwrap();
+
+    let loaded = history.load();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!((loaded[1].started_at, loaded[1].id), (1_000_000, 0));
+}
+
+#[test]
+fn filter_matches_target_arguments_and_error() {
+    let mut launch = record(1_000);
+    launch.fail("Condor.exe exited immediately (exit code 1). Is SteamVR running and your headset connected?");
+
+    assert!(launch.matches(""));
+    assert!(launch.matches("condor3"));
+    assert!(launch.matches("ridge RUN"));
+    assert!(launch.matches("steamvr"));
+    assert!(!launch.matches("Condor2"));
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use condor3_revive_helper::launch_plan::CREATE_NO_WINDOW;
+use condor3_revive_helper::{InjectorSettings, InterceptedLaunch, LaunchPlan};
+
+const INJECTOR: &str = r"C:\Program Files\Revive\Revive\ReviveInjector.exe";
+
+fn server_list_launch() -> InterceptedLaunch {
+    InterceptedLaunch::parse(r#"CondorVR.exe "C:\Condor3\Condor.exe" -hostname "My Server" -port 56278"#).unwrap()
+}
+
+#[test]
+fn runs_target_through_injector_from_its_directory() {
+    let plan = LaunchPlan::for_injector(&InjectorSettings::new(INJECTOR), &server_list_launch());
+
+    assert_eq!(
+        plan,
+        LaunchPlan {
+            exe: PathBuf::from(INJECTOR),
+            args: vec![
+                r"C:\Condor3\Condor.exe".to_string(),
+                "-hostname".to_string(),
+                "My Server".to_string(),
+                "-port".to_string(),
+                "56278".to_string(),
+            ],
+            cwd: Some(PathBuf::from(r"C:\Program Files\Revive\Revive")),
+            env: BTreeMap::new(),
+            creation_flags: CREATE_NO_WINDOW,
+        }
+    );
+}
+
+#[test]
+fn injector_options_come_before_the_target_and_env_is_passed() {
+    let mut injector = InjectorSettings::new(INJECTOR);
+    injector.args = vec!["/handle".to_string(), "xr".to_string()];
+    injector.env.insert("REVIVE_LOG".to_string(), "1".to_string());
+
+    let launch = InterceptedLaunch::parse(r"CondorVR.exe C:\Condor3\Condor.exe").unwrap();
+    let plan = LaunchPlan::for_injector(&injector, &launch);
+
+    assert_eq!(plan.args, ["/handle", "xr", r"C:\Condor3\Condor.exe"]);
+    assert_eq!(plan.env.get("REVIVE_LOG").map(String::as_str), Some("1"));
+}
+
+#[test]
+fn injector_without_directory_keeps_current_directory() {
+    let launch = InterceptedLaunch::parse(r"CondorVR.exe C:\Condor3\Condor.exe").unwrap();
+    let plan = LaunchPlan::for_injector(&InjectorSettings::new("ReviveInjector.exe"), &launch);
+    assert_eq!(plan.cwd, None);
+}
+
+#[test]
+fn plan_is_logged_as_a_command_line() {
+    let mut injector = InjectorSettings::new(INJECTOR);
+    injector.env.insert("REVIVE_LOG".to_string(), "1".to_string());
+    let plan = LaunchPlan::for_injector(&injector, &server_list_launch());
+
+    assert_eq!(
+        plan.to_string(),
+        "\"C:\\Program Files\\Revive\\Revive\\ReviveInjector.exe\" C:\\Condor3\\Condor.exe -hostname \"My Server\" -port 56278\n  \
+         in C:\\Program Files\\Revive\\Revive\n  \
+         with REVIVE_LOG=1\n  \
+         creation flags 0x08000000"
+    );
+}
+
+#[test]
+fn command_matches_the_plan() {
+    let plan = LaunchPlan::for_injector(&InjectorSettings::new(INJECTOR), &server_list_launch());
+    let cmd = plan.to_command();
+
+    assert_eq!(cmd.get_program(), INJECTOR);
+    assert_eq!(cmd.get_args().count(), plan.args.len());
+    assert_eq!(cmd.get_current_dir(), plan.cwd.as_deref());
+}
+
+#[test]
+fn flatscreen_launch_runs_the_target_directly() {
+    let plan = LaunchPlan::direct(&server_list_launch());
+    assert_eq!(plan.exe, PathBuf::from(r"C:\Condor3\Condor.exe"));
+    assert_eq!(plan.args, ["-hostname", "My Server", "-port", "56278"]);
+    assert_eq!(plan.cwd, Some(PathBuf::from(r"C:\Condor3")));
+    assert!(plan.env.is_empty());
+    assert_eq!(plan.creation_flags, 0);
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use condor3_revive_helper::logging::{read_records, rotated_path};
+use condor3_revive_helper::{Component, LogLevel, Logger};
+
+fn messages(path: &Path) -> Vec<String> {
+    read_records(path).into_iter().map(|r| r.message).collect()
+}
+
+#[test]
+fn log_level_allows_more_severe_messages() {
+    assert!(LogLevel::Info.allows(LogLevel::Warn));
+    assert!(!LogLevel::Warn.allows(LogLevel::Info));
+    assert!(!LogLevel::Off.allows(LogLevel::Error));
+    assert!(!LogLevel::Debug.allows(LogLevel::Off));
+}
+
+#[test]
+fn messages_are_written_as_json_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("launcher.jsonl");
+    let logger = Logger::with_path(Component::Launcher, Some(path.clone()), 1024 * 1024, 3);
+
+    logger.info("Launching Condor.exe");
+    logger.debug("Not written at the default level");
+    logger.error("Revive Injector failed");
+
+    let records = read_records(&path);
+    assert_eq!(records.len(), 2);
+    assert_eq!((records[0].level, records[0].component), (LogLevel::Info, Component::Launcher));
+    assert_eq!(records[0].message, "Launching Condor.exe");
+    assert_eq!(records[0].pid, std::process::id());
+    assert_eq!(records[1].level, LogLevel::Error);
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    let first: serde_json::Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+    assert_eq!(first["level"], "info");
+    assert_eq!(first["component"], "launcher");
+}
+
+#[test]
+fn level_can_be_changed_after_start() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("service.jsonl");
+    let logger = Logger::with_path(Component::Service, Some(path.clone()), 1024 * 1024, 3);
+
+    logger.set_level(LogLevel::Warn);
+    logger.info("Hidden");
+    logger.warn("Shown");
+    logger.set_level(LogLevel::Off);
+    logger.error("Hidden too");
+
+    assert_eq!(messages(&path), ["Shown"]);
+    assert_eq!(logger.level(), LogLevel::Off);
+}
+
+#[test]
+fn full_files_are_rotated_and_old_ones_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("configurer.jsonl");
+    // Small enough that every message starts a new file.
+    let logger = Logger::with_path(Component::Configurer, Some(path.clone()), 10, 2);
+    for n in 1..=4 {
+        logger.info(&format!("message {}", n));
+    }
+
+    assert_eq!(rotated_path(&path, 1), dir.path().join("configurer.1.jsonl"));
+    assert_eq!(messages(&path), ["message 4"]);
+    assert_eq!(messages(&rotated_path(&path, 1)), ["message 3"]);
+    assert_eq!(messages(&rotated_path(&path, 2)), ["message 2"]);
+    assert!(!rotated_path(&path, 3).exists());
+}
+
+#[test]
+fn unreadable_lines_are_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("gui.jsonl");
+    assert!(read_records(&path).is_empty());
+
+    let logger = Logger::with_path(Component::Gui, Some(path.clone()), 1024 * 1024, 3);
+    logger.info("first");
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, format!("[2026-10-17] old text line\n{}{{\"truncated\n", text)).unwrap();
+
+    let records = read_records(&path);
+    assert_eq!(records.len(), 1);
+    let line = records[0].to_string();
+    assert!(line.ends_with(&format!(" INFO  gui[{}]: first", std::process::id())), "{}", line);
+}
+
+#[test]
+fn only_elevated_programs_log_to_the_shared_folder() {
+    assert!(Component::Service.is_elevated());
+    assert!(Component::Configurer.is_elevated());
+    assert!(!Component::Launcher.is_elevated());
+    assert!(!Component::Gui.is_elevated());
+
+    let launcher = Logger::default_path(Component::Launcher);
+    assert_eq!(launcher.file_name().unwrap(), "launcher.jsonl");
+    assert_eq!(launcher.parent().unwrap().file_name().unwrap(), "Logs");
+}
+
+#[test]
+fn the_log_folder_is_created_on_first_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("CondorVR/Logs/gui.jsonl");
+    let logger = Logger::with_path(Component::Gui, Some(path.clone()), 1024 * 1024, 3);
+
+    logger.info("Started");
+
+    assert_eq!(messages(&path), ["Started"]);
+}
@@ -0,0 +1,69 @@
+use std::fs;
+use std::io;
+
+use condor3_revive_helper::atomic_write::LinkRefused;
+use condor3_revive_helper::outcome::SetupIniError;
+use condor3_revive_helper::plan::plan_setup_file;
+use condor3_revive_helper::{apply_setup_ini_changes, BackupStore, CondorVersion, SetupFile, UserSettings};
+
+#[test]
+fn io_errors_map_to_setup_ini_errors() {
+    let from = |e: io::Error| SetupIniError::from_io(&e);
+
+    assert_eq!(from(io::ErrorKind::NotFound.into()), SetupIniError::NotFound);
+    assert_eq!(from(io::ErrorKind::PermissionDenied.into()), SetupIniError::PermissionDenied);
+    assert_eq!(
+        from(io::Error::new(io::ErrorKind::InvalidInput, LinkRefused { path: "Setup.ini".into() })),
+        SetupIniError::ReparsePointRefused
+    );
+    assert_eq!(from(io::Error::other("disk full")), SetupIniError::Other("disk full".to_string()));
+}
+
+#[cfg(windows)]
+#[test]
+fn sharing_and_lock_violations_mean_condor_has_the_file_open() {
+    assert_eq!(SetupIniError::from_io(&io::Error::from_raw_os_error(32)), SetupIniError::LockedByCondor);
+    assert_eq!(SetupIniError::from_io(&io::Error::from_raw_os_error(33)), SetupIniError::LockedByCondor);
+    // ERROR_ACCESS_DENIED still reads as a permission problem.
+    assert_eq!(SetupIniError::from_io(&io::Error::from_raw_os_error(5)), SetupIniError::PermissionDenied);
+}
+
+#[test]
+fn the_previous_value_is_read_when_the_change_is_applied() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Condor3/Setup.ini");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "[Graphics]\r\nVROculusRift=0\r\n").unwrap();
+    let file = SetupFile { data_dir: "Condor3".into(), version: CondorVersion::Condor3, pilot: None, path: path.clone() };
+
+    let mut settings = UserSettings::default();
+    let plan = vec![plan_setup_file(file, &settings, true)];
+    assert_eq!(plan[0].before.as_deref(), Some("0"));
+
+    // Something else changes the file between planning and applying.
+    fs::write(&path, "[Graphics]\r\nVROculusRift=2\r\n").unwrap();
+    let results = apply_setup_ini_changes(&plan, &mut settings, &BackupStore::new(dir.path().join("Backups"), 10));
+
+    assert!(results[0].is_ok());
+    assert_eq!(results[0].previous.as_deref(), Some("2"));
+    assert_eq!(results[0].new, "1");
+}
+
+#[test]
+fn a_missing_file_is_reported_as_not_found() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Condor3/Pilots/Gone/Setup.ini");
+    let file = SetupFile {
+        data_dir: "Condor3".into(),
+        version: CondorVersion::Condor3,
+        pilot: Some("Gone".into()),
+        path,
+    };
+
+    let mut settings = UserSettings::default();
+    let plan = vec![plan_setup_file(file, &settings, true)];
+    let results = apply_setup_ini_changes(&plan, &mut settings, &BackupStore::new(dir.path().join("Backups"), 10));
+
+    assert_eq!(results[0].error, Some(SetupIniError::NotFound));
+    assert_eq!(results[0].previous, None);
+}
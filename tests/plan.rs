@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::Path;
+
+use condor3_revive_helper::config::{LEGACY_INJECTOR_VALUE, ServiceMode};
+use condor3_revive_helper::hooked_exe::HookedExe;
+use condor3_revive_helper::ifeo::{self, MemoryRegistry, Registry};
+use condor3_revive_helper::plan::{plan_setup_ini, RegistryChange, ServiceChange, SERVICE_SDDL};
+use condor3_revive_helper::{read_vr_enabled, ActivationPlan, CondorProfileSet, UserSettings, SETTINGS_PATH};
+
+const LAUNCHER: &str = r"C:\Program Files\CondorVR\CondorVR.exe";
+const REVIVE: &str = r"C:\Program Files\Revive\ReviveInjector.exe";
+
+fn exes() -> Vec<HookedExe> {
+    vec![HookedExe::new("Condor.exe"), HookedExe::new("CondorClub.exe")]
+}
+
+fn activate(registry: &MemoryRegistry) -> ActivationPlan {
+    let service = ServiceChange::install_or_update(Path::new(r"C:\Program Files\CondorVR\Service.exe"), false, ServiceMode::Demand);
+    ActivationPlan::activate(registry, Path::new(LAUNCHER), service, &exes(), Vec::new())
+}
+
+#[test]
+fn activation_installs_the_service_then_hooks_every_exe() {
+    let mut registry = MemoryRegistry::new();
+    let plan = activate(&registry);
+
+    assert!(matches!(plan.service, ServiceChange::Install { mode: ServiceMode::Demand, .. }));
+    assert_eq!(plan.service_sddl.as_deref(), Some(SERVICE_SDDL));
+    assert_eq!(plan.registry.len(), 2);
+    assert!(plan.registry[0].to_string().contains(r"\Condor.exe\Debugger"));
+    assert!(plan.registry[1].to_string().contains(r"\CondorClub.exe\Debugger"));
+
+    ifeo::apply_all(&mut registry, &plan.registry).unwrap();
+    assert!(exes().iter().all(|exe| ifeo::is_hooked(&registry, &exe.name)));
+
+    let text = plan.to_string();
+    assert!(text.find("Service:").unwrap() < text.find("Registry:").unwrap());
+}
+
+#[test]
+fn activation_drops_the_injector_path_older_versions_kept_in_the_registry() {
+    let mut registry = MemoryRegistry::new();
+    let old = RegistryChange::Set {
+        key: SETTINGS_PATH.to_string(),
+        name: LEGACY_INJECTOR_VALUE.to_string(),
+        value: REVIVE.to_string(),
+    };
+    registry.apply(&old).unwrap();
+
+    let plan = activate(&registry);
+    assert_eq!(
+        plan.registry.last(),
+        Some(&RegistryChange::Delete { key: SETTINGS_PATH.to_string(), name: LEGACY_INJECTOR_VALUE.to_string() })
+    );
+    ifeo::apply_all(&mut registry, &plan.registry).unwrap();
+    assert_eq!(registry.string(SETTINGS_PATH, LEGACY_INJECTOR_VALUE), None);
+}
+
+#[test]
+fn an_installed_service_is_updated_rather_than_reinstalled() {
+    let path = Path::new(r"C:\Program Files\CondorVR\Service.exe");
+    assert!(matches!(
+        ServiceChange::install_or_update(path, true, ServiceMode::Persistent),
+        ServiceChange::Update { mode: ServiceMode::Persistent, .. }
+    ));
+}
+
+#[test]
+fn deactivation_unhooks_before_uninstalling_the_service() {
+    let mut registry = MemoryRegistry::new();
+    let hooks = activate(&registry).registry;
+    ifeo::apply_all(&mut registry, &hooks).unwrap();
+
+    let plan = ActivationPlan::deactivate(&registry, &exes(), Vec::new());
+    assert_eq!(plan.service, ServiceChange::Uninstall);
+    assert_eq!(plan.service_sddl, None);
+
+    ifeo::apply_all(&mut registry, &plan.registry).unwrap();
+    assert!(exes().iter().all(|exe| !ifeo::is_hooked(&registry, &exe.name)));
+
+    let text = plan.to_string();
+    assert!(text.find("Registry:").unwrap() < text.find("Service:").unwrap());
+}
+
+#[test]
+fn setup_ini_changes_are_planned_without_writing() {
+    let docs = tempfile::tempdir().unwrap();
+    let global = docs.path().join("Condor3/Setup.ini");
+    let pilot = docs.path().join("Condor3/Pilots/Zoe/Setup.ini");
+    for path in [&global, &pilot] {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "[Graphics]\r\nVROculusRift=0\r\n").unwrap();
+    }
+
+    let changes = plan_setup_ini(&CondorProfileSet::discover(docs.path()), &UserSettings::default(), true);
+
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].file.path, global);
+    assert_eq!(changes[1].file.path, pilot);
+    assert!(changes.iter().all(|c| c.before.as_deref() == Some("0") && c.after == "1"));
+    assert!(!read_vr_enabled(&global) && !read_vr_enabled(&pilot));
+
+    // The dry run lists each file with its before and after value, after the registry.
+    let service = ServiceChange::install_or_update(Path::new(r"C:\Program Files\CondorVR\Service.exe"), true, ServiceMode::Demand);
+    let plan = ActivationPlan::activate(&MemoryRegistry::new(), Path::new(LAUNCHER), service, &exes(), changes);
+    let text = plan.to_string();
+    let section = &text[text.find("Setup.ini files:").unwrap()..];
+    assert!(text.find("Registry:").unwrap() < text.find("Setup.ini files:").unwrap());
+    assert_eq!(section.matches("VROculusRift 0 -> 1").count(), 2, "{}", section);
+    assert!(section.contains(&pilot.display().to_string()));
+}
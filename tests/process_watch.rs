@@ -0,0 +1,54 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use condor3_revive_helper::process_watch::{
+    EARLY_EXIT, ProcessEnding, ProcessEntry, ProcessRun, find_started_process, from_filetime,
+};
+
+fn process(pid: u32, parent_pid: u32, exe_name: &str) -> ProcessEntry {
+    ProcessEntry { pid, parent_pid, exe_name: exe_name.to_string() }
+}
+
+fn run(lifetime: Duration, exit_code: u32) -> ProcessRun {
+    let started = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    ProcessRun { pid: 42, exe_name: "Condor.exe".to_string(), started, exited: started + lifetime, exit_code }
+}
+
+#[test]
+fn started_process_is_the_injectors_child() {
+    let processes = [
+        process(10, 1, "Condor.exe"),
+        process(20, 7, "ReviveInjector.exe"),
+        process(30, 20, "condor.EXE"),
+        process(40, 20, "conhost.exe"),
+    ];
+    assert_eq!(find_started_process(&processes, 20, "Condor.exe"), Some(30));
+
+    // Without a child of the injector, only an unambiguous match will do.
+    assert_eq!(find_started_process(&processes, 99, "Condor.exe"), None);
+    assert_eq!(find_started_process(&processes[..2], 99, "Condor.exe"), Some(10));
+    assert_eq!(find_started_process(&processes, 20, "Condor2.exe"), None);
+}
+
+#[test]
+fn filetimes_convert_to_system_time() {
+    assert_eq!(from_filetime(116_444_736_000_000_000), UNIX_EPOCH);
+    assert_eq!(from_filetime(116_444_736_000_000_000 + 15_000_000), UNIX_EPOCH + Duration::from_millis(1500));
+    assert!(from_filetime(0) < UNIX_EPOCH);
+}
+
+#[test]
+fn quick_exits_and_crashes_are_problems() {
+    let quick = run(Duration::from_secs(2), 1);
+    assert_eq!(quick.ending(), ProcessEnding::Immediate);
+    assert!(quick.problem().unwrap().contains("Is SteamVR running"));
+    assert_eq!(run(EARLY_EXIT - Duration::from_millis(1), 0).ending(), ProcessEnding::Immediate);
+
+    let crash = run(Duration::from_secs(600), 0xC000_0005);
+    assert_eq!(crash.ending(), ProcessEnding::Crashed);
+    assert_eq!(crash.problem().unwrap(), "Condor.exe crashed (exit code 0xC0000005).");
+
+    let flight = run(Duration::from_secs(3723), 0);
+    assert_eq!(flight.ending(), ProcessEnding::Exited);
+    assert_eq!(flight.problem(), None);
+    assert_eq!(flight.to_string(), "Condor.exe (PID 42) ran for 1:02:03 and exited with code 0");
+}
@@ -0,0 +1,190 @@
+use std::fs;
+use std::path::Path;
+
+use condor3_revive_helper::{
+    read_vr_enabled, select_vr_for, update_condor_setup_ini, BackupStore, CondorProfileSet,
+    CondorVersion, UserSettings,
+};
+
+fn write_setup(path: &Path, vr: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("[Graphics]\r\nVROculusRift={}\r\n", vr)).unwrap();
+}
+
+#[test]
+fn discovers_global_settings_and_pilots() {
+    let docs = tempfile::tempdir().unwrap();
+    write_setup(&docs.path().join("Condor3/Setup.ini"), "0");
+    write_setup(&docs.path().join("Condor3/Pilots/Zoe/Setup.ini"), "0");
+    write_setup(&docs.path().join("Condor3/Pilots/Adam/Setup.ini"), "1");
+    fs::create_dir_all(docs.path().join("Condor3/Pilots/NoSetup")).unwrap();
+    write_setup(&docs.path().join("Condor2/Pilots/Old/Setup.ini"), "0");
+    write_setup(&docs.path().join("Other/Setup.ini"), "0");
+
+    let profiles = CondorProfileSet::discover(docs.path());
+
+    let labels: Vec<String> = profiles.setup_files().iter().map(|f| f.label()).collect();
+    assert_eq!(
+        labels,
+        [
+            "Pilot: Old (Condor2)",
+            "Global Settings (Condor3)",
+            "Pilot: Adam (Condor3)",
+            "Pilot: Zoe (Condor3)",
+        ]
+    );
+}
+
+#[test]
+fn detects_condor_version_of_each_data_dir() {
+    let docs = tempfile::tempdir().unwrap();
+    write_setup(&docs.path().join("Condor3/Setup.ini"), "0");
+    write_setup(&docs.path().join("Condor 2/Setup.ini"), "0");
+    write_setup(&docs.path().join("Condor/Setup.ini"), "0");
+    write_setup(&docs.path().join("CondorTools/Setup.ini"), "0");
+    write_setup(&docs.path().join("Condor_30/Setup.ini"), "0");
+    fs::create_dir_all(docs.path().join("CondorBeta/Pilots/Zoe")).unwrap();
+    fs::write(docs.path().join("CondorBeta/Pilots/Zoe/Setup.ini"), "[Graphics]\r\nUpscaling=1\r\n").unwrap();
+    fs::create_dir_all(docs.path().join("Condor Old")).unwrap();
+    fs::write(docs.path().join("Condor Old/Setup.ini"), "[Graphics]\r\nShaderModel=3\r\n").unwrap();
+
+    let profiles = CondorProfileSet::discover(docs.path());
+
+    let versions: Vec<(&str, CondorVersion)> =
+        profiles.data_dirs.iter().map(|d| (d.name.as_str(), d.version)).collect();
+    assert_eq!(
+        versions,
+        [
+            ("Condor", CondorVersion::Condor2),
+            ("Condor 2", CondorVersion::Condor2),
+            ("Condor Old", CondorVersion::Condor2),
+            ("Condor3", CondorVersion::Condor3),
+            ("CondorBeta", CondorVersion::Condor3),
+            ("CondorTools", CondorVersion::Unknown),
+            ("Condor_30", CondorVersion::Unknown),
+        ]
+    );
+    assert_eq!(CondorVersion::from_folder_name("Condor 3 Beta"), Some(CondorVersion::Condor3));
+    assert_eq!(CondorVersion::from_folder_name("Condor2"), Some(CondorVersion::Condor2));
+    assert_eq!(CondorVersion::from_folder_name("Condor23"), None);
+
+    // A file with both versions' keys, e.g. copied between them, doesn't settle it.
+    assert_eq!(CondorVersion::from_setup_ini(b"[Graphics]\r\nUpscaling=1\r\nShaderModel=3\r\n"), None);
+    assert_eq!(CondorVersion::from_setup_ini(b"[Graphics]\r\nVROculusRift=1\r\n"), None);
+}
+
+#[test]
+fn honours_configured_search_roots_and_data_dirs() {
+    let docs = tempfile::tempdir().unwrap();
+    write_setup(&docs.path().join("Documents/Condor3/Setup.ini"), "0");
+    write_setup(&docs.path().join("D/Games/Condor3/Pilots/Adam/Setup.ini"), "0");
+    write_setup(&docs.path().join("E/Soaring/Pilots/Zoe/Setup.ini"), "0");
+
+    let mut settings = UserSettings::default();
+    assert!(settings.add_search_root(&docs.path().join("D/Games")));
+    assert!(settings.add_data_dir(&docs.path().join("E/Soaring")));
+    assert!(!settings.add_data_dir(&docs.path().join("E/Soaring")));
+    let settings_path = docs.path().join("settings.ini");
+    settings.save(&settings_path).unwrap();
+    let settings = UserSettings::load(&settings_path);
+
+    let roots = [docs.path().join("Documents")]
+        .into_iter()
+        .chain(settings.search_roots().iter().cloned())
+        .collect::<Vec<_>>();
+    let profiles = CondorProfileSet::discover_all(&roots, settings.data_dirs());
+
+    let labels: Vec<String> = profiles.setup_files().iter().map(|f| f.label()).collect();
+    assert_eq!(
+        labels,
+        ["Global Settings (Condor3)", "Pilot: Adam (Condor3)", "Pilot: Zoe (Soaring)"]
+    );
+}
+
+#[test]
+fn missing_documents_root_is_empty() {
+    let docs = tempfile::tempdir().unwrap();
+    let profiles = CondorProfileSet::discover(&docs.path().join("missing"));
+    assert!(profiles.data_dirs.is_empty());
+    assert!(profiles.is_empty());
+}
+
+#[test]
+fn updates_every_setup_file_in_the_set() {
+    let docs = tempfile::tempdir().unwrap();
+    let global = docs.path().join("Condor3/Setup.ini");
+    let pilot = docs.path().join("Condor3/Pilots/Adam/Setup.ini");
+    write_setup(&global, "0");
+    write_setup(&pilot, "0");
+
+    let profiles = CondorProfileSet::discover(docs.path());
+    let backups = BackupStore::new(docs.path().join("Backups"), 10);
+    let results = update_condor_setup_ini(&profiles, &mut UserSettings::default(), &backups, true);
+
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(results[0].previous.as_deref(), Some("0"));
+    assert_eq!(results[0].new, "1");
+    assert!(read_vr_enabled(&global));
+    assert!(read_vr_enabled(&pilot));
+}
+
+#[test]
+fn opted_out_pilots_stay_flatscreen() {
+    let docs = tempfile::tempdir().unwrap();
+    let adam = docs.path().join("Condor3/Pilots/Adam/Setup.ini");
+    let zoe = docs.path().join("Condor3/Pilots/Zoe/Setup.ini");
+    write_setup(&adam, "0");
+    write_setup(&zoe, "0");
+
+    let profiles = CondorProfileSet::discover(docs.path());
+    let zoe_file: Vec<_> = profiles
+        .setup_files()
+        .into_iter()
+        .filter(|f| f.pilot.as_deref() == Some("Zoe"))
+        .collect();
+
+    let backups = BackupStore::new(docs.path().join("Backups"), 10);
+    let mut settings = UserSettings::default();
+    select_vr_for(&mut settings, &backups, &zoe_file, false, false);
+    let settings_path = docs.path().join("settings.ini");
+    settings.save(&settings_path).unwrap();
+    let mut settings = UserSettings::load(&settings_path);
+
+    update_condor_setup_ini(&profiles, &mut settings, &backups, true);
+    assert!(read_vr_enabled(&adam));
+    assert!(!read_vr_enabled(&zoe));
+}
+
+#[test]
+fn toggling_vr_swaps_graphics_profile() {
+    let docs = tempfile::tempdir().unwrap();
+    let setup = docs.path().join("Condor3/Setup.ini");
+    fs::create_dir_all(setup.parent().unwrap()).unwrap();
+    fs::write(&setup, "[Graphics]\r\nVROculusRift=0\r\nResolution=2560x1440\r\nAA=4\r\nFPS=60\r\n").unwrap();
+
+    let profiles = CondorProfileSet::discover(docs.path());
+    let backups = BackupStore::new(docs.path().join("Backups"), 10);
+    let settings_path = docs.path().join("settings.ini");
+    let mut settings = UserSettings::default();
+    settings.set_profile_keys(vec!["Resolution".to_string(), "AA".to_string()]);
+
+    // The first switch to VR only remembers the flatscreen values; the user then tunes VR in Condor.
+    update_condor_setup_ini(&profiles, &mut settings, &backups, true);
+    let tuned = fs::read_to_string(&setup).unwrap().replace("2560x1440", "1920x1080").replace("AA=4", "AA=0");
+    fs::write(&setup, tuned).unwrap();
+
+    settings.save(&settings_path).unwrap();
+    let mut settings = UserSettings::load(&settings_path);
+    let results = update_condor_setup_ini(&profiles, &mut settings, &backups, false);
+    assert_eq!(results[0].profile.len(), 2);
+    assert_eq!(
+        fs::read_to_string(&setup).unwrap(),
+        "[Graphics]\r\nVROculusRift=0\r\nResolution=2560x1440\r\nAA=4\r\nFPS=60\r\n"
+    );
+
+    update_condor_setup_ini(&profiles, &mut settings, &backups, true);
+    assert_eq!(
+        fs::read_to_string(&setup).unwrap(),
+        "[Graphics]\r\nVROculusRift=1\r\nResolution=1920x1080\r\nAA=0\r\nFPS=60\r\n"
+    );
+}
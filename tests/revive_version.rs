@@ -0,0 +1,142 @@
+use std::fs;
+
+use condor3_revive_helper::pe_version::{file_version, FileVersion};
+use condor3_revive_helper::revive_version::{injector_version, VERSION_SIDECAR};
+use condor3_revive_helper::Compatibility;
+
+const SECTION_RVA: u32 = 0x1000;
+const SECTION_OFFSET: usize = 0x200;
+
+fn put_u16(image: &mut [u8], offset: usize, value: u16) {
+    image[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(image: &mut [u8], offset: usize, value: u32) {
+    image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// A `VS_VERSIONINFO` block holding just the fixed file info.
+fn version_info(version: [u16; 4]) -> Vec<u8> {
+    let mut info = vec![0u8; 6];
+    info.extend("VS_VERSION_INFO\0".encode_utf16().flat_map(u16::to_le_bytes));
+    while !info.len().is_multiple_of(4) {
+        info.push(0);
+    }
+    let fixed = info.len();
+    info.resize(fixed + 52, 0);
+    put_u32(&mut info, fixed, 0xFEEF_04BD);
+    put_u32(&mut info, fixed + 4, 0x0001_0000);
+    put_u32(&mut info, fixed + 8, (u32::from(version[0]) << 16) | u32::from(version[1]));
+    put_u32(&mut info, fixed + 12, (u32::from(version[2]) << 16) | u32::from(version[3]));
+    // The product version differs, to check the file version is the one read.
+    put_u32(&mut info, fixed + 16, 9 << 16);
+    let len = info.len() as u16;
+    put_u16(&mut info, 0, len);
+    put_u16(&mut info, 2, 52);
+    info
+}
+
+/// A minimal PE image with one `.rsrc` section. `resource_type` is the type ID of its only resource.
+fn pe_image(pe32_plus: bool, resource_type: u32, info: &[u8]) -> Vec<u8> {
+    // Resource tree: type directory, name directory, language directory, data entry, data.
+    let mut rsrc = vec![0u8; 88];
+    for (directory, id, target) in [(0, resource_type, 0x8000_0000 | 24), (24, 1, 0x8000_0000 | 48), (48, 0x409, 72)] {
+        put_u16(&mut rsrc, directory + 14, 1);
+        put_u32(&mut rsrc, directory + 16, id);
+        put_u32(&mut rsrc, directory + 20, target);
+    }
+    put_u32(&mut rsrc, 72, SECTION_RVA + 88);
+    put_u32(&mut rsrc, 76, info.len() as u32);
+    rsrc.extend_from_slice(info);
+
+    let (magic, optional_size, directories) = if pe32_plus { (0x20B, 240, 112) } else { (0x10B, 224, 96) };
+    let mut image = vec![0u8; SECTION_OFFSET];
+    image[..2].copy_from_slice(b"MZ");
+    put_u32(&mut image, 0x3C, 0x40);
+    image[0x40..0x44].copy_from_slice(b"PE\0\0");
+    let coff = 0x44;
+    put_u16(&mut image, coff, if pe32_plus { 0x8664 } else { 0x14C });
+    put_u16(&mut image, coff + 2, 1);
+    put_u16(&mut image, coff + 16, optional_size);
+    let optional = coff + 20;
+    put_u16(&mut image, optional, magic);
+    put_u32(&mut image, optional + directories - 4, 16);
+    put_u32(&mut image, optional + directories + 16, SECTION_RVA);
+    put_u32(&mut image, optional + directories + 20, rsrc.len() as u32);
+
+    let section = optional + optional_size as usize;
+    image[section..section + 5].copy_from_slice(b".rsrc");
+    put_u32(&mut image, section + 8, rsrc.len() as u32);
+    put_u32(&mut image, section + 12, SECTION_RVA);
+    put_u32(&mut image, section + 16, rsrc.len() as u32);
+    put_u32(&mut image, section + 20, SECTION_OFFSET as u32);
+    image.extend(rsrc);
+    image
+}
+
+#[test]
+fn reads_file_version_from_pe32_plus_and_pe32() {
+    let info = version_info([3, 2, 0, 0]);
+    assert_eq!(file_version(&pe_image(true, 16, &info)), Some(FileVersion::new(3, 2, 0, 0)));
+    assert_eq!(file_version(&pe_image(false, 16, &version_info([3, 1, 4, 7]))), Some(FileVersion::new(3, 1, 4, 7)));
+}
+
+#[test]
+fn images_without_a_version_resource_have_no_version() {
+    // An icon (RT_ICON) rather than a version resource.
+    assert_eq!(file_version(&pe_image(true, 3, &version_info([3, 2, 0, 0]))), None);
+    assert_eq!(file_version(b"#!/bin/sh\n"), None);
+    assert_eq!(file_version(&[]), None);
+
+    let mut bad_signature = version_info([3, 2, 0, 0]);
+    bad_signature[40] = 0;
+    assert_eq!(file_version(&pe_image(true, 16, &bad_signature)), None);
+}
+
+#[test]
+fn truncated_images_do_not_panic() {
+    let image = pe_image(true, 16, &version_info([3, 2, 0, 0]));
+    for len in 0..image.len() {
+        assert_eq!(file_version(&image[..len]), None);
+    }
+}
+
+#[test]
+fn parses_and_displays_versions() {
+    assert_eq!(FileVersion::parse("v3.2.0"), Some(FileVersion::new(3, 2, 0, 0)));
+    assert_eq!(FileVersion::parse(" 3.2 "), Some(FileVersion::new(3, 2, 0, 0)));
+    assert_eq!(FileVersion::parse("3.2.0.1.5"), None);
+    assert_eq!(FileVersion::parse("three"), None);
+    assert_eq!(FileVersion::new(3, 2, 0, 0).to_string(), "3.2.0");
+    assert_eq!(FileVersion::new(3, 2, 0, 1).to_string(), "3.2.0.1");
+}
+
+#[test]
+fn checks_the_supported_range() {
+    assert_eq!(Compatibility::check(FileVersion::parse("3.2.0")), Compatibility::Supported(FileVersion::new(3, 2, 0, 0)));
+    assert!(Compatibility::check(FileVersion::parse("3.9.9")).is_supported());
+
+    let old = Compatibility::check(FileVersion::parse("3.1.2"));
+    assert!(old.blocks_launch());
+    assert_eq!(old.to_string(), "Revive 3.1.2 is too old; please update to Revive 3.2.0 or later");
+
+    let new = Compatibility::check(FileVersion::parse("4.0.0"));
+    assert!(!new.blocks_launch() && !new.is_supported());
+    assert!(!Compatibility::check(None).blocks_launch());
+}
+
+#[test]
+fn falls_back_to_a_version_file_in_the_revive_folder() {
+    let dir = tempfile::tempdir().unwrap();
+    let injector = dir.path().join("Revive/x64/ReviveInjector.exe");
+    fs::create_dir_all(injector.parent().unwrap()).unwrap();
+    fs::write(&injector, b"MZ not really").unwrap();
+    assert_eq!(injector_version(&injector), None);
+
+    fs::write(dir.path().join("Revive").join(VERSION_SIDECAR), "3.2.0\r\n").unwrap();
+    assert_eq!(injector_version(&injector), Some(FileVersion::new(3, 2, 0, 0)));
+
+    // The version resource wins over the file.
+    fs::write(&injector, pe_image(true, 16, &version_info([3, 3, 0, 0]))).unwrap();
+    assert_eq!(Compatibility::of_injector(&injector), Compatibility::Supported(FileVersion::new(3, 3, 0, 0)));
+}